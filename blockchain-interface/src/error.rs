@@ -48,6 +48,8 @@ pub enum BlockchainError {
     InvalidEpoch,
     #[error("Accounts diff not found")]
     AccountsDiffNotFound,
+    #[error("Accounts state at block {0} is not retained")]
+    AccountsStateNotRetained(u32),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -2,7 +2,7 @@ use nimiq_account::{
     Account, Accounts, BlockLogger, BlockState, RevertInfo, TransactionOperationReceipt,
 };
 use nimiq_block::{Block, BlockError, SkipBlockInfo};
-use nimiq_blockchain_interface::PushError;
+use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError, PushError};
 use nimiq_database::{mdbx::MdbxReadTransaction, traits::Database};
 use nimiq_keys::Address;
 use nimiq_primitives::{
@@ -207,6 +207,85 @@ impl Blockchain {
         Ok(total_size)
     }
 
+    /// Returns the account at the given address as it was right after the block at `block_number`
+    /// was applied. Returns `None` if the accounts tree is incomplete.
+    ///
+    /// Only the current accounts state is stored, past states are looked up in the accounts diffs
+    /// recorded for each block without modifying the database. If the account was changed after
+    /// `block_number`, its previous state is taken from the latest diff up to `block_number` that
+    /// touches it, which fails if that diff is no longer retained. Since only main chain blocks
+    /// since the latest macro block are walked forward, older blocks can't be queried.
+    pub fn get_account_at(
+        &self,
+        address: &Address,
+        block_number: u32,
+    ) -> Result<Option<Account>, BlockchainError> {
+        if block_number > self.block_number() {
+            return Err(BlockchainError::BlockNotFound);
+        }
+        if block_number < self.macro_head().block_number() {
+            return Err(BlockchainError::AccountsStateNotRetained(block_number));
+        }
+
+        let txn = self.read_transaction();
+        if !self.state.accounts.is_complete(Some(&txn)) {
+            return Ok(None);
+        }
+
+        let key = KeyNibbles::from(address);
+        let not_retained = |e| match e {
+            BlockchainError::AccountsDiffNotFound => {
+                BlockchainError::AccountsStateNotRetained(block_number)
+            }
+            e => e,
+        };
+
+        // Check whether any block after `block_number` changed the account.
+        let mut block_hash = self.head_hash();
+        let mut changed = false;
+        for _ in block_number..self.block_number() {
+            let diff = self
+                .chain_store
+                .get_accounts_diff(&block_hash, Some(&txn))
+                .map_err(not_retained)?;
+            changed |= diff.0.contains_key(&key);
+            block_hash = self
+                .chain_store
+                .get_block(&block_hash, false, Some(&txn))?
+                .parent_hash()
+                .clone();
+        }
+        if !changed {
+            let account = self
+                .state
+                .accounts
+                .get(address, Some(&txn))
+                .map_err(|_| BlockchainError::InconsistentState)?;
+            return Ok(Some(account));
+        }
+
+        // Find the latest change up to `block_number`.
+        loop {
+            let diff = self
+                .chain_store
+                .get_accounts_diff(&block_hash, Some(&txn))
+                .map_err(not_retained)?;
+            if let Some(value) = diff.0.get(&key) {
+                let account = match value {
+                    Some(value) => Account::deserialize_from_vec(value)
+                        .map_err(|_| BlockchainError::InconsistentState)?,
+                    None => Account::default(),
+                };
+                return Ok(Some(account));
+            }
+            block_hash = self
+                .chain_store
+                .get_block(&block_hash, false, Some(&txn))?
+                .parent_hash()
+                .clone();
+        }
+    }
+
     /// Produces a Merkle proof of the inclusion of the given keys in the
     /// Merkle Radix Trie.
     pub fn get_accounts_proof(&self, keys: Vec<&KeyNibbles>) -> Result<TrieProof, IncompleteTrie> {
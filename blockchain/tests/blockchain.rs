@@ -2,15 +2,19 @@ use std::sync::Arc;
 
 use nimiq_block::{Block, BlockError};
 use nimiq_blockchain::Blockchain;
-use nimiq_blockchain_interface::{AbstractBlockchain, PushError, PushResult};
+use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError, PushError, PushResult};
+use nimiq_genesis::NetworkId;
 use nimiq_hash::Hash;
-use nimiq_primitives::policy::Policy;
+use nimiq_keys::{Address, KeyPair, PrivateKey};
+use nimiq_primitives::{coin::Coin, policy::Policy};
+use nimiq_serde::Deserialize;
 use nimiq_tendermint::ProposalMessage;
 use nimiq_test_log::test;
 use nimiq_test_utils::{
     block_production::TemporaryBlockProducer,
     test_custom_block::{finalize_macro_block, next_macro_block_proposal},
 };
+use nimiq_transaction_builder::TransactionBuilder;
 
 #[test]
 fn prune_epoch_micro_blocks() {
@@ -169,3 +173,62 @@ fn can_detect_invalid_punished_set() {
         Err(PushError::InvalidBlock(BlockError::InvalidValidators))
     );
 }
+
+#[test]
+fn can_get_account_at_past_block() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let key_pair: KeyPair = PrivateKey::deserialize_from_vec(
+        &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587").unwrap(),
+    )
+    .unwrap()
+    .into();
+    let recipient = Address::from([0x42; Address::SIZE]);
+    let send = |value: u64| {
+        TransactionBuilder::new_basic(
+            &key_pair,
+            recipient.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1 + Policy::genesis_block_number(),
+            NetworkId::UnitAlbatross,
+        )
+        .unwrap()
+    };
+
+    // Block 1 creates the account, block 2 leaves it untouched and block 3 changes it again.
+    temp_producer.next_block_with_txs(vec![], false, vec![send(100)]);
+    temp_producer.next_block(vec![], false);
+    temp_producer.next_block_with_txs(vec![], false, vec![send(50)]);
+
+    let blockchain = temp_producer.blockchain.read();
+    let genesis = Policy::genesis_block_number();
+    let balance_at = |block_number| {
+        blockchain
+            .get_account_at(&recipient, block_number)
+            .unwrap()
+            .unwrap()
+            .balance()
+    };
+    assert_eq!(balance_at(genesis + 1), Coin::from_u64_unchecked(100));
+    assert_eq!(balance_at(genesis + 2), Coin::from_u64_unchecked(100));
+    assert_eq!(balance_at(genesis + 3), Coin::from_u64_unchecked(150));
+
+    // The database is left untouched.
+    assert_eq!(
+        blockchain
+            .get_account_if_complete(&recipient)
+            .unwrap()
+            .balance(),
+        Coin::from_u64_unchecked(150)
+    );
+
+    // No diff is recorded for the genesis block.
+    assert_eq!(
+        blockchain.get_account_at(&recipient, genesis),
+        Err(BlockchainError::AccountsStateNotRetained(genesis))
+    );
+    assert_eq!(
+        blockchain.get_account_at(&recipient, genesis + 4),
+        Err(BlockchainError::BlockNotFound)
+    );
+}
@@ -222,7 +222,6 @@ impl ClientInner {
             }
         }
 
-        #[cfg(feature = "full-consensus")]
         // Initialize clock
         let time = Arc::new(OffsetTime::new());
 
@@ -314,7 +313,7 @@ impl ClientInner {
         };
 
         // Setup libp2p network
-        let network_config = NetworkConfig::new(
+        let mut network_config = NetworkConfig::new(
             identity_keypair,
             peer_contact,
             seeds,
@@ -331,6 +330,9 @@ impl ClientInner {
                 .dht_quorum
                 .unwrap_or(NonZeroU8::new(3).unwrap()),
         );
+        network_config.clock = Arc::clone(&time);
+        // Prefer peers that provide the same services as we do.
+        network_config.desired_services = provided_services;
 
         log::debug!(
             addresses = ?config.network.listen_addresses,
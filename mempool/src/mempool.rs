@@ -4,7 +4,7 @@ use std::{
 };
 
 use futures::{
-    future::{AbortHandle, Abortable},
+    future::{self, AbortHandle, Abortable},
     lock::{Mutex, MutexGuard},
     stream::{BoxStream, StreamExt},
 };
@@ -23,6 +23,7 @@ use nimiq_transaction::{
 use nimiq_utils::spawn;
 use parking_lot::RwLock;
 use tokio_metrics::TaskMonitor;
+use tokio_stream::wrappers::BroadcastStream;
 
 #[cfg(feature = "metrics")]
 use crate::mempool_metrics::MempoolMetrics;
@@ -618,6 +619,14 @@ impl Mempool {
         .await
     }
 
+    /// Returns a stream of the transactions added to the mempool from now on. Transactions are skipped if the
+    /// subscriber falls too far behind.
+    pub fn subscribe_transactions(&self) -> BoxStream<'static, Transaction> {
+        BroadcastStream::new(self.state.read().tx_added.subscribe())
+            .filter_map(|tx| future::ready(tx.ok()))
+            .boxed()
+    }
+
     /// Checks whether a transaction has been filtered
     pub fn is_filtered(&self, hash: &Blake2bHash) -> bool {
         self.filter.read().blacklisted(hash)
@@ -8,6 +8,7 @@ use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::Address;
 use nimiq_primitives::account::AccountType;
 use nimiq_transaction::Transaction;
+use tokio::sync::broadcast::{channel as broadcast, Sender as BroadcastSender};
 
 #[cfg(feature = "metrics")]
 use crate::mempool_metrics::MempoolMetrics;
@@ -26,16 +27,24 @@ pub(crate) struct MempoolState {
     // The pending balance per sender.
     pub(crate) state_by_sender: HashMap<Address, SenderPendingState>,
 
+    /// Notifies subscribers about transactions added to the mempool.
+    pub(crate) tx_added: BroadcastSender<Transaction>,
+
     #[cfg(feature = "metrics")]
     pub(crate) metrics: Arc<MempoolMetrics>,
 }
 
 impl MempoolState {
+    /// Maximum number of added transactions buffered for each subscriber.
+    const BROADCAST_MAX_CAPACITY: usize = 1024;
+
     pub fn new(regular_txns_limit: usize, control_txns_limit: usize) -> Self {
+        let (tx_added, _rx) = broadcast(Self::BROADCAST_MAX_CAPACITY);
         MempoolState {
             regular_transactions: MempoolTransactions::new(regular_txns_limit),
             control_transactions: MempoolTransactions::new(control_txns_limit),
             state_by_sender: HashMap::new(),
+            tx_added,
             #[cfg(feature = "metrics")]
             metrics: Default::default(),
         }
@@ -106,6 +115,11 @@ impl MempoolState {
             self.remove(blockchain, &tx_hash, EvictionReason::TooFull);
         }
 
+        // Notify subscribers, unless the new transaction was evicted right away.
+        if self.contains(&tx_hash) {
+            let _ = self.tx_added.send(tx.clone());
+        }
+
         Ok(())
     }
 
@@ -1,5 +1,7 @@
 use std::{env, str::FromStr, sync::Arc, time::Duration};
 
+use futures::StreamExt;
+
 use nimiq_block::{Block, MicroBlock, MicroBody, MicroHeader};
 use nimiq_blockchain::{BlockProducer, Blockchain, BlockchainConfig};
 use nimiq_blockchain_interface::{AbstractBlockchain, PushResult};
@@ -1938,6 +1940,92 @@ async fn applies_total_tx_size_limits() {
     }
     assert_eq!(mempool_txns.len(), (num_txns - 1) as usize);
 }
+
+#[test(tokio::test)]
+async fn notifies_subscribers_about_added_transactions() {
+    let mut rng = test_rng(true);
+    let balance = 100;
+    let num_txns = 3;
+    let mut mempool_transactions = vec![];
+    let sender_balances = vec![balance * num_txns + 10; 1];
+    let recipient_balances = vec![0; num_txns as usize];
+    let mut genesis_builder = GenesisBuilder::default();
+    genesis_builder.with_network(NetworkId::UnitAlbatross);
+
+    // Generate recipient accounts
+    let recipient_accounts =
+        generate_accounts(recipient_balances, &mut genesis_builder, false, &mut rng);
+    // Generate sender accounts
+    let sender_accounts = generate_accounts(sender_balances, &mut genesis_builder, true, &mut rng);
+
+    // Generate transactions
+    for i in 0..num_txns {
+        let mempool_transaction = TestTransaction {
+            fee: i + 1,
+            value: balance,
+            recipient: recipient_accounts[i as usize].clone(),
+            sender: sender_accounts[0].clone(),
+        };
+        mempool_transactions.push(mempool_transaction);
+    }
+
+    let (txns, _) = generate_transactions(mempool_transactions, true);
+
+    let env = MdbxDatabase::new_volatile(Default::default()).unwrap();
+    genesis_builder.with_genesis_validator(
+        Address::from(&SchnorrKeyPair::generate(&mut rng)),
+        SchnorrPublicKey::from([0u8; 32]),
+        BlsKeyPair::generate(&mut rng).public_key,
+        Address::default(),
+        None,
+        None,
+        false,
+    );
+
+    let genesis_info = genesis_builder.generate(env.clone()).unwrap();
+
+    // The genesis block number must match the specs we are setting in Policy
+    let genesis_block = match genesis_info.block {
+        Block::Macro(mut block) => {
+            block.header.block_number = Policy::genesis_block_number();
+            Block::Macro(block)
+        }
+        Block::Micro(_) => panic!(),
+    };
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::with_genesis(
+            env,
+            BlockchainConfig::default(),
+            Arc::new(OffsetTime::new()),
+            NetworkId::UnitAlbatross,
+            genesis_block,
+            genesis_info.accounts,
+        )
+        .unwrap(),
+    ));
+
+    let mempool = Mempool::new(blockchain, MempoolConfig::default());
+    let mut added_txns = mempool.subscribe_transactions();
+
+    for tx in &txns {
+        mempool.add_transaction(tx.clone(), None).await.unwrap();
+    }
+
+    // Subscribers are notified about every added transaction, in order
+    for tx in &txns {
+        let added_tx = added_txns.next().await.unwrap();
+        assert_eq!(added_tx.hash::<Blake2bHash>(), tx.hash::<Blake2bHash>());
+    }
+
+    // Transactions that are already in the mempool are not announced again
+    assert!(mempool
+        .add_transaction(txns[0].clone(), None)
+        .await
+        .is_err());
+    assert!(futures::poll!(added_txns.next()).is_pending());
+}
+
 #[test(tokio::test)]
 async fn it_can_reject_invalid_vesting_contract_transaction() {
     let time = Arc::new(OffsetTime::new());
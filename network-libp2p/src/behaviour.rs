@@ -61,7 +61,8 @@ impl Behaviour {
             config.discovery.clone(),
             config.keypair.clone(),
             Arc::clone(&contacts),
-        );
+        )
+        .with_clock(Arc::clone(&config.clock));
 
         // Gossipsub behaviour
         let thresholds = gossipsub::PeerScoreThresholds::default();
@@ -86,7 +87,8 @@ impl Behaviour {
             config.seeds,
             config.discovery.required_services,
             config.desired_peer_count,
-        );
+        )
+        .with_desired_services(config.desired_services);
 
         // Request Response behaviour
         let protocol = StreamProtocol::new("/nimiq/reqres/0.0.1");
@@ -146,6 +148,11 @@ impl Behaviour {
         self.discovery.is_address_dialable(address)
     }
 
+    /// Returns the peers in the peer contact book that we are not connected to.
+    pub fn unconnected_contacts(&self) -> Vec<PeerId> {
+        self.discovery.unconnected_contacts()
+    }
+
     /// Updates the scores of all peers in the peer contact book.
     /// Updates are performed with the score values of Gossipsub
     pub fn update_scores(&self, contacts: Arc<RwLock<PeerContactBook>>) {
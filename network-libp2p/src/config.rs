@@ -1,8 +1,9 @@
-use std::{num::NonZeroU8, time::Duration};
+use std::{num::NonZeroU8, sync::Arc, time::Duration};
 
 use libp2p::{gossipsub, identity::Keypair, kad, Multiaddr, StreamProtocol};
 use nimiq_hash::Blake2bHash;
 use nimiq_network_interface::{network::MIN_SUPPORTED_MSG_SIZE, peer_info::Services};
+use nimiq_utils::time::OffsetTime;
 use sha2::{Digest, Sha256};
 
 use crate::{
@@ -29,12 +30,16 @@ pub struct Config {
     pub gossipsub: gossipsub::Config,
     pub memory_transport: bool,
     pub required_services: Services,
+    /// Services that we don't require, but prefer our peers to provide.
+    pub desired_services: Services,
     pub tls: Option<TlsConfig>,
     pub desired_peer_count: usize,
     pub autonat_allow_non_global_ips: bool,
     pub only_secure_ws_connections: bool,
     pub allow_loopback_addresses: bool,
     pub dht_quorum: NonZeroU8,
+    /// The clock of the node. It is adjusted by the median offset of the peers' clocks measured by the discovery.
+    pub clock: Arc<OffsetTime>,
 }
 
 impl Config {
@@ -94,12 +99,14 @@ impl Config {
             gossipsub,
             memory_transport,
             required_services,
+            desired_services: Services::empty(),
             tls: tls_settings,
             desired_peer_count,
             autonat_allow_non_global_ips,
             only_secure_ws_connections,
             allow_loopback_addresses,
             dht_quorum,
+            clock: Arc::new(OffsetTime::new()),
         }
     }
 }
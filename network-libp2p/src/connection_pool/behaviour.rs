@@ -24,11 +24,14 @@ use nimiq_network_interface::{network::CloseReason, peer_info::Services};
 use nimiq_time::{interval, sleep_until, Interval};
 use nimiq_utils::WakerExt as _;
 use parking_lot::RwLock;
-use rand::{seq::IteratorRandom, thread_rng};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    thread_rng,
+};
 use void::Void;
 
 use super::Error;
-use crate::discovery::peer_contacts::PeerContactBook;
+use crate::discovery::peer_contacts::{PeerContactBook, PeerContactInfo};
 
 /// Current state of connections and peers for connection limits
 #[derive(Clone, Debug)]
@@ -39,6 +42,8 @@ struct Limits {
     ip_subnet_count: HashMap<IpNetwork, usize>,
     /// Total peer count
     peer_count: usize,
+    /// Peers whose connection was initiated by them
+    inbound_peers: HashSet<PeerId>,
 }
 
 /// Connection pool behaviour configuration
@@ -48,6 +53,9 @@ struct Config {
     desired_peer_count: usize,
     /// Maximum count of peers
     peer_count_max: usize,
+    /// Number of peer slots that can only be used by outbound connections, such that inbound
+    /// connections can't prevent us from connecting to peers of our own choice.
+    min_reserved_outbound_slots: usize,
     /// Maximum peer count per IP
     peer_count_per_ip_max: usize,
     /// Maximum peer count per subnet
@@ -78,6 +86,7 @@ impl Default for Config {
         Self {
             desired_peer_count: 12,
             peer_count_max: 4000,
+            min_reserved_outbound_slots: 8,
             peer_count_per_ip_max: 20,
             peer_count_per_subnet_max: 20,
             ipv4_subnet_prefix_len: 24,
@@ -221,6 +230,13 @@ impl<T: Clone + Ord> ConnectionState<T> {
         }
     }
 
+    /// Forgets the failed dial attempts of a connection ID and whether it is marked as down.
+    /// Banned IDs stay banned.
+    fn forget(&mut self, id: &T) {
+        self.failed.remove(id);
+        self.down.remove(id);
+    }
+
     /// Returns whether a connection ID is banned
     fn is_banned(&self, id: T) -> bool {
         self.banned.contains(&id)
@@ -350,6 +366,10 @@ pub struct Behaviour {
     /// The set of services that this peer requires.
     required_services: Services,
 
+    /// Services that this peer doesn't require but prefers its peers to provide. Contacts
+    /// providing more of them are dialed first.
+    desired_services: Services,
+
     /// Connection state per Peer ID
     peer_ids: ConnectionState<PeerId>,
 
@@ -387,6 +407,7 @@ impl Behaviour {
             ip_count: HashMap::new(),
             ip_subnet_count: HashMap::new(),
             peer_count: 0,
+            inbound_peers: HashSet::new(),
         };
         let config = Config {
             desired_peer_count,
@@ -399,6 +420,7 @@ impl Behaviour {
             own_peer_id,
             seeds,
             required_services,
+            desired_services: Services::empty(),
             peer_ids: ConnectionState::new(
                 2,
                 config.retry_down_after,
@@ -422,6 +444,73 @@ impl Behaviour {
         }
     }
 
+    /// Weight of each desired, but not required, service in the dial score.
+    const DESIRED_SERVICE_WEIGHT: f64 = 0.5;
+
+    /// Weight of the (normalized) peer score in the dial score.
+    const PEER_SCORE_WEIGHT: f64 = 0.5;
+
+    /// Weight of the (normalized) latency of the peer in the dial score.
+    const LATENCY_WEIGHT: f64 = 0.5;
+
+    /// Sets the services that we prefer our peers to provide in addition to the required ones.
+    pub fn with_desired_services(mut self, desired_services: Services) -> Self {
+        self.desired_services = desired_services;
+        self
+    }
+
+    /// Returns the score used to rank the given peer when choosing peers to dial, or `None` if we
+    /// don't have a contact for it. See [`Self::dial_score_of`].
+    pub fn dial_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.contacts
+            .read()
+            .get(peer_id)
+            .map(|contact| self.dial_score_of(&contact))
+    }
+
+    /// Computes the score of a contact used to rank it when choosing peers to dial. Every required
+    /// service the contact advertises counts fully, every desired service counts with
+    /// [`Self::DESIRED_SERVICE_WEIGHT`]. The peer score of the contact is mapped to `(-1, 1)` and
+    /// added with [`Self::PEER_SCORE_WEIGHT`], the latency measured when we were last connected to
+    /// the peer is mapped to `[0, 1)` in seconds and subtracted with [`Self::LATENCY_WEIGHT`]. Thus
+    /// reliable and fast peers are preferred among equally rich contacts. Peers we never measured
+    /// the latency of aren't penalized.
+    fn dial_score_of(&self, contact: &PeerContactInfo) -> f64 {
+        let services = contact.services();
+        let num_required = (services & self.required_services).bits().count_ones();
+        let num_desired = (services & self.desired_services.difference(self.required_services))
+            .bits()
+            .count_ones();
+
+        f64::from(num_required)
+            + Self::DESIRED_SERVICE_WEIGHT * f64::from(num_desired)
+            + Self::PEER_SCORE_WEIGHT * contact.get_score().tanh()
+            - Self::LATENCY_WEIGHT
+                * contact
+                    .get_latency()
+                    .map_or(0., |latency| latency.as_secs_f64().tanh())
+    }
+
+    /// Chooses up to `num_peers` of the given contacts to dial, preferring the ones with the
+    /// highest dial score. Contacts with equal scores are chosen randomly.
+    fn rank_dial_candidates<I: Iterator<Item = Arc<PeerContactInfo>>>(
+        &self,
+        candidates: I,
+        num_peers: usize,
+    ) -> Vec<PeerId> {
+        let mut candidates: Vec<(f64, PeerId)> = candidates
+            .map(|contact| (self.dial_score_of(&contact), *contact.peer_id()))
+            .collect();
+        candidates.shuffle(&mut thread_rng());
+        candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        candidates
+            .into_iter()
+            .take(num_peers)
+            .map(|(_, peer_id)| peer_id)
+            .collect()
+    }
+
     fn get_ip_info_from_multiaddr(&self, address: &Multiaddr) -> Option<IpInfo> {
         // Get IP from multiaddress if it exists.
         match address.iter().next() {
@@ -509,6 +598,12 @@ impl Behaviour {
         self.active = false;
     }
 
+    /// Forgets the failed dial attempts of a peer and whether it is considered down, e.g. because
+    /// its contact was removed from the peer contact book. Banned peers stay banned.
+    pub fn forget_peer(&mut self, peer_id: &PeerId) {
+        self.peer_ids.forget(peer_id);
+    }
+
     /// Closes a peer connection with a reason
     ///
     /// This will take actions depending on the close reason. For instance:
@@ -537,20 +632,13 @@ impl Behaviour {
         let own_contact = contacts.get_own_contact();
         let own_peer_id = own_contact.peer_id();
 
-        contacts
-            .query(self.required_services)
-            .filter_map(|contact| {
-                let peer_id = contact.peer_id();
-                if peer_id != own_peer_id
-                    && self.peer_ids.can_dial(peer_id)
-                    && contact.addresses().count() > 0
-                {
-                    Some(*peer_id)
-                } else {
-                    None
-                }
-            })
-            .choose_multiple(&mut thread_rng(), num_peers)
+        let candidates = contacts.query(self.required_services).filter(|contact| {
+            let peer_id = contact.peer_id();
+            peer_id != own_peer_id
+                && self.peer_ids.can_dial(peer_id)
+                && contact.addresses().count() > 0
+        });
+        self.rank_dial_candidates(candidates, num_peers)
     }
 
     /// This function is used to select a list of peers, based on services flag, in order to dial them.
@@ -565,20 +653,13 @@ impl Behaviour {
         let own_contact = contacts.get_own_contact();
         let own_peer_id = own_contact.peer_id();
 
-        contacts
-            .query(services)
-            .filter_map(|contact| {
-                let peer_id = contact.peer_id();
-                if peer_id != own_peer_id
-                    && self.peer_ids.can_dial(peer_id)
-                    && contact.addresses().count() > 0
-                {
-                    Some(*peer_id)
-                } else {
-                    None
-                }
-            })
-            .choose_multiple(&mut thread_rng(), num_peers)
+        let candidates = contacts.query(services).filter(|contact| {
+            let peer_id = contact.peer_id();
+            peer_id != own_peer_id
+                && self.peer_ids.can_dial(peer_id)
+                && contact.addresses().count() > 0
+        });
+        self.rank_dial_candidates(candidates, num_peers)
     }
 
     fn choose_seeds_to_dial(&self) -> Vec<Multiaddr> {
@@ -691,6 +772,10 @@ impl Behaviour {
             self.limits.peer_count = self.limits.peer_count.saturating_add(1);
         }
 
+        if endpoint.is_listener() {
+            self.limits.inbound_peers.insert(*peer_id);
+        }
+
         // Peer is connected, mark it as such.
         let peer_services = self
             .contacts
@@ -740,6 +825,7 @@ impl Behaviour {
         }
 
         self.limits.peer_count = self.limits.peer_count.saturating_sub(1);
+        self.limits.inbound_peers.remove(peer_id);
 
         self.addresses.mark_closed(address.clone());
         self.peer_ids.mark_closed(*peer_id);
@@ -956,6 +1042,23 @@ impl NetworkBehaviour for Behaviour {
             return Err(ConnectionDenied::new(Error::BannedPeer));
         }
 
+        // Inbound connections must leave the reserved outbound slots untouched. Additional
+        // connections to an already connected peer don't take up another slot.
+        let max_inbound_peers = self
+            .config
+            .peer_count_max
+            .saturating_sub(self.config.min_reserved_outbound_slots);
+        if !self.peer_ids.connected.contains_key(&peer)
+            && max_inbound_peers < self.limits.inbound_peers.len().saturating_add(1)
+        {
+            debug!(
+                peer_id = %peer,
+                inbound_peers = self.limits.inbound_peers.len(),
+                "Max inbound peer connections limit reached"
+            );
+            return Err(ConnectionDenied::new(Error::MaxInboundConnectionsReached));
+        }
+
         Ok(dummy::ConnectionHandler)
     }
 
@@ -995,16 +1098,25 @@ impl NetworkBehaviour for Behaviour {
 
 #[cfg(test)]
 mod tests {
-    use std::task::Context;
+    use std::{sync::Arc, task::Context};
 
     use futures::{task::noop_waker, FutureExt};
     use instant::Duration;
-    use libp2p::PeerId;
+    use libp2p::{
+        core::{transport::PortUse, ConnectedPoint, Endpoint},
+        identity::Keypair,
+        swarm::{ConnectionId, NetworkBehaviour},
+        Multiaddr, PeerId,
+    };
     use nimiq_network_interface::peer_info::Services;
     use nimiq_test_log::test;
     use nimiq_time::sleep;
+    use parking_lot::RwLock;
 
-    use crate::connection_pool::behaviour::ConnectionState;
+    use crate::{
+        connection_pool::behaviour::{Behaviour, ConnectionState},
+        discovery::peer_contacts::{PeerContact, PeerContactBook},
+    };
 
     #[test(tokio::test)]
     async fn unban_peers_after_timeout() {
@@ -1048,4 +1160,147 @@ mod tests {
         // p2 and p3 should both be unbanned
         assert!(cs.banned.is_empty());
     }
+
+    #[test(tokio::test)]
+    async fn inbound_connections_keep_outbound_slots_free() {
+        let keypair = Keypair::generate_ed25519();
+        let own_contact = PeerContact {
+            addresses: vec![],
+            public_key: keypair.public(),
+            services: Services::empty(),
+            timestamp: None,
+        }
+        .sign(&keypair);
+        let contacts = Arc::new(RwLock::new(PeerContactBook::new(
+            own_contact,
+            false,
+            true,
+            true,
+        )));
+
+        let mut behaviour = Behaviour::new(
+            contacts,
+            keypair.public().to_peer_id(),
+            vec![],
+            Services::empty(),
+            1,
+        );
+        behaviour.config.peer_count_max = 4;
+        behaviour.config.min_reserved_outbound_slots = 2;
+
+        let local_addr: Multiaddr = "/ip4/127.0.0.1/tcp/8443".parse().unwrap();
+        let remote_addr =
+            |i: usize| -> Multiaddr { format!("/ip4/10.0.{i}.1/tcp/8443").parse().unwrap() };
+
+        // Fill up all slots that are available to inbound connections.
+        for i in 0..2 {
+            let peer_id = PeerId::random();
+            let connection_id = ConnectionId::new_unchecked(i);
+            assert!(behaviour
+                .handle_established_inbound_connection(
+                    connection_id,
+                    peer_id,
+                    &local_addr,
+                    &remote_addr(i),
+                )
+                .is_ok());
+            behaviour.on_connection_established(
+                &peer_id,
+                &connection_id,
+                &ConnectedPoint::Listener {
+                    local_addr: local_addr.clone(),
+                    send_back_addr: remote_addr(i),
+                },
+                &[],
+                0,
+            );
+        }
+
+        // Another inbound connection would consume the reserved outbound slots.
+        assert!(behaviour
+            .handle_established_inbound_connection(
+                ConnectionId::new_unchecked(2),
+                PeerId::random(),
+                &local_addr,
+                &remote_addr(2),
+            )
+            .is_err());
+
+        // Outbound connections can still use the reserved slots.
+        for i in 2..4 {
+            let peer_id = PeerId::random();
+            let connection_id = ConnectionId::new_unchecked(i);
+            assert!(behaviour
+                .handle_established_outbound_connection(
+                    connection_id,
+                    peer_id,
+                    &remote_addr(i),
+                    Endpoint::Dialer,
+                    PortUse::Reuse,
+                )
+                .is_ok());
+            behaviour.on_connection_established(
+                &peer_id,
+                &connection_id,
+                &ConnectedPoint::Dialer {
+                    address: remote_addr(i),
+                    role_override: Endpoint::Dialer,
+                    port_use: PortUse::Reuse,
+                },
+                &[],
+                0,
+            );
+        }
+        assert_eq!(behaviour.limits.peer_count, 4);
+        assert_eq!(behaviour.limits.inbound_peers.len(), 2);
+    }
+
+    #[test(tokio::test)]
+    async fn prefers_contacts_with_richer_services() {
+        let keypair = Keypair::generate_ed25519();
+        let own_contact = PeerContact {
+            addresses: vec![],
+            public_key: keypair.public(),
+            services: Services::empty(),
+            timestamp: None,
+        }
+        .sign(&keypair);
+        let contacts = Arc::new(RwLock::new(PeerContactBook::new(
+            own_contact,
+            false,
+            true,
+            true,
+        )));
+
+        let contact = |services: Services| {
+            let keypair = Keypair::generate_ed25519();
+            let mut contact = PeerContact {
+                addresses: vec!["/dns/test.local/tcp/443/wss".parse().unwrap()],
+                public_key: keypair.public(),
+                services,
+                timestamp: None,
+            };
+            contact.set_current_time();
+            contact.sign(&keypair)
+        };
+        let poor_contact = contact(Services::FULL_BLOCKS);
+        let rich_contact = contact(Services::FULL_BLOCKS | Services::HISTORY);
+        let poor_peer_id = poor_contact.peer_id();
+        let rich_peer_id = rich_contact.peer_id();
+        contacts.write().insert_all([poor_contact, rich_contact]);
+
+        let behaviour = Behaviour::new(
+            contacts,
+            keypair.public().to_peer_id(),
+            vec![],
+            Services::FULL_BLOCKS,
+            1,
+        )
+        .with_desired_services(Services::HISTORY);
+
+        assert!(behaviour.dial_score(&rich_peer_id) > behaviour.dial_score(&poor_peer_id));
+        for _ in 0..10 {
+            assert_eq!(behaviour.choose_peers_to_dial(), vec![rich_peer_id]);
+        }
+    }
 }
@@ -24,4 +24,8 @@ pub enum Error {
     ///Maximum peers connections per IP has been reached
     #[error("Maximum peers connections per IP has been reached")]
     MaxPeerPerIPConnectionsReached,
+
+    /// Maximum inbound peer connections has been reached
+    #[error("Maximum inbound peer connections has been reached")]
+    MaxInboundConnectionsReached,
 }
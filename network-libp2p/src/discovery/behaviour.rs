@@ -1,27 +1,36 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
     sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
 
 use futures::StreamExt;
+use instant::Instant;
 use libp2p::{
     core::{transport::PortUse, Endpoint},
     identity::Keypair,
+    multiaddr::Protocol,
     swarm::{
-        behaviour::{ConnectionClosed, ConnectionEstablished},
-        CloseConnection, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, ToSwarm,
+        behaviour::{
+            ConnectionClosed, ConnectionEstablished, DialFailure, ExternalAddrConfirmed,
+            ExternalAddrExpired, ListenFailure,
+        },
+        CloseConnection, ConnectionDenied, ConnectionId, DialError, FromSwarm, ListenError,
+        NetworkBehaviour, NotifyHandler, ToSwarm,
     },
     Multiaddr, PeerId,
 };
 use nimiq_hash::Blake2bHash;
 use nimiq_network_interface::peer_info::Services;
 use nimiq_time::{interval, Interval};
+use nimiq_utils::time::OffsetTime;
 use parking_lot::RwLock;
+use tokio::sync::mpsc;
 
 use super::{
-    handler::{Handler, HandlerOutEvent},
+    handler::{Error, Handler, HandlerInEvent, HandlerOutEvent, HandlerState},
     peer_contacts::{PeerContact, PeerContactBook},
 };
 
@@ -30,9 +39,22 @@ pub struct Config {
     /// Genesis hash for the network we want to be connected to.
     pub genesis_hash: Blake2bHash,
 
-    /// Interval in which we want to be updated.
+    /// Interval in which we want to be updated. With [`Config::adaptive_update`], this is the interval at
+    /// [`Config::target_peer_count`] connected peers, and the effective interval is scaled with the number of connected
+    /// peers, see [`Behaviour::update_interval`].
     pub update_interval: Duration,
 
+    /// Whether to scale the interval in which we want to be updated with the number of connected peers. If disabled,
+    /// [`Config::update_interval`] is always used.
+    pub adaptive_update: bool,
+
+    /// Maximum interval in which we want to be updated if [`Config::adaptive_update`] is enabled and more than
+    /// [`Config::target_peer_count`] peers are connected.
+    pub max_update_interval: Duration,
+
+    /// Number of connected peers at which the effective update interval is [`Config::update_interval`].
+    pub target_peer_count: usize,
+
     /// Minimum update interval, that we will accept. If peer contact updates are received faster than this, they will
     /// be rejected.
     pub min_recv_update_interval: Duration,
@@ -54,6 +76,80 @@ pub struct Config {
 
     /// Only secure websocket connections
     pub only_secure_ws_connections: bool,
+
+    /// Whether to only send the peer contacts that changed since the previous update to peers that support it,
+    /// instead of a full set of peer contacts with every update.
+    pub enable_delta_updates: bool,
+
+    /// Whether to report the addresses peers observed us on to the swarm as external address candidates.
+    pub process_observed_addresses: bool,
+
+    /// Whether to report any external address candidates to the swarm, i.e. our own addresses as well as the
+    /// addresses peers observed us on.
+    pub emit_external_addr_candidates: bool,
+
+    /// Whether to accept inbound connections. If disabled, inbound connections are denied.
+    pub accept_inbound: bool,
+
+    /// If set, a connection is only kept alive as long as the peer sent us a useful update, i.e. one containing
+    /// peer contacts other than its own, within this duration. Idle connections are then closed by the swarm once no
+    /// other behaviour uses them anymore.
+    pub keep_alive_idle_timeout: Option<Duration>,
+
+    /// Maximum number of peer contacts kept in the peer contact book, not counting our own. If exceeded, contacts are
+    /// evicted during house-keeping, see [`PeerContactBook::evict_excess`].
+    pub max_peer_contacts: usize,
+
+    /// Offset of a peer's clock to ours above which [`Event::ClockSkewDetected`] is emitted.
+    pub clock_skew_threshold: Duration,
+
+    /// Maximum offset of a peer's clock that is accepted when adjusting our clock. Larger offsets are clamped to this,
+    /// such that peers can shift our clock by at most this much.
+    pub max_clock_offset: Duration,
+
+    /// Maximum number of connections from a single IP address. If set, inbound connections from IP addresses that
+    /// already reached this limit are denied. Loopback addresses and [`Config::trusted_ips`] are exempt.
+    pub max_connections_per_ip: Option<usize>,
+
+    /// IP addresses that are exempt from [`Config::max_connections_per_ip`].
+    pub trusted_ips: Vec<IpAddr>,
+
+    /// Number of discovery protocol violations of a peer within [`Config::auto_ban_window`] after which the peer is
+    /// banned for [`Config::auto_ban_duration`], see [`Error::is_protocol_violation`]. Other errors, e.g. timeouts,
+    /// are not counted. If `None`, peers are never banned automatically.
+    pub auto_ban_errors: Option<usize>,
+
+    /// Window in which the protocol violations of a peer are counted towards [`Config::auto_ban_errors`].
+    pub auto_ban_window: Duration,
+
+    /// Duration for which peers are banned automatically.
+    pub auto_ban_duration: Duration,
+
+    /// Duration for which peers on a network with a different genesis block are suppressed in the peer contact book,
+    /// i.e. for which their contacts are ignored and dialing them is denied.
+    pub genesis_mismatch_cooldown: Duration,
+
+    /// Duration for which the addresses of a peer are not dialed again after the first failed dial. The backoff
+    /// doubles with every further failed dial, up to [`Config::max_dial_backoff`], and is reset once a connection to
+    /// the peer is established.
+    pub min_dial_backoff: Duration,
+
+    /// Maximum duration for which the addresses of a peer are not dialed after failed dials.
+    pub max_dial_backoff: Duration,
+
+    /// Maximum number of peer contacts a peer may send us in updates within [`Config::recv_contacts_window`]. This
+    /// bounds the load a peer can cause with large updates sent just slower than
+    /// [`Config::min_recv_update_interval`]. Connections to peers exceeding it are closed as on protocol errors. If
+    /// `None`, the number of received peer contacts is not limited.
+    pub max_recv_contacts: Option<usize>,
+
+    /// Maximum number of bytes of updates a peer may send us within [`Config::recv_contacts_window`]. Connections to
+    /// peers exceeding it are closed as on protocol errors. If `None`, the number of received bytes is not limited.
+    pub max_recv_bytes: Option<usize>,
+
+    /// Sliding window in which the peer contacts and bytes received from a peer are counted towards
+    /// [`Config::max_recv_contacts`] and [`Config::max_recv_bytes`].
+    pub recv_contacts_window: Duration,
 }
 
 impl Config {
@@ -65,6 +161,9 @@ impl Config {
         Self {
             genesis_hash,
             update_interval: Duration::from_secs(60),
+            adaptive_update: true,
+            max_update_interval: Duration::from_secs(5 * 60),
+            target_peer_count: 20,
             min_send_update_interval: Duration::from_secs(30),
             min_recv_update_interval: Duration::from_secs(30),
             update_limit: 64,
@@ -72,6 +171,25 @@ impl Config {
             house_keeping_interval: Duration::from_secs(60),
             keep_alive: true,
             only_secure_ws_connections,
+            enable_delta_updates: true,
+            process_observed_addresses: true,
+            emit_external_addr_candidates: true,
+            accept_inbound: true,
+            keep_alive_idle_timeout: None,
+            max_peer_contacts: 10_000,
+            clock_skew_threshold: Duration::from_secs(10),
+            max_clock_offset: Duration::from_secs(5),
+            max_connections_per_ip: None,
+            trusted_ips: vec![],
+            auto_ban_errors: Some(5),
+            auto_ban_window: Duration::from_secs(10 * 60),
+            auto_ban_duration: Duration::from_secs(30 * 60),
+            genesis_mismatch_cooldown: Duration::from_secs(60 * 60),
+            min_dial_backoff: Duration::from_secs(5),
+            max_dial_backoff: Duration::from_secs(30 * 60),
+            max_recv_contacts: Some(1_000),
+            max_recv_bytes: Some(512 * 1024),
+            recv_contacts_window: Duration::from_secs(5 * 60),
         }
     }
 }
@@ -84,19 +202,173 @@ pub enum Event {
         peer_contact: PeerContact,
     },
     Update,
+    /// The offset in milliseconds of a peer's clock to our clock was measured during the handshake.
+    ClockOffset {
+        peer_id: PeerId,
+        offset: i64,
+    },
+    /// The contact of a peer was removed from the peer contact book during house-keeping.
+    PeerRemoved {
+        peer_id: PeerId,
+        reason: PeerRemovalReason,
+    },
+    /// The offset in milliseconds of a peer's clock to our clock exceeds [`Config::clock_skew_threshold`].
+    /// Either the peer's clock or ours is off.
+    ClockSkewDetected {
+        peer_id: PeerId,
+        offset: i64,
+    },
+    /// A connected peer advertised different services in an updated contact than before.
+    ServicesChanged {
+        peer_id: PeerId,
+        old: Services,
+        new: Services,
+    },
+    /// A peer we dialed doesn't provide the [`Config::required_services`]. The connection to it is closed instead of
+    /// emitting [`Event::Established`].
+    PeerUseless {
+        peer_id: PeerId,
+    },
+    /// A peer was banned, either explicitly with [`Behaviour::ban_peer`] or because it exceeded
+    /// [`Config::auto_ban_errors`]. Connections to the peer are denied for the given duration.
+    PeerBanned {
+        peer_id: PeerId,
+        duration: Duration,
+    },
+    /// A connection to a peer could not be established or was closed because of a failure.
+    ConnectionFailed {
+        peer_id: Option<PeerId>,
+        address: Option<Multiaddr>,
+        reason: ConnectionFailReason,
+    },
+}
+
+/// The category of a connection failure reported with [`Event::ConnectionFailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionFailReason {
+    /// Dialing the peer failed.
+    DialFailure,
+    /// The connection was denied by one of the network behaviours.
+    Denied,
+    /// The connection or discovery handshake with the peer failed.
+    HandshakeFailure,
+    /// The peer is on a network with a different genesis block.
+    GenesisMismatch,
+    /// The peer violated the discovery protocol after the handshake.
+    ProtocolViolation,
+    /// The discovery substream failed.
+    StreamFailure,
+}
+
+/// The reason for removing a peer contact reported with [`Event::PeerRemoved`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerRemovalReason {
+    /// The contact exceeded its maximum age.
+    Expired,
+    /// The contact was evicted because the peer contact book exceeded [`Config::max_peer_contacts`].
+    Evicted,
+}
+
+impl From<&Error> for ConnectionFailReason {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::Io(_) | Error::Serialization(_) => Self::StreamFailure,
+            Error::GenesisHashMismatch { .. } => Self::GenesisMismatch,
+            Error::TooFrequentUpdates { .. }
+            | Error::UpdateLimitExceeded { .. }
+            | Error::UpdateBudgetExceeded { .. } => Self::ProtocolViolation,
+            Error::UnexpectedMessage { state, .. } if *state == HandlerState::Established => {
+                Self::ProtocolViolation
+            }
+            Error::UnexpectedMessage { .. }
+            | Error::StateTransitionTimeout { .. }
+            | Error::InvalidPeerContactSignature { .. }
+            | Error::ChallengeResponseFailed
+            | Error::Signing(_) => Self::HandshakeFailure,
+        }
+    }
+}
+
+/// An update of our own external addresses, as sent to the sender registered with
+/// [`Behaviour::with_external_address_updates`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalAddrUpdate {
+    /// The address was confirmed to be reachable from the outside.
+    Confirmed(Multiaddr),
+    /// The address is no longer considered to be reachable from the outside.
+    Expired(Multiaddr),
+}
+
+/// Snapshot of the state and counters of the discovery behaviour, as returned by [`Behaviour::metrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of currently connected peers.
+    pub connected_peers: usize,
+    /// Number of peer contacts in the peer contact book, not counting our own.
+    pub peer_contacts: usize,
+    /// Number of peer contacts in the peer contact book that provide the [`Config::required_services`].
+    pub peer_contacts_with_required_services: usize,
+    /// Number of peer exchanges that were established, i.e. of completed discovery handshakes.
+    pub peer_exchanges_established: u64,
+    /// Number of peer contact updates received from peers.
+    pub updates_received: u64,
+    /// Number of connections closed because of an error in the discovery protocol.
+    pub connections_closed_on_error: u64,
+    /// Number of peer contact updates rejected because they were received faster than
+    /// [`Config::min_recv_update_interval`].
+    pub updates_rejected: u64,
+    /// Number of peer contact updates rejected because the peer exceeded [`Config::max_recv_contacts`] or
+    /// [`Config::max_recv_bytes`].
+    pub updates_rate_limited: u64,
+    /// Number of house-keeping runs in the peer contact book.
+    pub house_keeping_runs: u64,
+}
+
+/// The reason for denying inbound connections if [`Config::accept_inbound`] is disabled.
+#[derive(Debug, thiserror::Error)]
+#[error("Inbound connections are disabled")]
+pub struct InboundConnectionsDisabled;
+
+/// The reason for denying an inbound connection if [`Config::max_connections_per_ip`] is exceeded.
+#[derive(Debug, thiserror::Error)]
+#[error("Maximum number of connections from {ip} ({limit}) reached")]
+pub struct ConnectionsPerIpExceeded {
+    pub ip: IpAddr,
+    pub limit: usize,
+}
+
+/// The reason for denying a connection to a peer banned with [`Behaviour::ban_peer`].
+#[derive(Debug, thiserror::Error)]
+#[error("Peer {peer_id} is banned")]
+pub struct PeerBanned {
+    pub peer_id: PeerId,
 }
 
-type DiscoveryToSwarm = ToSwarm<Event, ()>;
+/// The reason for denying to dial a peer that is on a network with a different genesis block, see
+/// [`Config::genesis_mismatch_cooldown`].
+#[derive(Debug, thiserror::Error)]
+#[error("Peer {peer_id} is on a different network")]
+pub struct GenesisMismatch {
+    pub peer_id: PeerId,
+}
+
+type DiscoveryToSwarm = ToSwarm<Event, HandlerInEvent>;
+
+/// Backoff from dialing a peer after failed dials.
+struct DialBackoff {
+    /// Number of consecutive failed dials.
+    failures: u32,
+    /// Time until which the peer's addresses are not dialed.
+    next_dial: Instant,
+}
 
 /// Network behaviour for peer exchange.
 ///
-/// When a connection to a peer is established, a handshake is done to exchange protocols and services filters, and
-/// subscription settings. The peers then send updates to each other in a configurable interval.
-///
-/// # TODO
-///
-///  - Exchange clock time with other peers.
+/// When a connection to a peer is established, a handshake is done to exchange protocols and services filters,
+/// subscription settings and the current time. The peers then send updates to each other in a configurable interval.
 ///
+/// The offset of each connected peer's clock is measured during the handshake. Our clock is adjusted by the median of
+/// these offsets, each clamped to [`Config::max_clock_offset`], such that peers can't shift our clock arbitrarily.
 pub struct Behaviour {
     /// Configuration for the discovery behaviour
     config: Config,
@@ -104,8 +376,40 @@ pub struct Behaviour {
     /// Identity key pair
     keypair: Keypair,
 
-    /// `PeerId`s of all connected peers.
-    connected_peers: HashSet<PeerId>,
+    /// Connections of all connected peers.
+    connected_peers: HashMap<PeerId, HashSet<ConnectionId>>,
+
+    /// Connections that were established by dialing the peer.
+    outbound_connections: HashSet<ConnectionId>,
+
+    /// Number of established connections per remote IP address.
+    connections_per_ip: HashMap<IpAddr, usize>,
+
+    /// Banned peers together with the time their ban expires.
+    banned_peers: HashMap<PeerId, Instant>,
+
+    /// Times of the recent discovery protocol violations of each peer, see [`Config::auto_ban_errors`].
+    peer_errors: HashMap<PeerId, VecDeque<Instant>>,
+
+    /// Peers that recently failed to be dialed, see [`Config::min_dial_backoff`].
+    dial_backoffs: HashMap<PeerId, DialBackoff>,
+
+    /// Whether sending periodic updates to peers is paused.
+    paused: bool,
+
+    /// Exponential moving average of the round-trip time measured for each connected peer.
+    peer_latencies: HashMap<PeerId, Duration>,
+
+    /// Services last advertised by each connected peer.
+    peer_services: HashMap<PeerId, Services>,
+
+    /// Offset in milliseconds of each connected peer's clock to our local system clock, clamped to
+    /// [`Config::max_clock_offset`].
+    peer_clock_offsets: HashMap<PeerId, i64>,
+
+    /// The clock whose time is sent to peers during the handshake, which their clocks are compared to and which is
+    /// adjusted by their median offset.
+    clock: Arc<OffsetTime>,
 
     /// Contains all known peer contacts.
     peer_contact_book: Arc<RwLock<PeerContactBook>>,
@@ -115,30 +419,91 @@ pub struct Behaviour {
 
     /// Timer to do house-keeping in the peer address book.
     house_keeping_timer: Interval,
+
+    /// Interval in which we currently want to be updated, scaled by the number of connected peers.
+    update_interval: Duration,
+
+    /// Receiver of updates of our own external addresses, if any.
+    external_address_tx: Option<mpsc::Sender<ExternalAddrUpdate>>,
+
+    /// Counters of the events reported by the connection handlers and of the house-keeping runs.
+    metrics: Metrics,
 }
 
 impl Behaviour {
+    /// Weight given to a new latency sample in the exponential moving average (in percent).
+    const LATENCY_EMA_WEIGHT: u32 = 20;
+
     pub fn new(
         config: Config,
         keypair: Keypair,
         peer_contact_book: Arc<RwLock<PeerContactBook>>,
     ) -> Self {
         let house_keeping_timer = interval(config.house_keeping_interval);
+        let update_interval = config.update_interval;
         peer_contact_book.write().update_own_contact(&keypair);
 
         // Report our own known addresses as candidates to the swarm
         let mut events = VecDeque::new();
-        for address in peer_contact_book.read().get_own_contact().addresses() {
-            events.push_back(ToSwarm::NewExternalAddrCandidate(address.clone()));
+        if config.emit_external_addr_candidates {
+            for address in peer_contact_book.read().get_own_contact().addresses() {
+                events.push_back(ToSwarm::NewExternalAddrCandidate(address.clone()));
+            }
         }
 
         Self {
             config,
             keypair,
-            connected_peers: HashSet::new(),
+            connected_peers: HashMap::new(),
+            outbound_connections: HashSet::new(),
+            connections_per_ip: HashMap::new(),
+            banned_peers: HashMap::new(),
+            peer_errors: HashMap::new(),
+            dial_backoffs: HashMap::new(),
+            paused: false,
+            peer_latencies: HashMap::new(),
+            peer_services: HashMap::new(),
+            peer_clock_offsets: HashMap::new(),
+            clock: Arc::new(OffsetTime::new()),
             peer_contact_book,
             events,
             house_keeping_timer,
+            update_interval,
+            external_address_tx: None,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Registers a sender that receives an [`ExternalAddrUpdate`] whenever one of our external addresses is
+    /// confirmed or expires. Updates are sent as they happen, independent of the swarm events. If the channel is
+    /// full, the update is dropped.
+    pub fn with_external_address_updates(mut self, tx: mpsc::Sender<ExternalAddrUpdate>) -> Self {
+        self.external_address_tx = Some(tx);
+        self
+    }
+
+    /// Sets the clock whose time is sent to peers during the handshake, which their clocks are compared to and which is
+    /// adjusted by their median offset.
+    pub fn with_clock(mut self, clock: Arc<OffsetTime>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn notify_external_address(&mut self, update: ExternalAddrUpdate) {
+        let Some(tx) = &self.external_address_tx else {
+            return;
+        };
+        match tx.try_send(update) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(update)) => {
+                warn!(
+                    ?update,
+                    "Dropping external address update, receiver is full"
+                );
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.external_address_tx = None;
+            }
         }
     }
 
@@ -149,11 +514,331 @@ impl Behaviour {
             .add_own_addresses(addresses, &self.keypair)
     }
 
+    /// Returns the interval in which we currently want to be updated by peers.
+    ///
+    /// With [`Config::adaptive_update`], the interval is proportional to the number of connected peers. It is
+    /// [`Config::update_interval`] at [`Config::target_peer_count`] peers, shorter with fewer peers down to
+    /// [`Config::min_recv_update_interval`], and longer with more peers up to [`Config::max_update_interval`]. With
+    /// few peers, we learn about new peer contacts quickly, and with many peers, each of them can update us less often
+    /// while we still learn about new peer contacts about as quickly. Changes are sent to the connected peers.
+    pub fn update_interval(&self) -> Duration {
+        self.update_interval
+    }
+
+    /// Computes the effective update interval for the current number of connected peers, see
+    /// [`Behaviour::update_interval`].
+    fn scaled_update_interval(&self) -> Duration {
+        let update_interval = self.config.update_interval;
+        if !self.config.adaptive_update {
+            return update_interval;
+        }
+        let min = self.config.min_recv_update_interval.min(update_interval);
+        let max = self.config.max_update_interval.max(update_interval);
+        let target = self.config.target_peer_count;
+        if target == 0 {
+            return max;
+        }
+
+        let ratio = self.connected_peers.len() as f64 / target as f64;
+        update_interval.mul_f64(ratio).clamp(min, max)
+    }
+
+    /// Returns the config for the handler of a new connection, which requests the current update interval.
+    fn handler_config(&self) -> Config {
+        let mut config = self.config.clone();
+        config.update_interval = self.update_interval;
+        config
+    }
+
     /// Returns whether an address in `Multiaddr` format is a dialable websocket address
     pub fn is_address_dialable(&self, address: &Multiaddr) -> bool {
         self.peer_contact_book.read().is_address_dialable(address)
     }
 
+    /// Pauses or resumes sending periodic peer contact updates to all connected peers. Connections are kept open
+    /// while paused. Updates sent by other peers are still accepted, but the update interval requested from them
+    /// during the handshake stays unchanged, so they keep sending updates.
+    pub fn set_discovery_paused(&mut self, paused: bool) {
+        if self.paused == paused {
+            return;
+        }
+        self.paused = paused;
+
+        let event = if paused {
+            HandlerInEvent::Pause
+        } else {
+            HandlerInEvent::Resume
+        };
+        for (peer_id, connections) in &self.connected_peers {
+            for connection_id in connections {
+                self.events.push_back(ToSwarm::NotifyHandler {
+                    peer_id: *peer_id,
+                    handler: NotifyHandler::One(*connection_id),
+                    event: event.clone(),
+                });
+            }
+        }
+    }
+
+    /// Replaces [`Config::required_services`] without reconnecting to the connected peers. Connections to peers whose
+    /// contact doesn't provide the new services are closed and [`Event::Update`] is emitted.
+    ///
+    /// The peer contacts received from the remaining peers are filtered by the new services from now on. Note that
+    /// the peers keep filtering the contacts they send by the services we requested during the handshake, thus if the
+    /// new services are less restrictive, contacts only matching the new services are learned after reconnecting.
+    pub fn update_required_services(&mut self, services: Services) {
+        if self.config.required_services == services {
+            return;
+        }
+        self.config.required_services = services;
+
+        let peer_contact_book = self.peer_contact_book.read();
+        for (peer_id, connections) in &self.connected_peers {
+            let satisfies_services = peer_contact_book
+                .get(peer_id)
+                .map_or(true, |contact| contact.matches(services));
+            if !satisfies_services {
+                debug!(%peer_id, ?services, "Closing connection to peer not providing the required services");
+                self.events.push_back(ToSwarm::CloseConnection {
+                    peer_id: *peer_id,
+                    connection: CloseConnection::All,
+                });
+                continue;
+            }
+            for connection_id in connections {
+                self.events.push_back(ToSwarm::NotifyHandler {
+                    peer_id: *peer_id,
+                    handler: NotifyHandler::One(*connection_id),
+                    event: HandlerInEvent::UpdateRequiredServices(services),
+                });
+            }
+        }
+        drop(peer_contact_book);
+
+        self.events.push_back(ToSwarm::GenerateEvent(Event::Update));
+    }
+
+    /// Returns whether sending periodic peer contact updates is paused.
+    pub fn is_discovery_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Records a round-trip time sample for a peer, folding it into the peer's latency moving average. The average is
+    /// also kept with the peer's contact, such that it can be considered when choosing peers to dial later on.
+    pub fn record_latency(&mut self, peer_id: PeerId, rtt: Duration) {
+        let latency = *self
+            .peer_latencies
+            .entry(peer_id)
+            .and_modify(|latency| {
+                *latency = (*latency * (100 - Self::LATENCY_EMA_WEIGHT)
+                    + rtt * Self::LATENCY_EMA_WEIGHT)
+                    / 100;
+            })
+            .or_insert(rtt);
+        if let Some(contact) = self.peer_contact_book.read().get(&peer_id) {
+            contact.set_latency(latency);
+        }
+    }
+
+    /// Returns the peers for which a latency was recorded together with their average latency,
+    /// sorted ascending by latency (fastest peer first).
+    pub fn peers_by_latency(&self) -> Vec<(PeerId, Duration)> {
+        let mut peers: Vec<(PeerId, Duration)> = self
+            .peer_latencies
+            .iter()
+            .map(|(peer_id, latency)| (*peer_id, *latency))
+            .collect();
+        peers.sort_by_key(|(_, latency)| *latency);
+        peers
+    }
+
+    /// Returns the peers that are present in the peer contact book but that we currently don't
+    /// have a connection to.
+    pub fn unconnected_contacts(&self) -> Vec<PeerId> {
+        self.peer_contact_book
+            .read()
+            .peer_ids()
+            .filter(|peer_id| !self.connected_peers.contains_key(peer_id))
+            .copied()
+            .collect()
+    }
+
+    /// Bans a peer for the given duration. Its connections are closed and new connections to it are denied until
+    /// the ban expires, including dialing it. Expired bans are removed during house-keeping.
+    pub fn ban_peer(&mut self, peer_id: PeerId, duration: Duration) {
+        debug!(%peer_id, ?duration, "Banning peer");
+        self.banned_peers.insert(peer_id, Instant::now() + duration);
+        self.peer_errors.remove(&peer_id);
+        self.events
+            .push_back(ToSwarm::GenerateEvent(Event::PeerBanned {
+                peer_id,
+                duration,
+            }));
+        self.events.push_back(ToSwarm::CloseConnection {
+            peer_id,
+            connection: CloseConnection::All,
+        });
+    }
+
+    /// Lifts the ban of a peer. Returns whether the peer was banned.
+    pub fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+        self.banned_peers.remove(peer_id).is_some()
+    }
+
+    /// Returns whether the peer is currently banned.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned_peers
+            .get(peer_id)
+            .is_some_and(|until| *until > Instant::now())
+    }
+
+    /// Records a discovery protocol violation of a peer. Returns whether the peer exceeded [`Config::auto_ban_errors`]
+    /// within [`Config::auto_ban_window`] and thus must be banned.
+    fn record_error(&mut self, peer_id: PeerId) -> bool {
+        let Some(max_errors) = self.config.auto_ban_errors else {
+            return false;
+        };
+        let now = Instant::now();
+        let errors = self.peer_errors.entry(peer_id).or_default();
+        while errors
+            .front()
+            .is_some_and(|time| now.duration_since(*time) > self.config.auto_ban_window)
+        {
+            errors.pop_front();
+        }
+        errors.push_back(now);
+        errors.len() >= max_errors
+    }
+
+    /// Closes the connections to a peer because of an error in the discovery protocol, or bans it if the error is a
+    /// protocol violation and the peer exceeded [`Config::auto_ban_errors`].
+    fn close_on_error(&mut self, peer_id: PeerId, error: Error) {
+        self.metrics.connections_closed_on_error += 1;
+        self.events
+            .push_back(ToSwarm::GenerateEvent(Event::ConnectionFailed {
+                peer_id: Some(peer_id),
+                address: None,
+                reason: ConnectionFailReason::from(&error),
+            }));
+        if error.is_protocol_violation() && self.record_error(peer_id) {
+            self.ban_peer(peer_id, self.config.auto_ban_duration);
+        } else {
+            self.events.push_back(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::All,
+            });
+        }
+    }
+
+    /// Removes expired bans and errors that are no longer within [`Config::auto_ban_window`].
+    fn expire_bans(&mut self) {
+        let now = Instant::now();
+        self.banned_peers.retain(|_, until| *until > now);
+        let window = self.config.auto_ban_window;
+        self.peer_errors.retain(|_, errors| {
+            errors
+                .back()
+                .is_some_and(|time| now.duration_since(*time) <= window)
+        });
+        // Forget dial failures once the peer wasn't dialed for a full maximum backoff after its backoff ended.
+        let max_backoff = self.config.max_dial_backoff;
+        self.dial_backoffs
+            .retain(|_, backoff| backoff.next_dial + max_backoff > now);
+    }
+
+    /// Returns the time until which the addresses of the given peer are not dialed because previous dials failed,
+    /// if the peer is currently in backoff.
+    pub fn dial_backoff(&self, peer_id: &PeerId) -> Option<Instant> {
+        self.dial_backoffs
+            .get(peer_id)
+            .map(|backoff| backoff.next_dial)
+            .filter(|next_dial| *next_dial > Instant::now())
+    }
+
+    /// Records a failed dial of the given peer and extends its backoff exponentially.
+    fn record_dial_failure(&mut self, peer_id: PeerId) {
+        let backoff = self.dial_backoffs.entry(peer_id).or_insert(DialBackoff {
+            failures: 0,
+            next_dial: Instant::now(),
+        });
+        backoff.failures = backoff.failures.saturating_add(1);
+        let delay = self
+            .config
+            .min_dial_backoff
+            .saturating_mul(2u32.saturating_pow(backoff.failures - 1))
+            .min(self.config.max_dial_backoff);
+        backoff.next_dial = Instant::now() + delay;
+        debug!(%peer_id, failures = backoff.failures, ?delay, "Backing off from dialing peer");
+    }
+
+    /// Returns a snapshot of the current state of the behaviour together with the counters of the events that
+    /// happened since it was created.
+    pub fn metrics(&self) -> Metrics {
+        let peer_contact_book = self.peer_contact_book.read();
+        Metrics {
+            connected_peers: self.connected_peers.len(),
+            peer_contacts: peer_contact_book.peer_ids().count(),
+            peer_contacts_with_required_services: peer_contact_book
+                .query(self.config.required_services)
+                .count(),
+            ..self.metrics
+        }
+    }
+
+    /// Returns the median offset in milliseconds of the connected peers' clocks to our local system clock, with each
+    /// offset clamped to [`Config::max_clock_offset`]. A positive offset means that the peers' clocks are ahead of ours.
+    /// This is the offset our clock is adjusted by.
+    pub fn clock_offset(&self) -> i64 {
+        let mut offsets: Vec<i64> = self.peer_clock_offsets.values().copied().collect();
+        if offsets.is_empty() {
+            return 0;
+        }
+        offsets.sort_unstable();
+        let mid = offsets.len() / 2;
+        if offsets.len() % 2 == 0 {
+            (offsets[mid - 1] + offsets[mid]) / 2
+        } else {
+            offsets[mid]
+        }
+    }
+
+    /// Adjusts our clock by the median offset of the connected peers' clocks, see [`Behaviour::clock_offset`]. The clock
+    /// keeps its offset once no peers are connected anymore.
+    fn adjust_clock(&self) {
+        if !self.peer_clock_offsets.is_empty() {
+            self.clock.set_offset(self.clock_offset());
+        }
+    }
+
+    /// Returns whether a peer providing the given services is useful to us, i.e. whether it provides the
+    /// [`Config::required_services`] or whether both we and the peer are validators. This matches the filter applied to
+    /// the contacts inserted into the peer contact book.
+    fn is_useful(&self, services: Services) -> bool {
+        services.contains(self.config.required_services)
+            || (services.contains(Services::VALIDATOR)
+                && self
+                    .peer_contact_book
+                    .read()
+                    .get_own_contact()
+                    .services()
+                    .contains(Services::VALIDATOR))
+    }
+
+    /// Returns the IP address of the given address, if it has one.
+    fn ip_of(address: &Multiaddr) -> Option<IpAddr> {
+        match address.iter().next() {
+            Some(Protocol::Ip4(ip)) => Some(IpAddr::V4(ip)),
+            Some(Protocol::Ip6(ip)) => Some(IpAddr::V6(ip)),
+            _ => None,
+        }
+    }
+
+    /// Returns the remote IP address of a connection that counts towards [`Config::max_connections_per_ip`], i.e.
+    /// that is neither a loopback address nor trusted.
+    fn limited_ip_of(&self, address: &Multiaddr) -> Option<IpAddr> {
+        Self::ip_of(address).filter(|ip| !ip.is_loopback() && !self.config.trusted_ips.contains(ip))
+    }
+
     /// Returns a reference to the peer contact book
     fn peer_contact_book(&self) -> Arc<RwLock<PeerContactBook>> {
         Arc::clone(&self.peer_contact_book)
@@ -171,12 +856,36 @@ impl NetworkBehaviour for Behaviour {
         _local_addr: &Multiaddr,
         remote_addr: &Multiaddr,
     ) -> Result<Handler, ConnectionDenied> {
+        if !self.config.accept_inbound {
+            return Err(ConnectionDenied::new(InboundConnectionsDisabled));
+        }
+
+        if self.is_banned(&peer) {
+            debug!(%peer, "Denying inbound connection, peer is banned");
+            return Err(ConnectionDenied::new(PeerBanned { peer_id: peer }));
+        }
+
+        if let (Some(limit), Some(ip)) = (
+            self.config.max_connections_per_ip,
+            self.limited_ip_of(remote_addr),
+        ) {
+            if self.connections_per_ip.get(&ip).copied().unwrap_or(0) >= limit {
+                debug!(%peer, %ip, limit, "Denying inbound connection, maximum number of connections per IP reached");
+                return Err(ConnectionDenied::new(ConnectionsPerIpExceeded {
+                    ip,
+                    limit,
+                }));
+            }
+        }
+
         Ok(Handler::new(
             peer,
-            self.config.clone(),
+            self.handler_config(),
             self.keypair.clone(),
+            Arc::clone(&self.clock),
             self.peer_contact_book(),
             remote_addr.clone(),
+            self.paused,
         ))
     }
 
@@ -188,12 +897,19 @@ impl NetworkBehaviour for Behaviour {
         _role_override: Endpoint,
         _port_use: PortUse,
     ) -> Result<Handler, ConnectionDenied> {
+        if self.is_banned(&peer) {
+            debug!(%peer, "Denying outbound connection, peer is banned");
+            return Err(ConnectionDenied::new(PeerBanned { peer_id: peer }));
+        }
+
         Ok(Handler::new(
             peer,
-            self.config.clone(),
+            self.handler_config(),
             self.keypair.clone(),
+            Arc::clone(&self.clock),
             self.peer_contact_book(),
             addr.clone(),
+            self.paused,
         ))
     }
 
@@ -209,6 +925,21 @@ impl NetworkBehaviour for Behaviour {
             Some(peer) => peer,
         };
 
+        if self.is_banned(&peer_id) {
+            debug!(%peer_id, "Denying to dial banned peer");
+            return Err(ConnectionDenied::new(PeerBanned { peer_id }));
+        }
+
+        if self.peer_contact_book.read().is_suppressed(&peer_id) {
+            debug!(%peer_id, "Denying to dial peer on a different network");
+            return Err(ConnectionDenied::new(GenesisMismatch { peer_id }));
+        }
+
+        if self.dial_backoff(&peer_id).is_some() {
+            trace!(%peer_id, "Omitting addresses of peer in dial backoff");
+            return Ok(vec![]);
+        }
+
         Ok(self
             .peer_contact_book
             .read()
@@ -217,6 +948,26 @@ impl NetworkBehaviour for Behaviour {
     }
 
     fn poll(&mut self, cx: &mut Context) -> Poll<DiscoveryToSwarm> {
+        // Scale the update interval to the number of connected peers
+        let update_interval = self.scaled_update_interval();
+        if update_interval != self.update_interval {
+            trace!(
+                ?update_interval,
+                num_peers = self.connected_peers.len(),
+                "Adjusting update interval"
+            );
+            self.update_interval = update_interval;
+            for (peer_id, connections) in &self.connected_peers {
+                for connection_id in connections {
+                    self.events.push_back(ToSwarm::NotifyHandler {
+                        peer_id: *peer_id,
+                        handler: NotifyHandler::One(*connection_id),
+                        event: HandlerInEvent::UpdateInterval(update_interval),
+                    });
+                }
+            }
+        }
+
         // Emit events
         if let Some(event) = self.events.pop_front() {
             return Poll::Ready(event);
@@ -226,9 +977,36 @@ impl NetworkBehaviour for Behaviour {
         match self.house_keeping_timer.poll_next_unpin(cx) {
             Poll::Ready(Some(_)) => {
                 trace!("Doing house-keeping in peer address book");
+                self.metrics.house_keeping_runs += 1;
+                self.expire_bans();
                 let mut peer_address_book = self.peer_contact_book.write();
                 peer_address_book.update_own_contact(&self.keypair);
-                peer_address_book.house_keeping();
+                let expired = peer_address_book.house_keeping();
+                let evicted = peer_address_book.evict_excess(
+                    self.config.max_peer_contacts,
+                    self.config.required_services,
+                    |peer_id| self.connected_peers.contains_key(peer_id),
+                );
+                drop(peer_address_book);
+
+                let removed = expired
+                    .into_iter()
+                    .map(|peer_id| (peer_id, PeerRemovalReason::Expired))
+                    .chain(
+                        evicted
+                            .into_iter()
+                            .map(|peer_id| (peer_id, PeerRemovalReason::Evicted)),
+                    );
+                for (peer_id, reason) in removed {
+                    self.events
+                        .push_back(ToSwarm::GenerateEvent(Event::PeerRemoved {
+                            peer_id,
+                            reason,
+                        }));
+                }
+                if let Some(event) = self.events.pop_front() {
+                    return Poll::Ready(event);
+                }
             }
             Poll::Ready(None) => unreachable!(),
             Poll::Pending => {}
@@ -241,14 +1019,75 @@ impl NetworkBehaviour for Behaviour {
         match event {
             FromSwarm::ConnectionClosed(ConnectionClosed {
                 peer_id,
+                connection_id,
+                endpoint,
                 remaining_established,
                 ..
             }) => {
+                if let Some(connections) = self.connected_peers.get_mut(&peer_id) {
+                    connections.remove(&connection_id);
+                }
+                self.outbound_connections.remove(&connection_id);
+                if let Some(ip) = self.limited_ip_of(endpoint.get_remote_address()) {
+                    if let Some(count) = self.connections_per_ip.get_mut(&ip) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.connections_per_ip.remove(&ip);
+                        }
+                    }
+                }
                 if remaining_established == 0 {
                     // There are no more remaining connections to this peer
                     self.connected_peers.remove(&peer_id);
+                    self.peer_latencies.remove(&peer_id);
+                    self.peer_services.remove(&peer_id);
+                    if self.peer_clock_offsets.remove(&peer_id).is_some() {
+                        self.adjust_clock();
+                    }
                 }
             }
+            FromSwarm::DialFailure(DialFailure { peer_id, error, .. }) => {
+                let (address, reason) = match error {
+                    // Not an actual failure, the dial was skipped on purpose.
+                    DialError::DialPeerConditionFalse(_) => return,
+                    DialError::Denied { .. } => (None, ConnectionFailReason::Denied),
+                    DialError::Transport(errors) => {
+                        // Only actual dial attempts count towards the backoff, e.g. not dials without addresses
+                        // because the peer is already in backoff.
+                        if let Some(peer_id) = peer_id {
+                            self.record_dial_failure(peer_id);
+                        }
+                        (
+                            errors.first().map(|(address, _)| address.clone()),
+                            ConnectionFailReason::DialFailure,
+                        )
+                    }
+                    _ => (None, ConnectionFailReason::DialFailure),
+                };
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::ConnectionFailed {
+                        peer_id,
+                        address,
+                        reason,
+                    }));
+            }
+            FromSwarm::ListenFailure(ListenFailure {
+                send_back_addr,
+                error,
+                peer_id,
+                ..
+            }) => {
+                let reason = match error {
+                    ListenError::Denied { .. } => ConnectionFailReason::Denied,
+                    _ => ConnectionFailReason::HandshakeFailure,
+                };
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::ConnectionFailed {
+                        peer_id,
+                        address: Some(send_back_addr.clone()),
+                        reason,
+                    }));
+            }
             FromSwarm::ConnectionEstablished(ConnectionEstablished {
                 peer_id,
                 connection_id,
@@ -256,12 +1095,22 @@ impl NetworkBehaviour for Behaviour {
                 failed_addresses,
                 other_established,
             }) => {
+                self.connected_peers
+                    .entry(peer_id)
+                    .or_default()
+                    .insert(connection_id);
+                if let Some(ip) = self.limited_ip_of(endpoint.get_remote_address()) {
+                    *self.connections_per_ip.entry(ip).or_insert(0) += 1;
+                }
+                if endpoint.is_dialer() {
+                    self.outbound_connections.insert(connection_id);
+                }
+                self.dial_backoffs.remove(&peer_id);
+
                 if other_established == 0 {
                     trace!(%peer_id, ?connection_id, ?endpoint, "Behaviour::inject_connection_established:");
 
                     // This is the first connection to this peer
-                    self.connected_peers.insert(peer_id);
-
                     if endpoint.is_listener() {
                         // Peer failed to connect with some of our own addresses, remove them from our own addresses
                         if !failed_addresses.is_empty() {
@@ -279,6 +1128,12 @@ impl NetworkBehaviour for Behaviour {
                     trace!(%peer_id, "Behaviour::inject_connection_established: Already have a connection established to peer");
                 }
             }
+            FromSwarm::ExternalAddrConfirmed(ExternalAddrConfirmed { addr }) => {
+                self.notify_external_address(ExternalAddrUpdate::Confirmed(addr.clone()));
+            }
+            FromSwarm::ExternalAddrExpired(ExternalAddrExpired { addr }) => {
+                self.notify_external_address(ExternalAddrUpdate::Expired(addr.clone()));
+            }
             _ => {}
         }
     }
@@ -286,7 +1141,7 @@ impl NetworkBehaviour for Behaviour {
     fn on_connection_handler_event(
         &mut self,
         peer_id: PeerId,
-        _connection: ConnectionId,
+        connection: ConnectionId,
         event: HandlerOutEvent,
     ) {
         trace!(%peer_id, ?event, "on_connection_handler_event");
@@ -295,7 +1150,24 @@ impl NetworkBehaviour for Behaviour {
             HandlerOutEvent::PeerExchangeEstablished {
                 peer_address,
                 peer_contact: signed_peer_contact,
+                clock_offset,
             } => {
+                self.metrics.peer_exchanges_established += 1;
+
+                // Don't keep connections we dialed to peers that can't serve what we need. Inbound connections are
+                // kept, since those peers connected to us to be served.
+                if self.outbound_connections.contains(&connection)
+                    && !self.is_useful(signed_peer_contact.inner.services)
+                {
+                    debug!(%peer_id, services = ?signed_peer_contact.inner.services, "Closing connection to peer not providing the required services");
+                    self.events
+                        .push_back(ToSwarm::GenerateEvent(Event::PeerUseless { peer_id }));
+                    self.events.push_back(ToSwarm::CloseConnection {
+                        peer_id,
+                        connection: CloseConnection::One(connection),
+                    });
+                    return;
+                }
                 if let Some(peer_contact) = self.peer_contact_book.read().get(&peer_id) {
                     self.events
                         .push_back(ToSwarm::GenerateEvent(Event::Established {
@@ -304,16 +1176,85 @@ impl NetworkBehaviour for Behaviour {
                             peer_contact: peer_contact.contact().clone(),
                         }));
                 }
+
+                self.peer_services
+                    .insert(peer_id, signed_peer_contact.inner.services);
+                // Peers running the initial version of the discovery protocol don't send their time.
+                let Some(clock_offset) = clock_offset else {
+                    return;
+                };
+                let max_offset = self.config.max_clock_offset.as_millis() as i64;
+                let system_offset =
+                    (clock_offset + self.clock.offset()).clamp(-max_offset, max_offset);
+                self.peer_clock_offsets.insert(peer_id, system_offset);
+                self.adjust_clock();
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::ClockOffset {
+                        peer_id,
+                        offset: clock_offset,
+                    }));
+                if clock_offset.unsigned_abs() as u128
+                    > self.config.clock_skew_threshold.as_millis()
+                {
+                    self.events
+                        .push_back(ToSwarm::GenerateEvent(Event::ClockSkewDetected {
+                            peer_id,
+                            offset: clock_offset,
+                        }));
+                }
             }
             HandlerOutEvent::ObservedAddress { observed_address } => {
+                if self.config.process_observed_addresses
+                    && self.config.emit_external_addr_candidates
+                {
+                    self.events
+                        .push_back(ToSwarm::NewExternalAddrCandidate(observed_address));
+                }
+            }
+            HandlerOutEvent::Update { peer_services } => {
+                self.metrics.updates_received += 1;
+                self.events.push_back(ToSwarm::GenerateEvent(Event::Update));
+
+                // Only peers we established the peer exchange with are tracked.
+                if let (Some(new), Some(old)) =
+                    (peer_services, self.peer_services.get_mut(&peer_id))
+                {
+                    if new != *old {
+                        let old = std::mem::replace(old, new);
+                        self.events
+                            .push_back(ToSwarm::GenerateEvent(Event::ServicesChanged {
+                                peer_id,
+                                old,
+                                new,
+                            }));
+                    }
+                }
+            }
+            HandlerOutEvent::GenesisMismatch { remote_genesis } => {
+                debug!(%peer_id, %remote_genesis, "Peer is on a different network");
+                self.metrics.connections_closed_on_error += 1;
+                self.peer_contact_book
+                    .write()
+                    .suppress(peer_id, self.config.genesis_mismatch_cooldown);
                 self.events
-                    .push_back(ToSwarm::NewExternalAddrCandidate(observed_address));
+                    .push_back(ToSwarm::GenerateEvent(Event::ConnectionFailed {
+                        peer_id: Some(peer_id),
+                        address: None,
+                        reason: ConnectionFailReason::GenesisMismatch,
+                    }));
+                self.events.push_back(ToSwarm::CloseConnection {
+                    peer_id,
+                    connection: CloseConnection::All,
+                });
+            }
+            HandlerOutEvent::Error(error) => {
+                match error {
+                    Error::TooFrequentUpdates { .. } => self.metrics.updates_rejected += 1,
+                    Error::UpdateBudgetExceeded { .. } => self.metrics.updates_rate_limited += 1,
+                    _ => {}
+                }
+                self.close_on_error(peer_id, error);
             }
-            HandlerOutEvent::Update => self.events.push_back(ToSwarm::GenerateEvent(Event::Update)),
-            HandlerOutEvent::Error(_) => self.events.push_back(ToSwarm::CloseConnection {
-                peer_id,
-                connection: CloseConnection::All,
-            }),
         }
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll, Waker},
@@ -20,9 +21,9 @@ use libp2p::{
 };
 use nimiq_hash::Blake2bHash;
 use nimiq_network_interface::peer_info::Services;
-use nimiq_serde::DeserializeError;
+use nimiq_serde::{DeserializeError, Serialize};
 use nimiq_time::{interval, Interval};
-use nimiq_utils::tagged_signing::TaggedKeyPair;
+use nimiq_utils::{tagged_signing::TaggedKeyPair, time::OffsetTime};
 use parking_lot::RwLock;
 use rand::{seq::IteratorRandom, thread_rng};
 use thiserror::Error;
@@ -31,21 +32,40 @@ use super::{
     behaviour::Config,
     message_codec::{MessageReader, MessageWriter},
     peer_contacts::{PeerContactBook, SignedPeerContact},
-    protocol::{ChallengeNonce, DiscoveryMessage, DiscoveryProtocol},
+    protocol::{ChallengeNonce, DiscoveryMessage, DiscoveryProtocol, HandshakeExtensions},
 };
 
+#[derive(Clone, Debug)]
+pub enum HandlerInEvent {
+    /// Stop sending periodic updates to the peer.
+    Pause,
+    /// Resume sending periodic updates to the peer.
+    Resume,
+    /// Filter the peer contacts received from the peer by these services from now on.
+    UpdateRequiredServices(Services),
+    /// Request updates from the peer in this interval from now on.
+    UpdateInterval(Duration),
+}
+
 #[derive(Debug)]
 pub enum HandlerOutEvent {
     /// List of observed addresses for the peer
-    ObservedAddress {
-        observed_address: Multiaddr,
-    },
+    ObservedAddress { observed_address: Multiaddr },
     /// A peer discovery exchange protocol with a peer has finalized
     PeerExchangeEstablished {
         peer_address: Multiaddr,
         peer_contact: SignedPeerContact,
+        /// Offset in milliseconds of the peer's clock to our clock, as measured during the handshake. `None` if the
+        /// peer doesn't send its time.
+        clock_offset: Option<i64>,
     },
-    Update,
+    /// The peer sent an update of peer contacts.
+    Update {
+        /// The services advertised by the peer in its own contact, if the update included it.
+        peer_services: Option<Services>,
+    },
+    /// The peer is on a network with a different genesis block.
+    GenesisMismatch { remote_genesis: Blake2bHash },
     /// An error occurred
     Error(Error),
 }
@@ -87,6 +107,16 @@ pub enum Error {
 
     #[error("Received update with too many peer contacts: {num_peer_contacts}")]
     UpdateLimitExceeded { num_peer_contacts: usize },
+
+    #[error(
+        "Received too many peer contacts within {}s: {num_peer_contacts} ({num_bytes} bytes)",
+        .window.as_secs()
+    )]
+    UpdateBudgetExceeded {
+        num_peer_contacts: usize,
+        num_bytes: usize,
+        window: Duration,
+    },
 }
 
 impl Error {
@@ -94,6 +124,19 @@ impl Error {
     pub fn connection_reset() -> Self {
         Self::Io(std::io::ErrorKind::ConnectionReset.into())
     }
+
+    /// Returns whether the error is caused by the peer violating the protocol, as opposed to e.g. connection
+    /// problems or timeouts that also occur with honest peers.
+    pub fn is_protocol_violation(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidPeerContactSignature { .. }
+                | Self::ChallengeResponseFailed
+                | Self::TooFrequentUpdates { .. }
+                | Self::UpdateLimitExceeded { .. }
+                | Self::UpdateBudgetExceeded { .. }
+        )
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -117,6 +160,94 @@ pub enum HandlerState {
     Established,
 }
 
+/// Keeps track of the peer contacts that were sent to a peer, such that subsequent updates only need to contain the
+/// peer contacts that changed since.
+#[derive(Debug, Default)]
+struct SentContacts {
+    /// Timestamps of the sent peer contacts by peer ID.
+    contacts: HashMap<PeerId, Option<u64>>,
+}
+
+impl SentContacts {
+    /// Records the given peer contacts as sent.
+    fn record(&mut self, peer_contacts: &[SignedPeerContact]) {
+        for peer_contact in peer_contacts {
+            self.contacts
+                .insert(peer_contact.peer_id(), peer_contact.inner.timestamp);
+        }
+    }
+
+    /// Selects the peer contacts to compare against the recorded ones: the recorded peer contacts that are still in
+    /// the contact book, our own contact and, if there is room left, randomly chosen other ones. Like the full updates,
+    /// the selection is limited to `limit` peer contacts.
+    fn select(
+        &self,
+        peer_contact_book: &PeerContactBook,
+        services: Services,
+        limit: usize,
+    ) -> Vec<SignedPeerContact> {
+        let own_contact = peer_contact_book.get_own_contact().signed().clone();
+        let own_peer_id = own_contact.peer_id();
+
+        let mut selected: Vec<SignedPeerContact> = self
+            .contacts
+            .keys()
+            .filter(|peer_id| **peer_id != own_peer_id)
+            .filter_map(|peer_id| peer_contact_book.get(peer_id))
+            .filter(|contact| !contact.is_seed() && contact.matches(services))
+            .take(limit.saturating_sub(1))
+            .map(|contact| contact.signed().clone())
+            .collect();
+        selected.push(own_contact);
+
+        let num_new = limit.saturating_sub(selected.len());
+        if num_new > 0 {
+            let mut rng = thread_rng();
+            let new_contacts = peer_contact_book
+                .query(services)
+                .filter(|contact| !self.contacts.contains_key(contact.peer_id()))
+                .choose_multiple(&mut rng, num_new);
+            selected.extend(new_contacts.iter().map(|contact| contact.signed().clone()));
+        }
+
+        selected
+    }
+
+    /// Computes the peer contacts that are new or changed and the peers that are no longer present in
+    /// `current_contacts` compared to the recorded ones. Returns `None` if the delta is larger than `limit`, in which
+    /// case nothing is recorded. Otherwise the delta is recorded as sent.
+    fn delta(
+        &mut self,
+        current_contacts: Vec<SignedPeerContact>,
+        limit: usize,
+    ) -> Option<(Vec<SignedPeerContact>, Vec<PeerId>)> {
+        let current_peer_ids: HashSet<PeerId> =
+            current_contacts.iter().map(|c| c.peer_id()).collect();
+
+        let added: Vec<SignedPeerContact> = current_contacts
+            .into_iter()
+            .filter(|c| self.contacts.get(&c.peer_id()) != Some(&c.inner.timestamp))
+            .collect();
+        let removed: Vec<PeerId> = self
+            .contacts
+            .keys()
+            .filter(|peer_id| !current_peer_ids.contains(peer_id))
+            .cloned()
+            .collect();
+
+        if added.len() + removed.len() > limit {
+            return None;
+        }
+
+        self.record(&added);
+        for peer_id in &removed {
+            self.contacts.remove(peer_id);
+        }
+
+        Some((added, removed))
+    }
+}
+
 pub struct Handler {
     /// Peer ID of the peer connected to us in this connection
     peer_id: PeerId,
@@ -127,6 +258,9 @@ pub struct Handler {
     /// Identity keypair for this node.
     keypair: Keypair,
 
+    /// The clock whose time is sent to the peer during the handshake.
+    clock: Arc<OffsetTime>,
+
     /// The peer contact book
     peer_contact_book: Arc<RwLock<PeerContactBook>>,
 
@@ -145,15 +279,46 @@ pub struct Handler {
     /// Services filter sent to us by this peer.
     services_filter: Services,
 
+    /// Offset in milliseconds of the peer's clock to our clock, as measured when receiving its handshake.
+    clock_offset: Option<i64>,
+
     /// The limit for peer updates sent to us by this peer.
     peer_list_limit: Option<u16>,
 
+    /// Whether sending periodic updates to this peer is paused.
+    paused: bool,
+
+    /// The peer contacts sent to this peer so far. This is only tracked if both sides support delta updates.
+    sent_contacts: Option<SentContacts>,
+
+    /// Peer IDs of the contacts this peer sent us and still advertises. Retracting a contact in a delta update only
+    /// removes it from this set, never from the peer contact book.
+    received_contacts: HashSet<PeerId>,
+
     /// The interval at which the other peer wants to be updates.
     periodic_update_interval: Option<Interval>,
 
+    /// Whether the other peer accepts changes of the interval in which we want to be updated after the handshake.
+    peer_accepts_update_interval: bool,
+
+    /// Whether [`Config::update_interval`] changed after it was sent to the other peer in the handshake.
+    update_interval_changed: bool,
+
     /// Time when we last received an update from the other peer.
     last_update_time: Option<Instant>,
 
+    /// Number of peer contacts and bytes received in recent updates from the other peer, together with the time they
+    /// were received, see [`Config::max_recv_contacts`] and [`Config::max_recv_bytes`].
+    recv_updates: VecDeque<(Instant, usize, usize)>,
+
+    /// Time when we last received an update containing peer contacts other than the peer's own. The connection is
+    /// established at the latest.
+    last_useful_update_time: Instant,
+
+    /// Future that fires when the connection becomes idle, i.e. when no useful update was received within
+    /// [`Config::keep_alive_idle_timeout`].
+    idle_timeout: Option<Delay>,
+
     /// The inbound message stream.
     inbound: Option<MessageReader<Stream, DiscoveryMessage>>,
 
@@ -170,22 +335,35 @@ impl Handler {
         peer_id: PeerId,
         config: Config,
         keypair: Keypair,
+        clock: Arc<OffsetTime>,
         peer_contact_book: Arc<RwLock<PeerContactBook>>,
         peer_address: Multiaddr,
+        paused: bool,
     ) -> Self {
+        let idle_timeout = config.keep_alive_idle_timeout.map(Delay::new);
         Self {
             peer_id,
             config,
             keypair,
+            clock,
             peer_contact_book,
             peer_address,
             challenge_nonce: ChallengeNonce::generate(),
             state: HandlerState::Init,
             state_timeout: None,
             services_filter: Services::empty(),
+            clock_offset: None,
             peer_list_limit: None,
+            paused,
+            sent_contacts: None,
+            received_contacts: HashSet::new(),
             periodic_update_interval: None,
+            peer_accepts_update_interval: false,
+            update_interval_changed: false,
             last_update_time: None,
+            recv_updates: VecDeque::new(),
+            last_useful_update_time: Instant::now(),
+            idle_timeout,
             inbound: None,
             outbound: None,
             waker: None,
@@ -225,6 +403,137 @@ impl Handler {
             .collect()
     }
 
+    /// Creates the next periodic update for this peer. If both sides support delta updates, only the peer contacts
+    /// that changed since the previous update are sent, unless there are more changes than the peer accepts.
+    fn next_update(&mut self) -> Option<DiscoveryMessage> {
+        let peer_contact_book = self.peer_contact_book.read();
+        let limit = self.peer_list_limit.unwrap() as usize;
+
+        if let Some(sent_contacts) = self.sent_contacts.as_mut() {
+            let current_contacts =
+                sent_contacts.select(&peer_contact_book, self.services_filter, limit);
+
+            if let Some((added, removed)) = sent_contacts.delta(current_contacts, limit) {
+                if added.is_empty() && removed.is_empty() {
+                    return None;
+                }
+                return Some(DiscoveryMessage::PeerAddressesDelta { added, removed });
+            }
+        }
+
+        let mut peer_contacts = self.get_peer_contacts(&peer_contact_book, limit - 1);
+        // Always include our own contact for updates
+        peer_contacts.push(peer_contact_book.get_own_contact().signed().clone());
+
+        // The full update replaces the base for subsequent delta updates.
+        if let Some(sent_contacts) = self.sent_contacts.as_mut() {
+            *sent_contacts = SentContacts::default();
+            sent_contacts.record(&peer_contacts);
+        }
+
+        Some(DiscoveryMessage::PeerAddresses { peer_contacts })
+    }
+
+    /// Returns the services the peer advertises in its own contact, if it is among the given peer contacts.
+    fn own_services_in(&self, peer_contacts: &[SignedPeerContact]) -> Option<Services> {
+        peer_contacts
+            .iter()
+            .find(|contact| contact.peer_id() == self.peer_id)
+            .map(|contact| contact.inner.services)
+    }
+
+    /// Checks that a received update is not too frequent, not too large and that the peer contacts in it verify.
+    fn check_update(
+        &mut self,
+        peer_contacts: &[SignedPeerContact],
+        num_removed: usize,
+        num_bytes: usize,
+    ) -> Result<(), Error> {
+        // Check if the update is actually not too frequent
+        let now = Instant::now();
+        if let Some(last_update_time) = self.last_update_time {
+            let interval = now - last_update_time;
+            if interval < self.config.min_recv_update_interval {
+                // TODO: Should we just close, or ban?
+                return Err(Error::TooFrequentUpdates { interval });
+            }
+        }
+        self.last_update_time = Some(now);
+
+        // Check if the update is not too large and if the peer contacts verify
+        let num_peer_contacts = peer_contacts.len() + num_removed;
+        if num_peer_contacts > self.config.update_limit as usize {
+            return Err(Error::UpdateLimitExceeded { num_peer_contacts });
+        }
+        self.record_recv_update(now, num_peer_contacts, num_bytes)?;
+        for peer_contact in peer_contacts {
+            if !peer_contact.verify() {
+                return Err(Error::InvalidPeerContactSignature {
+                    peer_contact: peer_contact.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the number of peer contacts and bytes received in an update and checks that the totals received within
+    /// [`Config::recv_contacts_window`] don't exceed [`Config::max_recv_contacts`] and [`Config::max_recv_bytes`].
+    fn record_recv_update(
+        &mut self,
+        now: Instant,
+        num_peer_contacts: usize,
+        num_bytes: usize,
+    ) -> Result<(), Error> {
+        if self.config.max_recv_contacts.is_none() && self.config.max_recv_bytes.is_none() {
+            return Ok(());
+        }
+
+        while self.recv_updates.front().is_some_and(|(time, _, _)| {
+            now.duration_since(*time) > self.config.recv_contacts_window
+        }) {
+            self.recv_updates.pop_front();
+        }
+        self.recv_updates
+            .push_back((now, num_peer_contacts, num_bytes));
+
+        let (total_contacts, total_bytes) = self
+            .recv_updates
+            .iter()
+            .fold((0, 0), |(contacts, bytes), (_, c, b)| {
+                (contacts + c, bytes + b)
+            });
+        let exceeds = |max: Option<usize>, total| max.is_some_and(|max| total > max);
+        if exceeds(self.config.max_recv_contacts, total_contacts)
+            || exceeds(self.config.max_recv_bytes, total_bytes)
+        {
+            return Err(Error::UpdateBudgetExceeded {
+                num_peer_contacts: total_contacts,
+                num_bytes: total_bytes,
+                window: self.config.recv_contacts_window,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Sets the interval in seconds in which the other peer wants to be updated, but not faster than
+    /// [`Config::min_send_update_interval`].
+    fn set_periodic_update_interval(&mut self, update_interval: u64) {
+        let update_interval = update_interval
+            .max(self.config.min_send_update_interval.as_secs())
+            .max(1);
+        self.periodic_update_interval = Some(interval(Duration::from_secs(update_interval)));
+    }
+
+    /// Records that the peer sent us a useful update, postponing the point at which the connection becomes idle.
+    fn record_useful_update(&mut self) {
+        self.last_useful_update_time = Instant::now();
+        if let Some(timeout) = self.config.keep_alive_idle_timeout {
+            self.idle_timeout = Some(Delay::new(timeout));
+        }
+    }
+
     /// Checks if the handler is ready to start the discovery protocol.
     /// This basically checks that:
     /// - Both inbound and outbound are available
@@ -245,7 +554,7 @@ impl Handler {
 }
 
 impl ConnectionHandler for Handler {
-    type FromBehaviour = ();
+    type FromBehaviour = HandlerInEvent;
     type ToBehaviour = HandlerOutEvent;
     type InboundProtocol = DiscoveryProtocol;
     type OutboundProtocol = DiscoveryProtocol;
@@ -272,7 +581,14 @@ impl ConnectionHandler for Handler {
                 if self.inbound.is_some() {
                     panic!("Inbound already connected");
                 }
-                self.inbound = Some(protocol);
+                self.inbound = Some(if self.config.keep_alive_idle_timeout.is_some() {
+                    // Let the idle timeout rather than the open substream decide whether the connection is kept alive.
+                    let mut stream = protocol.into_inner();
+                    stream.ignore_for_keep_alive();
+                    MessageReader::new(stream)
+                } else {
+                    protocol
+                });
                 self.check_initialized();
             }
             ConnectionEvent::FullyNegotiatedOutbound(FullyNegotiatedOutbound {
@@ -284,7 +600,13 @@ impl ConnectionHandler for Handler {
                 if self.state != HandlerState::OpenSubstream {
                     panic!("Unexpected outbound");
                 }
-                self.outbound = Some(protocol);
+                self.outbound = Some(if self.config.keep_alive_idle_timeout.is_some() {
+                    let mut stream = protocol.into_inner();
+                    stream.ignore_for_keep_alive();
+                    MessageWriter::new(stream)
+                } else {
+                    protocol
+                });
                 self.check_initialized();
             }
             ConnectionEvent::DialUpgradeError(DialUpgradeError { error, .. }) => {
@@ -294,10 +616,37 @@ impl ConnectionHandler for Handler {
         }
     }
 
-    fn on_behaviour_event(&mut self, _event: ()) {}
+    fn on_behaviour_event(&mut self, event: HandlerInEvent) {
+        match event {
+            HandlerInEvent::Pause => self.paused = true,
+            HandlerInEvent::Resume => self.paused = false,
+            HandlerInEvent::UpdateRequiredServices(services) => {
+                self.config.required_services = services;
+            }
+            HandlerInEvent::UpdateInterval(update_interval) => {
+                if update_interval.as_secs() == self.config.update_interval.as_secs() {
+                    return;
+                }
+                self.config.update_interval = update_interval;
+                // Before the handshake ack is sent, the peer receives the new interval with it.
+                if matches!(
+                    self.state,
+                    HandlerState::ReceiveHandshakeAck | HandlerState::Established
+                ) {
+                    self.update_interval_changed = true;
+                }
+            }
+        }
+    }
 
     fn connection_keep_alive(&self) -> bool {
-        self.config.keep_alive
+        if !self.config.keep_alive {
+            return false;
+        }
+        match self.config.keep_alive_idle_timeout {
+            Some(timeout) => self.last_useful_update_time.elapsed() < timeout,
+            None => true,
+        }
     }
 
     fn poll(
@@ -314,6 +663,14 @@ impl ConnectionHandler for Handler {
                 }
             }
 
+            // Check if the connection became idle. Nothing needs to be done here apart from polling the timer, since
+            // the swarm checks whether the connection should be kept alive after every poll.
+            if let Some(ref mut idle_timeout) = self.idle_timeout {
+                if idle_timeout.poll_unpin(cx).is_ready() {
+                    self.idle_timeout = None;
+                }
+            }
+
             // Send message
             // This should be done first, so we can flush the outbound sink's buffer.
             if let Some(outbound) = self.outbound.as_mut() {
@@ -363,6 +720,11 @@ impl ConnectionHandler for Handler {
                         genesis_hash: self.config.genesis_hash.clone(),
                         limit: self.config.update_limit,
                         services: self.config.required_services,
+                        extensions: Some(HandshakeExtensions {
+                            delta_updates: self.config.enable_delta_updates,
+                            timestamp: self.clock.now(),
+                            update_interval_changes: true,
+                        }),
                     };
 
                     if let Err(e) = self.send(&msg) {
@@ -386,17 +748,15 @@ impl ConnectionHandler for Handler {
                                     genesis_hash,
                                     limit,
                                     services,
+                                    extensions,
                                 } => {
                                     // Check if the received genesis hash matches.
                                     if genesis_hash != self.config.genesis_hash {
                                         return Poll::Ready(
                                             ConnectionHandlerEvent::NotifyBehaviour(
-                                                HandlerOutEvent::Error(
-                                                    Error::GenesisHashMismatch {
-                                                        expected: self.config.genesis_hash.clone(),
-                                                        received: genesis_hash,
-                                                    },
-                                                ),
+                                                HandlerOutEvent::GenesisMismatch {
+                                                    remote_genesis: genesis_hash,
+                                                },
                                             ),
                                         );
                                     }
@@ -409,7 +769,32 @@ impl ConnectionHandler for Handler {
                                     self.peer_list_limit = Some(limit);
                                     self.services_filter = services;
 
+                                    // Measure the offset of the peer's clock to our clock. The transmission delay
+                                    // of the handshake is neglected. Peers running the initial protocol version
+                                    // don't send their time.
+                                    self.clock_offset = extensions.as_ref().map(|extensions| {
+                                        extensions.timestamp as i64 - self.clock.now() as i64
+                                    });
+                                    let delta_updates = extensions
+                                        .as_ref()
+                                        .is_some_and(|extensions| extensions.delta_updates);
+                                    self.peer_accepts_update_interval =
+                                        extensions.as_ref().is_some_and(|extensions| {
+                                            extensions.update_interval_changes
+                                        });
+
                                     let peer_contact_book = self.peer_contact_book.read();
+                                    let peer_contacts = self.get_peer_contacts(
+                                        &peer_contact_book,
+                                        self.peer_list_limit.unwrap() as usize,
+                                    );
+
+                                    // The initial set of peer contacts is the base for delta updates.
+                                    if self.config.enable_delta_updates && delta_updates {
+                                        let mut sent_contacts = SentContacts::default();
+                                        sent_contacts.record(&peer_contacts);
+                                        self.sent_contacts = Some(sent_contacts);
+                                    }
 
                                     let msg = DiscoveryMessage::HandshakeAck {
                                         peer_contact: peer_contact_book
@@ -420,10 +805,7 @@ impl ConnectionHandler for Handler {
                                         update_interval: Some(
                                             self.config.update_interval.as_secs(),
                                         ),
-                                        peer_contacts: self.get_peer_contacts(
-                                            &peer_contact_book,
-                                            self.peer_list_limit.unwrap() as usize,
-                                        ),
+                                        peer_contacts,
                                     };
 
                                     drop(peer_contact_book);
@@ -549,6 +931,9 @@ impl ConnectionHandler for Handler {
                                     peer_contact_book.insert(peer_contact.clone());
 
                                     // Insert the peer's contacts (filtered) into my contact book
+                                    self.received_contacts.extend(
+                                        peer_contacts.iter().map(|contact| contact.peer_id()),
+                                    );
                                     peer_contact_book.insert_all_filtered(
                                         peer_contacts,
                                         self.config.required_services,
@@ -558,14 +943,8 @@ impl ConnectionHandler for Handler {
                                     drop(peer_contact_book);
 
                                     // Timer for periodic updates
-                                    if let Some(mut update_interval) = update_interval {
-                                        let min_secs =
-                                            self.config.min_send_update_interval.as_secs();
-                                        if update_interval < min_secs {
-                                            update_interval = min_secs;
-                                        }
-                                        self.periodic_update_interval =
-                                            Some(interval(Duration::from_secs(update_interval)));
+                                    if let Some(update_interval) = update_interval {
+                                        self.set_periodic_update_interval(update_interval);
                                     }
 
                                     // Switch to established state
@@ -577,6 +956,7 @@ impl ConnectionHandler for Handler {
                                         HandlerOutEvent::PeerExchangeEstablished {
                                             peer_contact,
                                             peer_address: self.peer_address.clone(),
+                                            clock_offset: self.clock_offset,
                                         },
                                     ));
                                 }
@@ -606,55 +986,48 @@ impl ConnectionHandler for Handler {
                 }
 
                 HandlerState::Established => {
+                    // Tell the peer about a change of the interval in which we want to be updated.
+                    if self.update_interval_changed {
+                        self.update_interval_changed = false;
+                        if self.peer_accepts_update_interval {
+                            let msg = DiscoveryMessage::UpdateInterval {
+                                update_interval: self.config.update_interval.as_secs(),
+                            };
+                            if let Err(e) = self.send(&msg) {
+                                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                                    HandlerOutEvent::Error(e.into()),
+                                ));
+                            }
+                        }
+                    }
+
                     // Check for incoming updates.
                     match self.receive(cx) {
                         Poll::Ready(Some(Ok(message))) => {
+                            let num_bytes = message.serialized_size();
                             match message {
                                 DiscoveryMessage::PeerAddresses { peer_contacts } => {
-                                    // Check if the update is actually not too frequent
-                                    let now = Instant::now();
-                                    if let Some(last_update_time) = self.last_update_time {
-                                        let interval = now - last_update_time;
-                                        if interval < self.config.min_recv_update_interval {
-                                            // TODO: Should we just close, or ban?
-                                            return Poll::Ready(
-                                                ConnectionHandlerEvent::NotifyBehaviour(
-                                                    HandlerOutEvent::Error(
-                                                        Error::TooFrequentUpdates { interval },
-                                                    ),
-                                                ),
-                                            );
-                                        }
-                                    }
-                                    self.last_update_time = Some(now);
-
-                                    // Check if the update is not too large and if the peer contacts verify
-                                    if peer_contacts.len() > self.config.update_limit as usize {
+                                    if let Err(e) = self.check_update(&peer_contacts, 0, num_bytes)
+                                    {
                                         return Poll::Ready(
                                             ConnectionHandlerEvent::NotifyBehaviour(
-                                                HandlerOutEvent::Error(
-                                                    Error::UpdateLimitExceeded {
-                                                        num_peer_contacts: peer_contacts.len(),
-                                                    },
-                                                ),
+                                                HandlerOutEvent::Error(e),
                                             ),
                                         );
                                     }
-                                    for peer_contact in &peer_contacts {
-                                        if !peer_contact.verify() {
-                                            return Poll::Ready(
-                                                ConnectionHandlerEvent::NotifyBehaviour(
-                                                    HandlerOutEvent::Error(
-                                                        Error::InvalidPeerContactSignature {
-                                                            peer_contact: peer_contact.clone(),
-                                                        },
-                                                    ),
-                                                ),
-                                            );
-                                        }
+
+                                    if peer_contacts
+                                        .iter()
+                                        .any(|contact| contact.peer_id() != self.peer_id)
+                                    {
+                                        self.record_useful_update();
                                     }
+                                    let peer_services = self.own_services_in(&peer_contacts);
 
                                     // Insert the new peer contacts into the peer contact book.
+                                    self.received_contacts.extend(
+                                        peer_contacts.iter().map(|contact| contact.peer_id()),
+                                    );
                                     self.peer_contact_book.write().insert_all_filtered(
                                         peer_contacts,
                                         self.config.required_services,
@@ -662,10 +1035,50 @@ impl ConnectionHandler for Handler {
                                     );
 
                                     return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                                        HandlerOutEvent::Update,
+                                        HandlerOutEvent::Update { peer_services },
+                                    ));
+                                }
+
+                                DiscoveryMessage::PeerAddressesDelta { added, removed }
+                                    if self.config.enable_delta_updates =>
+                                {
+                                    if let Err(e) =
+                                        self.check_update(&added, removed.len(), num_bytes)
+                                    {
+                                        return Poll::Ready(
+                                            ConnectionHandlerEvent::NotifyBehaviour(
+                                                HandlerOutEvent::Error(e),
+                                            ),
+                                        );
+                                    }
+
+                                    if !added.is_empty() || !removed.is_empty() {
+                                        self.record_useful_update();
+                                    }
+                                    let peer_services = self.own_services_in(&added);
+
+                                    // Removed contacts are only no longer advertised by this peer, so we just forget
+                                    // that it sent them. The contacts themselves expire in the house-keeping.
+                                    for peer_id in &removed {
+                                        self.received_contacts.remove(peer_id);
+                                    }
+                                    self.received_contacts
+                                        .extend(added.iter().map(|contact| contact.peer_id()));
+                                    self.peer_contact_book.write().insert_all_filtered(
+                                        added,
+                                        self.config.required_services,
+                                        self.config.only_secure_ws_connections,
+                                    );
+
+                                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                                        HandlerOutEvent::Update { peer_services },
                                     ));
                                 }
 
+                                DiscoveryMessage::UpdateInterval { update_interval } => {
+                                    self.set_periodic_update_interval(update_interval);
+                                }
+
                                 _ => {
                                     return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                                         HandlerOutEvent::Error(Error::UnexpectedMessage {
@@ -693,21 +1106,13 @@ impl ConnectionHandler for Handler {
                     if let Some(timer) = self.periodic_update_interval.as_mut() {
                         match timer.poll_next_unpin(cx) {
                             Poll::Ready(Some(_instant)) => {
-                                let peer_contacts = {
-                                    let peer_contact_book = &self.peer_contact_book.read();
-                                    let mut peer_contacts = self.get_peer_contacts(
-                                        peer_contact_book,
-                                        self.peer_list_limit.unwrap() as usize - 1,
-                                    );
-                                    // Always include our own contact for updates
-                                    peer_contacts
-                                        .push(peer_contact_book.get_own_contact().signed().clone());
-                                    peer_contacts
+                                let msg = if self.paused {
+                                    None
+                                } else {
+                                    self.next_update()
                                 };
 
-                                if !peer_contacts.is_empty() {
-                                    let msg = DiscoveryMessage::PeerAddresses { peer_contacts };
-
+                                if let Some(msg) = msg {
                                     if let Err(e) = self.send(&msg) {
                                         return Poll::Ready(
                                             ConnectionHandlerEvent::NotifyBehaviour(
@@ -729,3 +1134,121 @@ impl ConnectionHandler for Handler {
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use libp2p::identity::Keypair;
+    use nimiq_network_interface::peer_info::Services;
+    use nimiq_test_log::test;
+
+    use super::SentContacts;
+    use crate::discovery::peer_contacts::{PeerContact, PeerContactBook, SignedPeerContact};
+
+    fn signed_contact(keypair: &Keypair, timestamp: u64) -> SignedPeerContact {
+        PeerContact {
+            addresses: vec![],
+            public_key: keypair.public(),
+            services: Services::FULL_BLOCKS,
+            timestamp: Some(timestamp),
+        }
+        .sign(keypair)
+    }
+
+    #[test]
+    fn delta_only_contains_changed_contacts() {
+        let unchanged = Keypair::generate_ed25519();
+        let changed = Keypair::generate_ed25519();
+        let removed = Keypair::generate_ed25519();
+        let new = Keypair::generate_ed25519();
+
+        let mut sent_contacts = SentContacts::default();
+        sent_contacts.record(&[
+            signed_contact(&unchanged, 1),
+            signed_contact(&changed, 1),
+            signed_contact(&removed, 1),
+        ]);
+
+        let (added, removed_peers) = sent_contacts
+            .delta(
+                vec![
+                    signed_contact(&unchanged, 1),
+                    signed_contact(&changed, 2),
+                    signed_contact(&new, 2),
+                ],
+                64,
+            )
+            .unwrap();
+
+        let mut added_peers: Vec<_> = added.iter().map(|c| c.peer_id()).collect();
+        added_peers.sort();
+        let mut expected_peers = vec![changed.public().to_peer_id(), new.public().to_peer_id()];
+        expected_peers.sort();
+        assert_eq!(added_peers, expected_peers);
+        assert_eq!(removed_peers, vec![removed.public().to_peer_id()]);
+
+        // Nothing changed since the last delta
+        let (added, removed_peers) = sent_contacts
+            .delta(
+                vec![
+                    signed_contact(&unchanged, 1),
+                    signed_contact(&changed, 2),
+                    signed_contact(&new, 2),
+                ],
+                64,
+            )
+            .unwrap();
+        assert!(added.is_empty());
+        assert!(removed_peers.is_empty());
+    }
+
+    #[test]
+    fn selection_is_based_on_sent_contacts() {
+        let own = Keypair::generate_ed25519();
+        let mut peer_contact_book =
+            PeerContactBook::new(signed_contact(&own, 1), false, true, false);
+        let keypairs: Vec<_> = (0..10).map(|_| Keypair::generate_ed25519()).collect();
+        for keypair in &keypairs {
+            peer_contact_book.insert(signed_contact(keypair, 1));
+        }
+
+        let mut sent_contacts = SentContacts::default();
+        sent_contacts.record(&[
+            signed_contact(&own, 1),
+            signed_contact(&keypairs[0], 1),
+            signed_contact(&keypairs[1], 1),
+            signed_contact(&keypairs[2], 1),
+        ]);
+        peer_contact_book.remove(&keypairs[2].public().to_peer_id());
+
+        let selected: Vec<_> = sent_contacts
+            .select(&peer_contact_book, Services::FULL_BLOCKS, 5)
+            .iter()
+            .map(|contact| contact.peer_id())
+            .collect();
+
+        // The sent contacts still in the book, our own contact and two new ones fill the limit
+        assert_eq!(selected.len(), 5);
+        assert!(selected.contains(&own.public().to_peer_id()));
+        assert!(selected.contains(&keypairs[0].public().to_peer_id()));
+        assert!(selected.contains(&keypairs[1].public().to_peer_id()));
+        assert!(!selected.contains(&keypairs[2].public().to_peer_id()));
+
+        // The resulting delta stays within the limit
+        let selected = sent_contacts.select(&peer_contact_book, Services::FULL_BLOCKS, 5);
+        let (added, removed) = sent_contacts.delta(selected, 5).unwrap();
+        assert_eq!(added.len(), 2);
+        assert_eq!(removed, vec![keypairs[2].public().to_peer_id()]);
+    }
+
+    #[test]
+    fn delta_exceeding_limit_falls_back() {
+        let mut sent_contacts = SentContacts::default();
+        let contacts: Vec<_> = (0..3)
+            .map(|_| signed_contact(&Keypair::generate_ed25519(), 1))
+            .collect();
+
+        assert!(sent_contacts.delta(contacts.clone(), 2).is_none());
+        // Nothing was recorded, so the full delta is still pending
+        assert_eq!(sent_contacts.delta(contacts, 3).unwrap().0.len(), 3);
+    }
+}
@@ -4,5 +4,7 @@ pub mod message_codec;
 pub mod peer_contacts;
 pub mod protocol;
 
-pub use behaviour::{Behaviour, Config, Event};
+pub use behaviour::{
+    Behaviour, Config, ConnectionFailReason, Event, ExternalAddrUpdate, Metrics, PeerRemovalReason,
+};
 pub use handler::Error;
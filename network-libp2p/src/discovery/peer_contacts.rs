@@ -4,7 +4,7 @@ use std::{
     time::Duration,
 };
 
-use instant::SystemTime;
+use instant::{Instant, SystemTime};
 use libp2p::{
     gossipsub,
     identity::{Keypair, PublicKey},
@@ -184,6 +184,8 @@ impl SignedPeerContact {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct PeerContactMeta {
     score: f64,
+    /// Average round-trip time to the peer, if we were connected to it.
+    latency: Option<Duration>,
 }
 
 /// This encapsulates a peer contact (signed), but also pre-computes frequently used values such as `peer_id` and
@@ -207,7 +209,10 @@ impl From<SignedPeerContact> for PeerContactInfo {
         Self {
             peer_id,
             contact,
-            meta: RwLock::new(PeerContactMeta { score: 0. }),
+            meta: RwLock::new(PeerContactMeta {
+                score: 0.,
+                latency: None,
+            }),
         }
     }
 }
@@ -271,6 +276,16 @@ impl PeerContactInfo {
     pub fn set_score(&self, score: f64) {
         self.meta.write().score = score;
     }
+
+    /// Gets the average round-trip time to the peer, if we were connected to it
+    pub fn get_latency(&self) -> Option<Duration> {
+        self.meta.read().latency
+    }
+
+    /// Sets the average round-trip time to the peer
+    pub fn set_latency(&self, latency: Duration) {
+        self.meta.write().latency = Some(latency);
+    }
 }
 
 /// Main structure that holds the peer information that has been obtained or
@@ -292,6 +307,9 @@ pub struct PeerContactBook {
     allow_loopback_addresses: bool,
     /// Flag to indicate whether to support memory transport addresses
     memory_transport: bool,
+    /// Peers that are suppressed until the given time, e.g. because they are on a different network. Their contacts
+    /// are neither stored nor returned.
+    suppressed_peers: HashMap<PeerId, Instant>,
 }
 
 impl PeerContactBook {
@@ -313,13 +331,14 @@ impl PeerContactBook {
             only_secure_addresses,
             allow_loopback_addresses,
             memory_transport,
+            suppressed_peers: HashMap::new(),
         }
     }
 
     /// Insert a peer contact or update an existing one
     pub fn insert(&mut self, contact: SignedPeerContact) {
         // Don't insert our own contact into our peer contacts
-        if contact.peer_id() == self.own_peer_id {
+        if contact.peer_id() == self.own_peer_id || self.is_suppressed(&contact.peer_id()) {
             return;
         }
 
@@ -361,6 +380,9 @@ impl PeerContactBook {
         services_filter: Services,
         only_secure_ws_connections: bool,
     ) {
+        if self.is_suppressed(&contact.peer_id()) {
+            return;
+        }
         let info = PeerContactInfo::from(contact);
 
         // A peer is interesting to us in two cases:
@@ -415,6 +437,26 @@ impl PeerContactBook {
         }
     }
 
+    /// Removes the peer contact for the given peer_id if it exists.
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.peer_contacts.remove(peer_id);
+    }
+
+    /// Removes the peer contact for the given peer_id and ignores any contacts of the peer for the given duration,
+    /// such that the peer is not dialed again in the meantime.
+    pub fn suppress(&mut self, peer_id: PeerId, duration: Duration) {
+        self.peer_contacts.remove(&peer_id);
+        self.suppressed_peers
+            .insert(peer_id, Instant::now() + duration);
+    }
+
+    /// Returns whether the given peer is currently suppressed, see [`Self::suppress`].
+    pub fn is_suppressed(&self, peer_id: &PeerId) -> bool {
+        self.suppressed_peers
+            .get(peer_id)
+            .is_some_and(|until| *until > Instant::now())
+    }
+
     /// Gets a peer contact if it exists given its peer_id.
     /// If the peer_id is not found, `None` is returned.
     pub fn get(&self, peer_id: &PeerId) -> Option<Arc<PeerContactInfo>> {
@@ -460,6 +502,11 @@ impl PeerContactBook {
             .collect()
     }
 
+    /// Returns the peer IDs of all peers in the contact book.
+    pub fn peer_ids(&self) -> impl Iterator<Item = &PeerId> {
+        self.peer_contacts.keys()
+    }
+
     /// Gets a set of peer contacts given a services filter.
     /// Every peer contact that matches such services will be returned.
     pub fn query(&self, services: Services) -> impl Iterator<Item = Arc<PeerContactInfo>> + '_ {
@@ -530,8 +577,13 @@ impl PeerContactBook {
     }
 
     /// Removes peer contacts that have already exceeded the maximum age as
-    /// defined in `MAX_PEER_AGE`.
-    pub fn house_keeping(&mut self) {
+    /// defined in `MAX_PEER_AGE`. Returns the peer IDs of the removed contacts.
+    /// Suppressions that expired are lifted as well.
+    pub fn house_keeping(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+        self.suppressed_peers.retain(|_, until| *until > now);
+
+        let mut removed = vec![];
         if let Ok(unix_time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
             let delete_peers = self
                 .peer_contacts
@@ -552,8 +604,51 @@ impl PeerContactBook {
 
             for peer_id in delete_peers {
                 self.peer_contacts.remove(&peer_id);
+                removed.push(peer_id);
             }
         }
+        removed
+    }
+
+    /// Removes peer contacts until at most `max_peer_contacts` remain. Contacts that don't provide the
+    /// `required_services` are evicted first, then the ones that were updated least recently. Seeds are evicted last.
+    /// Contacts for which `is_protected` returns true, e.g. those of connected peers, are never evicted. Our own
+    /// contact is not counted. Returns the peer IDs of the removed contacts.
+    pub fn evict_excess(
+        &mut self,
+        max_peer_contacts: usize,
+        required_services: Services,
+        is_protected: impl Fn(&PeerId) -> bool,
+    ) -> Vec<PeerId> {
+        let num_excess = self.peer_contacts.len().saturating_sub(max_peer_contacts);
+        if num_excess == 0 {
+            return vec![];
+        }
+
+        let mut candidates: Vec<(bool, bool, u64, PeerId)> = self
+            .peer_contacts
+            .iter()
+            .filter(|(peer_id, _)| !is_protected(peer_id))
+            .map(|(peer_id, peer_contact)| {
+                (
+                    peer_contact.matches(required_services),
+                    peer_contact.is_seed(),
+                    peer_contact.contact().timestamp.unwrap_or_default(),
+                    *peer_id,
+                )
+            })
+            .collect();
+        candidates.sort_unstable();
+
+        candidates
+            .into_iter()
+            .take(num_excess)
+            .map(|(_, _, _, peer_id)| {
+                debug!(%peer_id, "Removing peer contact because the peer contact book is full");
+                self.peer_contacts.remove(&peer_id);
+                peer_id
+            })
+            .collect()
     }
 
     /// Returns true if an address is a secure websocket connection.
@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 
 use futures::{future, AsyncRead, AsyncWrite};
-use libp2p::{core::UpgradeInfo, identity::Keypair, InboundUpgrade, Multiaddr, OutboundUpgrade};
+use libp2p::{
+    core::UpgradeInfo, identity::Keypair, InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId,
+};
 use nimiq_hash::Blake2bHash;
 use nimiq_macros::{add_hex_io_fns_typed_arr, add_serialization_fns_typed_arr, create_typed_array};
 use nimiq_network_interface::peer_info::Services;
@@ -51,6 +53,11 @@ pub enum DiscoveryMessage {
 
         /// Service flags for which the sender needs peer contacts.
         services: Services,
+
+        /// Fields added after the initial version of the protocol. This must stay the last field, see
+        /// [`HandshakeExtensions`].
+        #[serde(deserialize_with = "deserialize_trailing")]
+        extensions: Option<HandshakeExtensions>,
     },
 
     HandshakeAck {
@@ -71,6 +78,50 @@ pub enum DiscoveryMessage {
     PeerAddresses {
         peer_contacts: Vec<SignedPeerContact>,
     },
+
+    /// Update containing only the changes since the previous update sent to the receiver.
+    PeerAddressesDelta {
+        /// Peer contacts that are new or changed since the previous update.
+        added: Vec<SignedPeerContact>,
+
+        /// Peers whose contacts are no longer advertised since the previous update.
+        removed: Vec<PeerId>,
+    },
+
+    /// Change of the interval in which the sender wants to receive updates. Only sent to peers that announced support
+    /// for it in their handshake, see [`HandshakeExtensions::update_interval_changes`].
+    UpdateInterval {
+        /// Interval in seconds in which the sender wants to receive new updates.
+        update_interval: u64,
+    },
+}
+
+/// Handshake fields that peers running the initial version of the protocol neither send nor expect.
+///
+/// The extensions are appended to the end of the handshake. Older peers ignore them, since messages are deserialized
+/// without checking for trailing data, and handshakes received from older peers end before them, in which case they
+/// are `None`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeExtensions {
+    /// Whether the sender supports receiving delta updates.
+    pub delta_updates: bool,
+
+    /// Current time of the sender in milliseconds since the unix epoch.
+    pub timestamp: u64,
+
+    /// Whether the sender accepts changes of the update interval after the handshake.
+    pub update_interval_changes: bool,
+}
+
+/// Deserializes an optional field at the end of a message, which is absent if the message ends before it.
+fn deserialize_trailing<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    Ok(<Option<T> as serde::Deserialize>::deserialize(deserializer)
+        .ok()
+        .flatten())
 }
 
 /// # TODO
@@ -116,3 +167,75 @@ where
         future::ok(MessageWriter::new(socket))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The handshake as sent by peers running the initial version of the protocol.
+    #[derive(Debug, Serialize, Deserialize)]
+    #[repr(u8)]
+    enum LegacyDiscoveryMessage {
+        Handshake {
+            observed_address: Multiaddr,
+            challenge_nonce: ChallengeNonce,
+            genesis_hash: Blake2bHash,
+            limit: u16,
+            services: Services,
+        },
+    }
+
+    #[test]
+    fn handshake_is_compatible_with_initial_version() {
+        let observed_address: Multiaddr = "/ip4/127.0.0.1/tcp/8443/ws".parse().unwrap();
+        let extensions = HandshakeExtensions {
+            delta_updates: true,
+            timestamp: 1_000,
+            update_interval_changes: true,
+        };
+
+        // Handshakes from older peers don't have extensions.
+        let legacy = LegacyDiscoveryMessage::Handshake {
+            observed_address: observed_address.clone(),
+            challenge_nonce: ChallengeNonce::generate(),
+            genesis_hash: Blake2bHash::default(),
+            limit: 64,
+            services: Services::FULL_BLOCKS,
+        };
+        match DiscoveryMessage::deserialize_from_vec(&legacy.serialize_to_vec()).unwrap() {
+            DiscoveryMessage::Handshake {
+                limit, extensions, ..
+            } => {
+                assert_eq!(limit, 64);
+                assert_eq!(extensions, None);
+            }
+            message => panic!("Unexpected message: {message:?}"),
+        }
+
+        // Older peers ignore the extensions of our handshakes.
+        let handshake = DiscoveryMessage::Handshake {
+            observed_address: observed_address.clone(),
+            challenge_nonce: ChallengeNonce::generate(),
+            genesis_hash: Blake2bHash::default(),
+            limit: 32,
+            services: Services::FULL_BLOCKS,
+            extensions: Some(extensions.clone()),
+        };
+        let bytes = handshake.serialize_to_vec();
+        let LegacyDiscoveryMessage::Handshake {
+            limit,
+            observed_address: legacy_address,
+            ..
+        } = LegacyDiscoveryMessage::deserialize_from_vec(&bytes).unwrap();
+        assert_eq!(limit, 32);
+        assert_eq!(legacy_address, observed_address);
+
+        match DiscoveryMessage::deserialize_from_vec(&bytes).unwrap() {
+            DiscoveryMessage::Handshake {
+                extensions: received,
+                ..
+            } => assert_eq!(received, Some(extensions)),
+            message => panic!("Unexpected message: {message:?}"),
+        }
+    }
+}
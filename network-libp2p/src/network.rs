@@ -36,7 +36,7 @@ use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
 #[cfg(feature = "metrics")]
 use crate::network_metrics::NetworkMetrics;
 use crate::{
-    discovery::peer_contacts::PeerContactBook,
+    discovery::peer_contacts::{PeerContact, PeerContactBook},
     network_types::{GossipsubId, NetworkAction, ValidateMessage},
     rate_limiting::RequestRateLimitData,
     swarm::{new_swarm, swarm_task},
@@ -159,6 +159,53 @@ impl Network {
         Ok(output_rx.await?)
     }
 
+    /// Gets the addresses the network is currently listening on. For listeners on port 0, these
+    /// contain the port that was actually assigned by the OS.
+    pub async fn listen_addresses(&self) -> Result<Vec<Multiaddr>, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::ListenAddresses { output: output_tx })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Gets the contacts in the peer contact book of the peers providing the given services,
+    /// including seeds.
+    pub fn peer_contacts(&self, services: Services) -> Vec<PeerContact> {
+        let contacts = self.contacts.read();
+        contacts
+            .peer_ids()
+            .filter_map(|peer_id| contacts.get(peer_id))
+            .filter(|contact| contact.matches(services))
+            .map(|contact| contact.contact().clone())
+            .collect()
+    }
+
+    /// Gets the peers we know a contact for but are currently not connected to.
+    pub async fn unconnected_contacts(&self) -> Result<Vec<PeerId>, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::UnconnectedContacts { output: output_tx })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Gets the median offset in milliseconds of the connected peers' clocks to our local clock.
+    /// A positive offset means that the peers' clocks are ahead of ours.
+    pub async fn clock_offset(&self) -> Result<i64, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::ClockOffset { output: output_tx })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
     /// Tells the network to listen on a specific address received in a
     /// `Multiaddr` format.
     pub async fn listen_on(&self, listen_addresses: Vec<Multiaddr>) {
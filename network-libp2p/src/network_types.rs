@@ -70,6 +70,15 @@ pub(crate) enum NetworkAction {
     NetworkInfo {
         output: oneshot::Sender<NetworkInfo>,
     },
+    ListenAddresses {
+        output: oneshot::Sender<Vec<Multiaddr>>,
+    },
+    UnconnectedContacts {
+        output: oneshot::Sender<Vec<PeerId>>,
+    },
+    ClockOffset {
+        output: oneshot::Sender<i64>,
+    },
     ReceiveRequests {
         type_id: RequestType,
         output: mpsc::Sender<(Bytes, InboundRequestId, PeerId)>,
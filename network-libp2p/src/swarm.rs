@@ -611,6 +611,35 @@ fn handle_event(
                             }
                         }
                         Event::Update => {}
+                        Event::ClockOffset { peer_id, offset } => {
+                            debug!(%peer_id, offset, "Measured clock offset of peer");
+                        }
+                        Event::PeerRemoved { peer_id, reason } => {
+                            trace!(%peer_id, ?reason, "Peer contact removed");
+                            swarm.behaviour_mut().pool.forget_peer(&peer_id);
+                        }
+                        Event::ClockSkewDetected { peer_id, offset } => {
+                            warn!(%peer_id, offset, "Clock of peer deviates from ours");
+                        }
+                        Event::ServicesChanged { peer_id, old, new } => {
+                            debug!(%peer_id, ?old, ?new, "Peer changed its services");
+                            if let Some(peer_info) = connected_peers.write().get_mut(&peer_id) {
+                                *peer_info = PeerInfo::new(peer_info.get_address(), new);
+                            }
+                        }
+                        Event::PeerUseless { peer_id } => {
+                            debug!(%peer_id, "Peer doesn't provide the required services");
+                        }
+                        Event::PeerBanned { peer_id, duration } => {
+                            info!(%peer_id, ?duration, "Peer banned by discovery");
+                        }
+                        Event::ConnectionFailed {
+                            peer_id,
+                            address,
+                            reason,
+                        } => {
+                            debug!(?peer_id, ?address, ?reason, "Connection failed");
+                        }
                     }
                 }
                 behaviour::BehaviourEvent::Gossipsub(event) => match event {
@@ -672,6 +701,10 @@ fn handle_event(
                         }
                         Ok(duration) => {
                             log::trace!(?event.peer, ?duration, "Successful ping from peer");
+                            swarm
+                                .behaviour_mut()
+                                .discovery
+                                .record_latency(event.peer, duration);
                         }
                     };
                 }
@@ -1095,6 +1128,36 @@ fn perform_action(action: NetworkAction, swarm: &mut NimiqSwarm, state: &mut Tas
                 );
             }
         }
+        NetworkAction::ListenAddresses { output } => {
+            if output.send(swarm.listeners().cloned().collect()).is_err() {
+                error!(
+                    error = "receiver hung up",
+                    "could not send listen addresses result to channel",
+                );
+            }
+        }
+        NetworkAction::UnconnectedContacts { output } => {
+            if output
+                .send(swarm.behaviour().unconnected_contacts())
+                .is_err()
+            {
+                error!(
+                    error = "receiver hung up",
+                    "could not send unconnected contacts result to channel",
+                );
+            }
+        }
+        NetworkAction::ClockOffset { output } => {
+            if output
+                .send(swarm.behaviour().discovery.clock_offset())
+                .is_err()
+            {
+                error!(
+                    error = "receiver hung up",
+                    "could not send clock offset result to channel",
+                );
+            }
+        }
         NetworkAction::ReceiveRequests {
             type_id,
             output,
@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::{net::Ipv4Addr, sync::Arc, time::Duration};
 
 use futures::StreamExt;
 use libp2p::{
@@ -6,12 +6,14 @@ use libp2p::{
         multiaddr::{multiaddr, Multiaddr},
         transport::MemoryTransport,
         upgrade::Version,
+        ConnectedPoint, Endpoint,
     },
     identity::Keypair,
     noise,
     swarm::{
+        behaviour::ConnectionEstablished,
         dial_opts::{DialOpts, PeerCondition},
-        Swarm, SwarmEvent,
+        ConnectionId, FromSwarm, NetworkBehaviour, Swarm, SwarmEvent, ToSwarm,
     },
     yamux, PeerId, SwarmBuilder, Transport,
 };
@@ -19,12 +21,14 @@ use nimiq_hash::Blake2bHash;
 use nimiq_network_interface::peer_info::Services;
 use nimiq_network_libp2p::discovery::{
     self,
+    handler::{HandlerInEvent, HandlerOutEvent},
     peer_contacts::{PeerContact, PeerContactBook, SignedPeerContact},
 };
 use nimiq_test_log::test;
-use nimiq_utils::spawn;
+use nimiq_utils::{spawn, time::OffsetTime};
 use parking_lot::RwLock;
 use rand::{thread_rng, Rng};
+use tokio::sync::mpsc;
 
 struct TestNode {
     peer_id: PeerId,
@@ -35,6 +39,23 @@ struct TestNode {
 
 impl TestNode {
     pub fn new() -> Self {
+        Self::build(|_| {}, |behaviour| behaviour)
+    }
+
+    pub fn with_behaviour(
+        configure: impl FnOnce(discovery::Behaviour) -> discovery::Behaviour,
+    ) -> Self {
+        Self::build(|_| {}, configure)
+    }
+
+    pub fn with_config(configure: impl FnOnce(&mut discovery::Config)) -> Self {
+        Self::build(configure, |behaviour| behaviour)
+    }
+
+    fn build(
+        configure_config: impl FnOnce(&mut discovery::Config),
+        configure_behaviour: impl FnOnce(discovery::Behaviour) -> discovery::Behaviour,
+    ) -> Self {
         let keypair = Keypair::generate_ed25519();
         let peer_id = PeerId::from(keypair.public());
 
@@ -50,9 +71,12 @@ impl TestNode {
             .timeout(Duration::from_secs(20))
             .boxed();
 
-        let config = discovery::Config {
+        let mut config = discovery::Config {
             genesis_hash: Blake2bHash::default(),
             update_interval: Duration::from_secs(10),
+            adaptive_update: false,
+            max_update_interval: Duration::from_secs(10),
+            target_peer_count: 20,
             min_send_update_interval: Duration::from_secs(5),
             update_limit: 64,
             required_services: Services::FULL_BLOCKS,
@@ -60,7 +84,27 @@ impl TestNode {
             house_keeping_interval: Duration::from_secs(1),
             keep_alive: true,
             only_secure_ws_connections: false,
+            enable_delta_updates: true,
+            process_observed_addresses: true,
+            emit_external_addr_candidates: true,
+            accept_inbound: true,
+            keep_alive_idle_timeout: None,
+            max_peer_contacts: 10_000,
+            clock_skew_threshold: Duration::from_secs(10),
+            max_clock_offset: Duration::from_secs(5),
+            max_connections_per_ip: None,
+            trusted_ips: vec![],
+            auto_ban_errors: None,
+            auto_ban_window: Duration::from_secs(10 * 60),
+            auto_ban_duration: Duration::from_secs(30 * 60),
+            genesis_mismatch_cooldown: Duration::from_secs(60 * 60),
+            min_dial_backoff: Duration::from_secs(5),
+            max_dial_backoff: Duration::from_secs(30 * 60),
+            max_recv_contacts: None,
+            max_recv_bytes: None,
+            recv_contacts_window: Duration::from_secs(5 * 60),
         };
+        configure_config(&mut config);
 
         let peer_contact = PeerContact {
             addresses: Some(address.clone()).into_iter().collect(),
@@ -77,8 +121,11 @@ impl TestNode {
             true,
         )));
 
-        let behaviour =
-            discovery::Behaviour::new(config, keypair.clone(), Arc::clone(&peer_contact_book));
+        let behaviour = configure_behaviour(discovery::Behaviour::new(
+            config,
+            keypair.clone(),
+            Arc::clone(&peer_contact_book),
+        ));
 
         let mut swarm = SwarmBuilder::with_existing_identity(keypair)
             .with_tokio()
@@ -284,8 +331,9 @@ fn test_housekeeping() {
         .unwrap();
     assert_eq!(peer_contact.contact(), &old_contact.inner);
 
-    // Call house-keeping on peer contact book
-    peer_contact_book.house_keeping();
+    // Call house-keeping on peer contact book, only the old contact is removed
+    let removed = peer_contact_book.house_keeping();
+    assert_eq!(removed, vec![old_contact.peer_id()]);
 
     // Check that fresh contact is still in there
     let peer_contact = peer_contact_book
@@ -298,3 +346,822 @@ fn test_housekeeping() {
         .get(&old_contact.public_key().clone().to_peer_id())
         .is_none());
 }
+
+#[test]
+fn test_evicting_excess_peer_contacts() {
+    let mut peer_contact_book = PeerContactBook::new(
+        random_peer_contact(1, Services::FULL_BLOCKS),
+        false,
+        true,
+        true,
+    );
+
+    let contact_with_age = |n: usize, services: Services, age: u64| {
+        let keypair = Keypair::generate_ed25519();
+        let mut peer_contact = PeerContact {
+            addresses: vec![format!("/dns/test{}.local/tcp/443/wss", n).parse().unwrap()],
+            public_key: keypair.public(),
+            services,
+            timestamp: None,
+        };
+        peer_contact.set_current_time();
+        if let Some(timestamp) = peer_contact.timestamp.as_mut() {
+            *timestamp -= age;
+        }
+        peer_contact.sign(&keypair)
+    };
+
+    let unneeded = contact_with_age(10, Services::empty(), 0);
+    let old = contact_with_age(11, Services::FULL_BLOCKS, 60);
+    let connected = contact_with_age(12, Services::FULL_BLOCKS, 120);
+    let fresh = contact_with_age(13, Services::FULL_BLOCKS, 0);
+    peer_contact_book.insert_all(vec![
+        unneeded.clone(),
+        old.clone(),
+        connected.clone(),
+        fresh.clone(),
+    ]);
+
+    // The contact of the connected peer is protected even though it is the oldest
+    let connected_peer_id = connected.peer_id();
+    peer_contact_book.evict_excess(2, Services::FULL_BLOCKS, |peer_id| {
+        *peer_id == connected_peer_id
+    });
+
+    assert!(peer_contact_book.get(&unneeded.peer_id()).is_none());
+    assert!(peer_contact_book.get(&old.peer_id()).is_none());
+    assert!(peer_contact_book.get(&connected.peer_id()).is_some());
+    assert!(peer_contact_book.get(&fresh.peer_id()).is_some());
+}
+
+#[test(tokio::test)]
+pub async fn test_peers_by_latency() {
+    let mut node = TestNode::new();
+
+    let fast_peer = PeerId::random();
+    let medium_peer = PeerId::random();
+    let slow_peer = PeerId::random();
+
+    let behaviour = node.swarm.behaviour_mut();
+    behaviour.record_latency(slow_peer, Duration::from_millis(300));
+    behaviour.record_latency(fast_peer, Duration::from_millis(20));
+    behaviour.record_latency(medium_peer, Duration::from_millis(100));
+
+    let peers = behaviour.peers_by_latency();
+    assert_eq!(
+        peers,
+        vec![
+            (fast_peer, Duration::from_millis(20)),
+            (medium_peer, Duration::from_millis(100)),
+            (slow_peer, Duration::from_millis(300)),
+        ]
+    );
+
+    // A new sample is folded into the average instead of replacing it, which reorders the peers
+    behaviour.record_latency(fast_peer, Duration::from_millis(520));
+    let peers = behaviour.peers_by_latency();
+    assert_eq!(
+        peers,
+        vec![
+            (medium_peer, Duration::from_millis(100)),
+            (fast_peer, Duration::from_millis(120)),
+            (slow_peer, Duration::from_millis(300)),
+        ]
+    );
+}
+
+#[test(tokio::test)]
+pub async fn test_unconnected_contacts() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+    let peer2_contact = node2
+        .peer_contact_book
+        .read()
+        .get_own_contact()
+        .signed()
+        .clone();
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) =
+            node1.swarm.next().await
+        {
+            break;
+        }
+    }
+
+    // Add the contact of the connected peer and some contacts of peers we're not connected to
+    let known_only: Vec<SignedPeerContact> = (0..3)
+        .map(|i| random_peer_contact(i, Services::FULL_BLOCKS))
+        .collect();
+    {
+        let mut peer_contact_book = node1.peer_contact_book.write();
+        peer_contact_book.insert(peer2_contact);
+        peer_contact_book.insert_all(known_only.clone());
+    }
+
+    let mut unconnected = node1.swarm.behaviour().unconnected_contacts();
+    unconnected.sort();
+    let mut expected: Vec<PeerId> = known_only
+        .iter()
+        .map(|contact| contact.public_key().clone().to_peer_id())
+        .collect();
+    expected.sort();
+    assert_eq!(unconnected, expected);
+    assert!(!unconnected.contains(&peer2_id));
+}
+
+#[test(tokio::test)]
+pub async fn test_external_address_updates() {
+    let (tx, mut rx) = mpsc::channel(16);
+    let mut node =
+        TestNode::with_behaviour(|behaviour| behaviour.with_external_address_updates(tx));
+
+    let address: Multiaddr = "/dns/test.local/tcp/443/wss".parse().unwrap();
+    node.swarm.add_external_address(address.clone());
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        discovery::ExternalAddrUpdate::Confirmed(address.clone())
+    );
+
+    node.swarm.remove_external_address(&address);
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        discovery::ExternalAddrUpdate::Expired(address)
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+fn num_external_addr_candidates(node: &TestNode) -> usize {
+    node.swarm
+        .behaviour()
+        .events
+        .iter()
+        .filter(|event| matches!(event, ToSwarm::NewExternalAddrCandidate(_)))
+        .count()
+}
+
+fn report_observed_address(node: &mut TestNode) {
+    node.swarm.behaviour_mut().on_connection_handler_event(
+        PeerId::random(),
+        ConnectionId::new_unchecked(0),
+        HandlerOutEvent::ObservedAddress {
+            observed_address: "/dns/observed.local/tcp/443/wss".parse().unwrap(),
+        },
+    );
+}
+
+#[test(tokio::test)]
+pub async fn test_disabling_external_addr_candidates() {
+    let mut node = TestNode::new();
+    assert_eq!(num_external_addr_candidates(&node), 1);
+    report_observed_address(&mut node);
+    assert_eq!(num_external_addr_candidates(&node), 2);
+
+    let mut node = TestNode::with_config(|config| config.emit_external_addr_candidates = false);
+    assert_eq!(num_external_addr_candidates(&node), 0);
+    report_observed_address(&mut node);
+    assert_eq!(num_external_addr_candidates(&node), 0);
+}
+
+#[test(tokio::test)]
+pub async fn test_disabling_observed_addresses() {
+    let mut node = TestNode::with_config(|config| config.process_observed_addresses = false);
+
+    // Our own addresses are still reported, but observed addresses are ignored
+    assert_eq!(num_external_addr_candidates(&node), 1);
+    report_observed_address(&mut node);
+    assert_eq!(num_external_addr_candidates(&node), 1);
+}
+
+#[test(tokio::test)]
+pub async fn test_disabling_inbound_connections() {
+    let remote_address = multiaddr![Memory(thread_rng().gen::<u64>())];
+
+    let mut node = TestNode::new();
+    let local_address = node.address.clone();
+    assert!(node
+        .swarm
+        .behaviour_mut()
+        .handle_established_inbound_connection(
+            ConnectionId::new_unchecked(0),
+            PeerId::random(),
+            &local_address,
+            &remote_address,
+        )
+        .is_ok());
+
+    let mut node = TestNode::with_config(|config| config.accept_inbound = false);
+    let local_address = node.address.clone();
+    assert!(node
+        .swarm
+        .behaviour_mut()
+        .handle_established_inbound_connection(
+            ConnectionId::new_unchecked(0),
+            PeerId::random(),
+            &local_address,
+            &remote_address,
+        )
+        .is_err());
+}
+
+#[test(tokio::test)]
+pub async fn test_max_connections_per_ip() {
+    let trusted_ip = Ipv4Addr::new(5, 6, 7, 8);
+    let mut node = TestNode::with_config(|config| {
+        config.max_connections_per_ip = Some(1);
+        config.trusted_ips = vec![trusted_ip.into()];
+    });
+    let local_address = node.address.clone();
+
+    let mut connect = |remote_address: Multiaddr, connection_id: usize| {
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(connection_id);
+        let behaviour = node.swarm.behaviour_mut();
+        if behaviour
+            .handle_established_inbound_connection(
+                connection_id,
+                peer_id,
+                &local_address,
+                &remote_address,
+            )
+            .is_err()
+        {
+            return false;
+        }
+        behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+            peer_id,
+            connection_id,
+            endpoint: &ConnectedPoint::Listener {
+                local_addr: local_address.clone(),
+                send_back_addr: remote_address,
+            },
+            failed_addresses: &[],
+            other_established: 0,
+        }));
+        true
+    };
+
+    // Only a single connection from the same IP is accepted
+    assert!(connect(multiaddr![Ip4([1, 2, 3, 4]), Tcp(1u16)], 0));
+    assert!(!connect(multiaddr![Ip4([1, 2, 3, 4]), Tcp(2u16)], 1));
+    assert!(connect(multiaddr![Ip4([1, 2, 3, 5]), Tcp(1u16)], 2));
+
+    // Loopback and trusted addresses are exempt
+    for connection_id in 3..6 {
+        assert!(connect(
+            multiaddr![Ip4([127, 0, 0, 1]), Tcp(1u16)],
+            connection_id
+        ));
+    }
+    for connection_id in 6..9 {
+        assert!(connect(
+            multiaddr![Ip4(trusted_ip), Tcp(1u16)],
+            connection_id
+        ));
+    }
+}
+
+#[test(tokio::test)]
+pub async fn test_ban_peer() {
+    let mut node = TestNode::with_config(|config| config.auto_ban_errors = Some(2));
+    let local_address = node.address.clone();
+    let remote_address = multiaddr![Ip4([1, 2, 3, 4]), Tcp(1u16)];
+    let behaviour = node.swarm.behaviour_mut();
+
+    let connect = |behaviour: &mut discovery::Behaviour, peer_id| {
+        behaviour
+            .handle_established_inbound_connection(
+                ConnectionId::new_unchecked(0),
+                peer_id,
+                &local_address,
+                &remote_address,
+            )
+            .is_ok()
+    };
+
+    // Errors that also occur with honest peers don't count towards a ban
+    let peer_id = PeerId::random();
+    for _ in 0..2 {
+        assert!(connect(behaviour, peer_id));
+        behaviour.on_connection_handler_event(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            HandlerOutEvent::Error(discovery::Error::connection_reset()),
+        );
+    }
+    assert!(!behaviour.is_banned(&peer_id));
+
+    // A peer is banned automatically after too many protocol violations
+    let peer_id = PeerId::random();
+    for _ in 0..2 {
+        assert!(connect(behaviour, peer_id));
+        behaviour.on_connection_handler_event(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            HandlerOutEvent::Error(discovery::Error::ChallengeResponseFailed),
+        );
+    }
+    assert!(behaviour.is_banned(&peer_id));
+    assert!(!connect(behaviour, peer_id));
+    assert!(connect(behaviour, PeerId::random()));
+    assert!(behaviour.unban_peer(&peer_id));
+    assert!(connect(behaviour, peer_id));
+
+    // Explicit bans expire, banned peers are not dialed in the meantime
+    behaviour.ban_peer(peer_id, Duration::from_millis(100));
+    assert!(!connect(behaviour, peer_id));
+    assert!(behaviour
+        .handle_pending_outbound_connection(
+            ConnectionId::new_unchecked(1),
+            Some(peer_id),
+            &[],
+            Endpoint::Dialer,
+        )
+        .is_err());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(connect(behaviour, peer_id));
+}
+
+#[test(tokio::test)]
+pub async fn test_genesis_mismatch_suppresses_peer() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::with_config(|config| config.genesis_hash = Blake2bHash::from([1u8; 32]));
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::ConnectionFailed {
+            peer_id,
+            reason,
+            ..
+        })) = node1.swarm.next().await
+        {
+            assert_eq!(peer_id, Some(peer2_id));
+            assert_eq!(reason, discovery::ConnectionFailReason::GenesisMismatch);
+            break;
+        }
+    }
+
+    // The peer is not dialed again during the cooldown
+    assert!(node1.peer_contact_book.read().is_suppressed(&peer2_id));
+    assert!(node1
+        .swarm
+        .behaviour_mut()
+        .handle_pending_outbound_connection(
+            ConnectionId::new_unchecked(0),
+            Some(peer2_id),
+            &[],
+            Endpoint::Dialer,
+        )
+        .is_err());
+}
+
+#[test(tokio::test)]
+pub async fn test_useless_peer_is_disconnected() {
+    let mut node1 = TestNode::with_config(|config| {
+        config.required_services = Services::FULL_BLOCKS | Services::HISTORY
+    });
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    loop {
+        match node1.swarm.next().await {
+            Some(SwarmEvent::Behaviour(discovery::Event::PeerUseless { peer_id })) => {
+                assert_eq!(peer_id, peer2_id);
+                break;
+            }
+            Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) => {
+                panic!("Connection to useless peer was established")
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test(tokio::test)]
+pub async fn test_update_interval_scales_with_peer_count() {
+    let mut node1 = TestNode::with_config(|config| {
+        config.adaptive_update = true;
+        config.max_update_interval = Duration::from_secs(30);
+        config.target_peer_count = 2;
+    });
+    let node2 = TestNode::new();
+
+    assert_eq!(
+        node1.swarm.behaviour().update_interval(),
+        Duration::from_secs(10)
+    );
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) =
+            node1.swarm.next().await
+        {
+            break;
+        }
+    }
+
+    // The interval is adjusted the next time the behaviour is polled.
+    let _ = tokio::time::timeout(Duration::from_millis(100), node1.swarm.next()).await;
+
+    // One of two target peers is connected, so the interval is half of the configured one.
+    assert_eq!(
+        node1.swarm.behaviour().update_interval(),
+        Duration::from_secs(5)
+    );
+}
+
+#[test(tokio::test)]
+pub async fn test_services_changed_event() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    let old = loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { peer_contact, .. })) =
+            node1.swarm.next().await
+        {
+            break peer_contact.services;
+        }
+    };
+    let new = old | Services::HISTORY;
+
+    let behaviour = node1.swarm.behaviour_mut();
+    behaviour.events.clear();
+    for peer_services in [None, Some(old), Some(new), Some(new)] {
+        behaviour.on_connection_handler_event(
+            peer2_id,
+            ConnectionId::new_unchecked(0),
+            HandlerOutEvent::Update { peer_services },
+        );
+    }
+
+    // Only the actual change of services is reported
+    let changes: Vec<_> = behaviour
+        .events
+        .drain(..)
+        .filter_map(|event| match event {
+            ToSwarm::GenerateEvent(discovery::Event::ServicesChanged { peer_id, old, new }) => {
+                Some((peer_id, old, new))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(changes, vec![(peer2_id, old, new)]);
+}
+
+#[test(tokio::test)]
+pub async fn test_dial_failure_event() {
+    let mut node = TestNode::new();
+
+    // Nobody is listening on this address, so the dial must fail
+    let address = multiaddr![Memory(thread_rng().gen::<u64>())];
+    node.dial(address.clone());
+
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::ConnectionFailed {
+            peer_id,
+            address: failed_address,
+            reason,
+        })) = node.swarm.next().await
+        {
+            assert_eq!(peer_id, None);
+            assert_eq!(failed_address, Some(address));
+            assert_eq!(reason, discovery::ConnectionFailReason::DialFailure);
+            break;
+        }
+    }
+}
+
+#[test(tokio::test)]
+pub async fn test_dial_backoff() {
+    let mut node = TestNode::new();
+
+    // Nobody is listening on the address of this peer, so dialing it must fail
+    let keypair = Keypair::generate_ed25519();
+    let peer_id = PeerId::from(keypair.public());
+    let mut peer_contact = PeerContact {
+        addresses: vec![multiaddr![Memory(thread_rng().gen::<u64>())]],
+        public_key: keypair.public(),
+        services: Services::empty(),
+        timestamp: None,
+    };
+    peer_contact.set_current_time();
+    node.peer_contact_book
+        .write()
+        .insert(peer_contact.sign(&keypair));
+
+    assert!(node.swarm.behaviour().dial_backoff(&peer_id).is_none());
+    node.dial_peer_id(&peer_id);
+
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::ConnectionFailed { .. })) =
+            node.swarm.next().await
+        {
+            break;
+        }
+    }
+
+    // The peer's addresses are omitted while it is in backoff
+    assert!(node.swarm.behaviour().dial_backoff(&peer_id).is_some());
+    assert_eq!(
+        node.swarm
+            .behaviour_mut()
+            .handle_pending_outbound_connection(
+                ConnectionId::new_unchecked(0),
+                Some(peer_id),
+                &[],
+                Endpoint::Dialer,
+            )
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test(tokio::test)]
+pub async fn test_metrics() {
+    let mut node = TestNode::new();
+    assert_eq!(
+        node.swarm.behaviour().metrics(),
+        discovery::Metrics::default()
+    );
+
+    let behaviour = node.swarm.behaviour_mut();
+    behaviour.on_connection_handler_event(
+        PeerId::random(),
+        ConnectionId::new_unchecked(0),
+        HandlerOutEvent::Update {
+            peer_services: None,
+        },
+    );
+    behaviour.on_connection_handler_event(
+        PeerId::random(),
+        ConnectionId::new_unchecked(1),
+        HandlerOutEvent::Error(discovery::Error::ChallengeResponseFailed),
+    );
+    behaviour.on_connection_handler_event(
+        PeerId::random(),
+        ConnectionId::new_unchecked(2),
+        HandlerOutEvent::Error(discovery::Error::TooFrequentUpdates {
+            interval: Duration::from_millis(10),
+        }),
+    );
+
+    assert_eq!(
+        behaviour.metrics(),
+        discovery::Metrics {
+            connected_peers: 0,
+            peer_contacts: 0,
+            peer_contacts_with_required_services: 0,
+            peer_exchanges_established: 0,
+            updates_received: 1,
+            connections_closed_on_error: 2,
+            updates_rejected: 1,
+            updates_rate_limited: 0,
+            house_keeping_runs: 0,
+        }
+    );
+}
+
+#[test(tokio::test)]
+pub async fn test_recv_update_budget() {
+    let mut node1 = TestNode::with_config(|config| {
+        config.update_interval = Duration::from_secs(1);
+        config.min_recv_update_interval = Duration::ZERO;
+        config.max_recv_bytes = Some(1);
+    });
+    let node2 = TestNode::with_config(|config| {
+        config.min_send_update_interval = Duration::ZERO;
+        config.enable_delta_updates = false;
+    });
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // The first periodic update of node 2 exceeds the budget
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::ConnectionFailed {
+            peer_id,
+            reason,
+            ..
+        })) = node1.swarm.next().await
+        {
+            assert_eq!(peer_id, Some(peer2_id));
+            assert_eq!(reason, discovery::ConnectionFailReason::ProtocolViolation);
+            break;
+        }
+    }
+
+    // The update was rejected before it was applied
+    let metrics = node1.swarm.behaviour().metrics();
+    assert_eq!(metrics.updates_received, 0);
+    assert_eq!(metrics.updates_rate_limited, 1);
+}
+
+#[test(tokio::test)]
+pub async fn test_clock_offset() {
+    let clock = Arc::new(OffsetTime::new());
+    let mut node1 = TestNode::with_behaviour(|behaviour| behaviour.with_clock(Arc::clone(&clock)));
+    // The clock of node 2 is one hour ahead
+    let node2 = TestNode::with_behaviour(|behaviour| {
+        behaviour.with_clock(Arc::new(OffsetTime::with_offset(60 * 60 * 1000)))
+    });
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    let (peer_id, offset) = loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::ClockOffset { peer_id, offset })) =
+            node1.swarm.next().await
+        {
+            break (peer_id, offset);
+        }
+    };
+    assert_eq!(peer_id, peer2_id);
+    assert!((offset - 60 * 60 * 1000).abs() < 5000);
+
+    // The skew is reported right after the offset
+    let Some(SwarmEvent::Behaviour(discovery::Event::ClockSkewDetected { peer_id, .. })) =
+        node1.swarm.next().await
+    else {
+        panic!("Expected clock skew to be detected");
+    };
+    assert_eq!(peer_id, peer2_id);
+
+    // Our clock is adjusted by the offset, clamped to the configured maximum
+    assert_eq!(node1.swarm.behaviour().clock_offset(), 5000);
+    assert_eq!(clock.offset(), 5000);
+    let applied_offset = clock.now() as i64 - OffsetTime::new().now() as i64;
+    assert!((applied_offset - 5000).abs() < 1000);
+}
+
+#[test(tokio::test)]
+pub async fn test_pausing_discovery_notifies_handlers() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // Wait for the peer exchange to be established
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) =
+            node1.swarm.next().await
+        {
+            break;
+        }
+    }
+
+    let behaviour = node1.swarm.behaviour_mut();
+    behaviour.set_discovery_paused(true);
+    assert!(behaviour.is_discovery_paused());
+    assert!(behaviour.events.iter().any(|event| matches!(
+        event,
+        ToSwarm::NotifyHandler { peer_id, event: HandlerInEvent::Pause, .. } if *peer_id == peer2_id
+    )));
+
+    // Setting the same state again doesn't notify the handlers again
+    let num_events = behaviour.events.len();
+    behaviour.set_discovery_paused(true);
+    assert_eq!(behaviour.events.len(), num_events);
+
+    behaviour.set_discovery_paused(false);
+    assert!(!behaviour.is_discovery_paused());
+    assert!(behaviour.events.iter().any(|event| matches!(
+        event,
+        ToSwarm::NotifyHandler { peer_id, event: HandlerInEvent::Resume, .. } if *peer_id == peer2_id
+    )));
+}
+
+#[test(tokio::test)]
+pub async fn test_update_required_services() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // Wait for the peer exchange to be established
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) =
+            node1.swarm.next().await
+        {
+            break;
+        }
+    }
+
+    // Node 2 still provides the required services, so the handlers are only notified
+    let behaviour = node1.swarm.behaviour_mut();
+    behaviour.update_required_services(Services::empty());
+    assert!(behaviour.events.iter().any(|event| matches!(
+        event,
+        ToSwarm::NotifyHandler { peer_id, event: HandlerInEvent::UpdateRequiredServices(_), .. } if *peer_id == peer2_id
+    )));
+
+    // Node 2 doesn't provide the history service, so the connection to it is closed
+    behaviour.update_required_services(Services::FULL_BLOCKS | Services::HISTORY);
+    let closed = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(SwarmEvent::ConnectionClosed { peer_id, .. }) = node1.swarm.next().await {
+                break peer_id;
+            }
+        }
+    })
+    .await
+    .expect("Connection wasn't closed");
+    assert_eq!(closed, peer2_id);
+}
+
+#[test(tokio::test)]
+pub async fn test_idle_connection_is_closed() {
+    let idle_timeout = Duration::from_secs(1);
+    let mut node1 =
+        TestNode::with_config(|config| config.keep_alive_idle_timeout = Some(idle_timeout));
+    let node2 = TestNode::new();
+    let peer2_id = node2.peer_id;
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // Wait for the peer exchange to be established
+    loop {
+        if let Some(SwarmEvent::Behaviour(discovery::Event::Established { .. })) =
+            node1.swarm.next().await
+        {
+            break;
+        }
+    }
+    let established = std::time::Instant::now();
+
+    // Node 2 doesn't know any other peers, so it never sends a useful update and the connection is closed once idle
+    let closed = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(SwarmEvent::ConnectionClosed { peer_id, .. }) = node1.swarm.next().await {
+                break peer_id;
+            }
+        }
+    })
+    .await
+    .expect("Idle connection wasn't closed");
+
+    assert_eq!(closed, peer2_id);
+    assert!(established.elapsed() < idle_timeout + Duration::from_secs(5));
+}
@@ -49,6 +49,9 @@ fn network_config(address: Multiaddr) -> Config {
         discovery: discovery::Config {
             genesis_hash: Default::default(),
             update_interval: Duration::from_secs(60),
+            adaptive_update: false,
+            max_update_interval: Duration::from_secs(60),
+            target_peer_count: 20,
             min_recv_update_interval: Duration::from_secs(30),
             update_limit: 64,
             required_services: Services::all(),
@@ -56,17 +59,38 @@ fn network_config(address: Multiaddr) -> Config {
             house_keeping_interval: Duration::from_secs(60),
             keep_alive: false,
             only_secure_ws_connections: false,
+            enable_delta_updates: true,
+            process_observed_addresses: true,
+            emit_external_addr_candidates: true,
+            accept_inbound: true,
+            keep_alive_idle_timeout: None,
+            max_peer_contacts: 10_000,
+            clock_skew_threshold: Duration::from_secs(10),
+            max_clock_offset: Duration::from_secs(5),
+            max_connections_per_ip: None,
+            trusted_ips: vec![],
+            auto_ban_errors: None,
+            auto_ban_window: Duration::from_secs(10 * 60),
+            auto_ban_duration: Duration::from_secs(30 * 60),
+            genesis_mismatch_cooldown: Duration::from_secs(60 * 60),
+            min_dial_backoff: Duration::from_secs(5),
+            max_dial_backoff: Duration::from_secs(30 * 60),
+            max_recv_contacts: None,
+            max_recv_bytes: None,
+            recv_contacts_window: Duration::from_secs(5 * 60),
         },
         kademlia: Default::default(),
         gossipsub,
         memory_transport: true,
         required_services: Services::all(),
+        desired_services: Services::empty(),
         tls: None,
         desired_peer_count: 3,
         autonat_allow_non_global_ips: true,
         only_secure_ws_connections: false,
         allow_loopback_addresses: true,
         dht_quorum: NonZeroU8::new(1).unwrap(),
+        clock: Default::default(),
     }
 }
 
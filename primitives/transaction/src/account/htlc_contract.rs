@@ -153,6 +153,20 @@ impl PreImage {
             PreImage::PreImage64(hash) => &hash.0,
         }
     }
+
+    /// Hashes the pre-image `hash_count` times with the hasher `H`. A pre-image unlocks a contract
+    /// if this yields its hash root, with the hasher matching the algorithm of the hash root.
+    pub fn hash_repeatedly<H>(&self, hash_count: u8) -> PreImage
+    where
+        H: Hasher,
+        H::Output: Into<PreImage>,
+    {
+        let mut hash = self.clone();
+        for _ in 0..hash_count {
+            hash = H::default().digest(hash.as_bytes()).into();
+        }
+        hash
+    }
 }
 
 impl Default for PreImage {
@@ -274,24 +288,11 @@ impl OutgoingHTLCTransactionProof {
                 pre_image,
                 signature_proof,
             } => {
-                let mut tmp_hash = pre_image.clone();
-                for _ in 0..*hash_depth {
-                    match &hash_root {
-                        AnyHash::Blake2b(_) => {
-                            tmp_hash = PreImage::from(
-                                Blake2bHasher::default().digest(tmp_hash.as_bytes()),
-                            );
-                        }
-                        AnyHash::Sha256(_) => {
-                            tmp_hash =
-                                PreImage::from(Sha256Hasher::default().digest(tmp_hash.as_bytes()));
-                        }
-                        AnyHash::Sha512(_) => {
-                            tmp_hash =
-                                PreImage::from(Sha512Hasher::default().digest(tmp_hash.as_bytes()));
-                        }
-                    }
-                }
+                let tmp_hash = match hash_root {
+                    AnyHash::Blake2b(_) => pre_image.hash_repeatedly::<Blake2bHasher>(*hash_depth),
+                    AnyHash::Sha256(_) => pre_image.hash_repeatedly::<Sha256Hasher>(*hash_depth),
+                    AnyHash::Sha512(_) => pre_image.hash_repeatedly::<Sha512Hasher>(*hash_depth),
+                };
 
                 if hash_root.as_bytes() != tmp_hash.as_bytes() {
                     warn!(
@@ -292,3 +292,21 @@ fn it_can_verify_timeout_resolve() {
         )),
     );
 }
+
+#[test]
+fn it_can_hash_pre_images_repeatedly() {
+    let pre_image = PreImage::from(Blake2bHasher::default().digest(&[1u8; 32]));
+
+    let once = Sha256Hasher::default().digest(pre_image.as_bytes());
+    let twice = Sha256Hasher::default().digest(once.as_bytes());
+    assert_eq!(
+        pre_image.hash_repeatedly::<Sha256Hasher>(2),
+        PreImage::from(twice)
+    );
+
+    assert_eq!(pre_image.hash_repeatedly::<Blake2bHasher>(0), pre_image);
+    assert!(matches!(
+        pre_image.hash_repeatedly::<Sha512Hasher>(1),
+        PreImage::PreImage64(_)
+    ));
+}
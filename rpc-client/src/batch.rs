@@ -0,0 +1,249 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Error};
+use nimiq_keys::Address;
+use nimiq_primitives::coin::Coin;
+use serde::Deserialize;
+
+use crate::error::input_error;
+
+/// The kind of transaction described by a row of a batch file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchTransactionType {
+    /// A basic transaction from `sender` to `recipient`.
+    Basic,
+    /// A stake transaction from `sender` adding stake to the staker `recipient`.
+    Stake,
+}
+
+impl FromStr for BatchTransactionType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "basic" => Ok(Self::Basic),
+            "stake" => Ok(Self::Stake),
+            other => bail!("Unknown transaction type `{other}`, expected `basic` or `stake`"),
+        }
+    }
+}
+
+/// A single transaction of a batch file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchTransaction {
+    pub tx_type: BatchTransactionType,
+    pub sender: Address,
+    pub recipient: Address,
+    pub value: Coin,
+    pub fee: Coin,
+}
+
+/// A row of a JSON batch file. Values may be given either as strings or as numbers, both in NIM.
+#[derive(Debug, Deserialize)]
+struct JsonRow {
+    #[serde(rename = "type")]
+    tx_type: String,
+    sender: String,
+    recipient: String,
+    value: serde_json::Value,
+    #[serde(default)]
+    fee: Option<serde_json::Value>,
+}
+
+/// Reads the transactions described in the file at `path`.
+///
+/// Files ending in `.csv` are parsed as CSV with the header `type,sender,recipient,value,fee`,
+/// all other files as a JSON array of objects with these fields. Values and fees are given in NIM,
+/// the fee may be omitted, in which case it defaults to 0 NIM.
+pub fn read_batch_file(path: &Path) -> Result<Vec<BatchTransaction>, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(input_error)
+        .with_context(|| format!("Could not read batch file {}", path.display()))?;
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let transactions = if is_csv {
+        parse_csv(&content)
+    } else {
+        parse_json(&content)
+    };
+    transactions.map_err(input_error)
+}
+
+/// Reads the payouts from `sender` described in the CSV file at `path`.
+///
+/// Each line contains the `address,value,fee` of a basic transaction, where values and fees are
+/// given in NIM and the fee may be omitted, in which case it defaults to 0 NIM. A header line
+/// naming these columns is optional.
+pub fn read_payout_file(path: &Path, sender: &Address) -> Result<Vec<BatchTransaction>, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(input_error)
+        .with_context(|| format!("Could not read recipients file {}", path.display()))?;
+    parse_payouts(&content, sender).map_err(input_error)
+}
+
+fn parse_payouts(content: &str, sender: &Address) -> Result<Vec<BatchTransaction>, Error> {
+    let sender = sender.to_user_friendly_address();
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter(|(index, line)| *index > 0 || !line.trim().to_lowercase().starts_with("address"))
+        .map(|(index, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [recipient, value] => parse_row("basic", &sender, recipient, value, None),
+                [recipient, value, fee] => {
+                    parse_row("basic", &sender, recipient, value, Some(*fee))
+                }
+                _ => Err(anyhow!("Expected `address,value,fee`")),
+            }
+            .with_context(|| format!("Invalid recipient on line {}", index + 1))
+        })
+        .collect()
+}
+
+fn parse_json(content: &str) -> Result<Vec<BatchTransaction>, Error> {
+    let rows: Vec<JsonRow> = serde_json::from_str(content)?;
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let fee = row.fee.map(|fee| json_amount(&fee)).transpose();
+            parse_row(
+                &row.tx_type,
+                &row.sender,
+                &row.recipient,
+                &json_amount(&row.value)?,
+                fee?.as_deref(),
+            )
+            .with_context(|| format!("Invalid transaction {}", index + 1))
+        })
+        .collect()
+}
+
+fn json_amount(value: &serde_json::Value) -> Result<String, Error> {
+    match value {
+        serde_json::Value::String(value) => Ok(value.clone()),
+        serde_json::Value::Number(value) => Ok(value.to_string()),
+        other => Err(anyhow!("Invalid amount {other}")),
+    }
+}
+
+fn parse_csv(content: &str) -> Result<Vec<BatchTransaction>, Error> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| anyhow!("The batch file is empty"))?;
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| {
+        columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| anyhow!("Missing column `{name}` in the header"))
+    };
+    let (tx_type, sender, recipient, value) = (
+        column("type")?,
+        column("sender")?,
+        column("recipient")?,
+        column("value")?,
+    );
+    let fee = column("fee").ok();
+
+    lines
+        .map(|(index, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |column: usize| {
+                fields
+                    .get(column)
+                    .copied()
+                    .ok_or_else(|| anyhow!("Missing field `{}`", columns[column]))
+            };
+            parse_row(
+                field(tx_type)?,
+                field(sender)?,
+                field(recipient)?,
+                field(value)?,
+                fee.and_then(|fee| fields.get(fee).copied()),
+            )
+            .with_context(|| format!("Invalid transaction on line {}", index + 1))
+        })
+        .collect()
+}
+
+fn parse_row(
+    tx_type: &str,
+    sender: &str,
+    recipient: &str,
+    value: &str,
+    fee: Option<&str>,
+) -> Result<BatchTransaction, Error> {
+    let fee = match fee.map(str::trim) {
+        None | Some("") => Coin::ZERO,
+        Some(fee) => Coin::from_str(fee)?,
+    };
+    Ok(BatchTransaction {
+        tx_type: tx_type.parse()?,
+        sender: Address::from_any_str(sender.trim())?,
+        recipient: Address::from_any_str(recipient.trim())?,
+        value: Coin::from_str(value.trim())?,
+        fee,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDER: &str = "NQ07 0000 0000 0000 0000 0000 0000 0000 0000";
+
+    #[test]
+    fn parses_json_and_csv_batches() {
+        let json = format!(
+            r#"[
+                {{"type": "basic", "sender": "{SENDER}", "recipient": "{SENDER}", "value": 1.5, "fee": "0.001"}},
+                {{"type": "stake", "sender": "{SENDER}", "recipient": "{SENDER}", "value": "10"}}
+            ]"#
+        );
+        let csv = format!(
+            "type,sender,recipient,value,fee\n\
+             basic,{SENDER},{SENDER},1.5,0.001\n\
+             \n\
+             stake,{SENDER},{SENDER},10,\n"
+        );
+
+        for transactions in [parse_json(&json).unwrap(), parse_csv(&csv).unwrap()] {
+            assert_eq!(transactions.len(), 2);
+            assert_eq!(transactions[0].tx_type, BatchTransactionType::Basic);
+            assert_eq!(transactions[0].value, Coin::from_u64_unchecked(150_000));
+            assert_eq!(transactions[0].fee, Coin::from_u64_unchecked(100));
+            assert_eq!(transactions[1].tx_type, BatchTransactionType::Stake);
+            assert_eq!(transactions[1].fee, Coin::ZERO);
+        }
+
+        assert!(parse_csv("type,sender,recipient\n").is_err());
+
+        let sender = Address::from_any_str(SENDER).unwrap();
+        let payouts = parse_payouts(
+            &format!("address,value,fee\n{SENDER},1.5,0.001\n{SENDER},2\n"),
+            &sender,
+        )
+        .unwrap();
+        assert_eq!(payouts.len(), 2);
+        assert_eq!(payouts[0].tx_type, BatchTransactionType::Basic);
+        assert_eq!(payouts[0].sender, sender);
+        assert_eq!(payouts[1].value, Coin::from_u64_unchecked(200_000));
+        assert_eq!(payouts[1].fee, Coin::ZERO);
+        assert!(parse_payouts(&format!("{SENDER}\n"), &sender).is_err());
+        assert!(parse_json(&format!(
+            r#"[{{"type": "vesting", "sender": "{SENDER}", "recipient": "{SENDER}", "value": 1}}]"#
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use nimiq_hash::Blake2bHash;
+
+/// Bounded window of the most recently applied blocks seen by a subscription, used to detect
+/// duplicate deliveries and reverts of blocks that were seen before.
+#[derive(Debug)]
+pub struct BlockWindow {
+    blocks: VecDeque<Blake2bHash>,
+    capacity: usize,
+}
+
+impl BlockWindow {
+    /// Creates a new window remembering at most `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            blocks: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records that the block with the given hash was applied. Returns `false` if the block is
+    /// already in the window, i.e. if it was delivered before.
+    pub fn apply(&mut self, block_hash: &Blake2bHash) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.blocks.contains(block_hash) {
+            return false;
+        }
+        if self.blocks.len() == self.capacity {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(block_hash.clone());
+        true
+    }
+
+    /// Records that the block with the given hash was reverted, such that it is reported again if
+    /// it is applied again later. Returns whether the block was in the window.
+    pub fn revert(&mut self, block_hash: &Blake2bHash) -> bool {
+        let Some(index) = self.blocks.iter().position(|hash| hash == block_hash) else {
+            return false;
+        };
+        self.blocks.remove(index);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> Blake2bHash {
+        Blake2bHash::from([n; 32])
+    }
+
+    #[test]
+    fn detects_duplicates_and_reverts_within_the_window() {
+        let mut window = BlockWindow::new(2);
+
+        assert!(window.apply(&hash(1)));
+        assert!(!window.apply(&hash(1)));
+
+        // A reverted block is reported again when it is re-applied
+        assert!(window.revert(&hash(1)));
+        assert!(!window.revert(&hash(1)));
+        assert!(window.apply(&hash(1)));
+
+        // The oldest block is evicted once the window is full
+        assert!(window.apply(&hash(2)));
+        assert!(window.apply(&hash(3)));
+        assert!(!window.revert(&hash(1)));
+        assert!(window.revert(&hash(2)));
+    }
+}
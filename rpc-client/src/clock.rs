@@ -0,0 +1,121 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Error;
+use async_trait::async_trait;
+use nimiq_jsonrpc_client::ArcClient;
+use nimiq_rpc_interface::{
+    blockchain::{BlockchainInterface, BlockchainProxy},
+    types::ValidityStartHeight,
+};
+
+use crate::transport::Transport;
+
+/// Source of the current time used by the client.
+pub trait Clock: Send + Sync {
+    /// Returns the current time as milliseconds since the unix epoch.
+    fn now_millis(&self) -> u64;
+}
+
+/// Clock backed by the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time is before the unix epoch")
+            .as_millis() as u64
+    }
+}
+
+/// Clock that always returns the same time.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Source of the current head height used by the client, e.g. to resolve relative validity start
+/// heights of transactions.
+#[async_trait]
+pub trait HeadHeightProvider: Send + Sync {
+    /// Returns the block number of the current head.
+    async fn head_height(&self) -> Result<u32, Error>;
+
+    /// Resolves the given validity start height to an absolute block number.
+    async fn validity_start_height(
+        &self,
+        validity_start_height: ValidityStartHeight,
+    ) -> Result<u32, Error> {
+        match validity_start_height {
+            ValidityStartHeight::Absolute(block_number) => Ok(block_number),
+            relative => Ok(relative.block_number(self.head_height().await?)),
+        }
+    }
+}
+
+/// Head height provider querying the node the client is connected to.
+pub struct NetworkHeadHeight {
+    client: ArcClient<Transport>,
+}
+
+impl NetworkHeadHeight {
+    pub fn new(client: ArcClient<Transport>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HeadHeightProvider for NetworkHeadHeight {
+    async fn head_height(&self) -> Result<u32, Error> {
+        let mut blockchain = BlockchainProxy::new(self.client.clone());
+        Ok(blockchain.get_block_number().await?.data)
+    }
+}
+
+/// Head height provider that always returns the same block number.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedHeadHeight(pub u32);
+
+#[async_trait]
+impl HeadHeightProvider for FixedHeadHeight {
+    async fn head_height(&self) -> Result<u32, Error> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolves_validity_start_heights_against_the_head() {
+        let head_height = FixedHeadHeight(1000);
+
+        assert_eq!(
+            head_height
+                .validity_start_height(ValidityStartHeight::Relative(0))
+                .await
+                .unwrap(),
+            1000
+        );
+        assert_eq!(
+            head_height
+                .validity_start_height(ValidityStartHeight::Relative(20))
+                .await
+                .unwrap(),
+            1020
+        );
+        assert_eq!(
+            head_height
+                .validity_start_height(ValidityStartHeight::Absolute(42))
+                .await
+                .unwrap(),
+            42
+        );
+    }
+}
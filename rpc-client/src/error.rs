@@ -0,0 +1,160 @@
+use std::fmt;
+
+use anyhow::Error;
+use nimiq_rpc_interface::error::MEMPOOL_REJECTED_ERROR_CODE;
+
+use crate::transport::TransportError;
+
+/// Error returned by the client, classified such that scripts can tell the error classes apart by
+/// the exit code of the process, see [`AppError::exit_code`].
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// The node couldn't be reached or the connection to it was lost.
+    #[error(transparent)]
+    Connection(Error),
+    /// The node responded with an error.
+    #[error(transparent)]
+    Rpc(Error),
+    /// The arguments, config or input data given by the user are invalid.
+    #[error(transparent)]
+    InvalidInput(Error),
+    /// The node's mempool rejected a transaction.
+    #[error(transparent)]
+    TransactionRejected(Error),
+    /// Any other error.
+    #[error(transparent)]
+    Other(Error),
+}
+
+impl AppError {
+    /// Exit code for usage errors detected while parsing the command line.
+    pub const USAGE_EXIT_CODE: i32 = 4;
+
+    /// Classifies an error that occurred while running a command against the node. Only errors
+    /// of the transport count as connection errors, while e.g. failing to read a file given by
+    /// the user is invalid input, see [`input_error`].
+    pub fn classify(error: Error) -> Self {
+        if error.chain().any(|cause| cause.is::<InputError>()) {
+            return Self::InvalidInput(error);
+        }
+        if let Some(transport_error) = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<TransportError>())
+        {
+            if transport_error.is_connection_error() {
+                return Self::Connection(error);
+            }
+            if transport_error
+                .rpc_error()
+                .is_some_and(|rpc_error| rpc_error.code == MEMPOOL_REJECTED_ERROR_CODE)
+            {
+                return Self::TransactionRejected(error);
+            }
+            return Self::Rpc(error);
+        }
+        if error.chain().any(|cause| {
+            cause.is::<hex::FromHexError>() || cause.is::<nimiq_serde::DeserializeError>()
+        }) {
+            return Self::InvalidInput(error);
+        }
+        Self::Other(error)
+    }
+
+    /// Returns the exit code of the process for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Other(_) => 1,
+            AppError::Connection(_) => 2,
+            AppError::Rpc(_) => 3,
+            AppError::InvalidInput(_) => Self::USAGE_EXIT_CODE,
+            AppError::TransactionRejected(_) => 5,
+        }
+    }
+
+    /// Returns the underlying error.
+    pub fn inner(&self) -> &Error {
+        match self {
+            AppError::Connection(error)
+            | AppError::Rpc(error)
+            | AppError::InvalidInput(error)
+            | AppError::TransactionRejected(error)
+            | AppError::Other(error) => error,
+        }
+    }
+}
+
+/// Marks an error as caused by invalid input given by the user, e.g. a file that can't be read or
+/// parsed, such that it is classified as [`AppError::InvalidInput`].
+pub fn input_error(error: impl Into<Error>) -> Error {
+    InputError(error.into()).into()
+}
+
+/// Wrapper of errors caused by invalid input, see [`input_error`]. It takes the place of the
+/// wrapped error in the chain of causes.
+#[derive(Debug)]
+struct InputError(Error);
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, time::Duration};
+
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+
+    fn exit_code(error: Error) -> i32 {
+        AppError::classify(error).exit_code()
+    }
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(exit_code(anyhow!("Something went wrong")), 1);
+        assert_eq!(
+            exit_code(TransportError::Timeout(Duration::from_secs(1)).into()),
+            2
+        );
+        assert_eq!(
+            exit_code(Error::from(hex::FromHexError::OddLength).context("Invalid transaction")),
+            4
+        );
+    }
+
+    #[test]
+    fn io_errors_are_not_connection_errors() {
+        let error = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(exit_code(error), 1);
+
+        let error = input_error(io::Error::from(io::ErrorKind::NotFound))
+            .context("Could not read batch file");
+        assert_eq!(exit_code(error), 4);
+    }
+
+    #[test]
+    fn input_errors_keep_their_message() {
+        let error = input_error(anyhow!("The passwords don't match"));
+        assert_eq!(error.to_string(), "The passwords don't match");
+
+        let error = input_error(
+            Error::from(io::Error::from(io::ErrorKind::NotFound)).context("Could not read file"),
+        );
+        assert_eq!(
+            format!("{error:#}"),
+            format!(
+                "Could not read file: {}",
+                io::Error::from(io::ErrorKind::NotFound)
+            )
+        );
+    }
+}
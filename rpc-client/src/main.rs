@@ -1,29 +1,116 @@
-use anyhow::{bail, Error};
-use clap::Parser;
-use nimiq_jsonrpc_client::{
-    websocket::WebsocketClient, ArcClient, Client as RPCclient, Credentials,
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
+
+use anyhow::{anyhow, bail, Context, Error};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use directories::UserDirs;
+use nimiq_hash::Blake2bHash;
+use nimiq_jsonrpc_client::{ArcClient, Client as RPCclient, Credentials};
+use nimiq_primitives::networks::NetworkId;
 use nimiq_rpc_interface::{
-    blockchain::BlockchainProxy, consensus::ConsensusProxy, mempool::MempoolProxy,
-    network::NetworkProxy, policy::PolicyProxy, validator::ValidatorProxy, wallet::WalletProxy,
+    blockchain::{BlockchainInterface, BlockchainProxy},
+    consensus::ConsensusProxy,
+    mempool::MempoolProxy,
+    network::NetworkProxy,
+    policy::{PolicyInterface, PolicyProxy},
+    types::ValidityStartHeight,
+    validator::ValidatorProxy,
+    wallet::WalletProxy,
     zkp_component::ZKPComponentProxy,
 };
+use serde::Deserialize;
 use url::Url;
+pub mod batch;
+pub mod block_window;
+pub mod clock;
+pub mod error;
+pub mod output;
+pub mod sent_transactions;
 pub mod subcommands;
+pub mod subscriptions;
+pub mod transport;
 
-use crate::subcommands::*;
+use crate::{
+    clock::{Clock, HeadHeightProvider, NetworkHeadHeight, SystemClock},
+    error::AppError,
+    output::{Output, OutputFormat},
+    subcommands::*,
+    subscriptions::{SubscriptionGuard, SubscriptionTracker},
+    transport::Transport,
+};
 
 #[derive(Debug, Parser)]
 struct Opt {
+    /// Path to a TOML config file containing the `url`, `username` and `password` to connect with.
+    /// Can also be given in the `NIMIQ_RPC_CONFIG` environment variable, otherwise defaults to
+    /// `$HOME/.nimiq/rpc-client.toml` if it exists. Command line arguments take
+    /// precedence over the `NIMIQ_RPC_URL`, `NIMIQ_RPC_USERNAME` and `NIMIQ_RPC_PASSWORD`
+    /// environment variables, which take precedence over the config file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// URL of the RPC server to connect to. Both websocket (`ws://`, `wss://`) and HTTP
+    /// (`http://`, `https://`) endpoints are supported, but commands using subscriptions, e.g. to
+    /// follow the chain, require a websocket endpoint.
     #[clap(short)]
     url: Option<String>,
 
+    /// Username to authenticate with.
     #[clap(short = 'U')]
     username: Option<String>,
 
+    /// Password to authenticate with. Prefer the `NIMIQ_RPC_PASSWORD` environment variable or the
+    /// config file, since passwords given on the command line end up in the shell history and
+    /// are visible in process listings.
     #[clap(short = 'P')]
     password: Option<String>,
 
+    /// The format to print results in.
+    #[clap(short, long, global = true, value_enum, default_value_t)]
+    output: OutputFormat,
+
+    /// Wrap JSON results in an envelope containing the command name and its success state.
+    /// Only applies to the JSON output formats.
+    #[clap(long, global = true)]
+    envelope: bool,
+
+    /// Maximum number of subscriptions this client keeps open at the same time.
+    #[clap(long)]
+    max_subscriptions: Option<usize>,
+
+    /// Don't reconnect to the node if the connection is lost while following the chain, but stop
+    /// instead.
+    #[clap(long)]
+    no_reconnect: bool,
+
+    /// Fail if the node doesn't respond to a request within this number of seconds. Items streamed
+    /// by subscriptions, e.g. of commands that follow the chain, may take arbitrarily long to
+    /// arrive.
+    #[clap(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Number of times connecting to the node is retried if it isn't reachable, e.g. because it
+    /// is still starting up. The delay between two attempts doubles after each attempt.
+    /// Authentication failures are not retried.
+    #[clap(long, default_value_t = 0)]
+    connect_retries: u32,
+
+    /// Delay before retrying to connect to the node for the first time.
+    #[clap(long, value_name = "SECS", default_value_t = 1)]
+    connect_retry_delay: u64,
+
+    /// Aborts before running the command if the node is not on this network, e.g.
+    /// `main-albatross` or `test-albatross`.
+    #[clap(long)]
+    expect_network: Option<NetworkId>,
+
+    /// Aborts before running the command if the hash of the node's genesis block is not this one.
+    #[clap(long)]
+    expect_genesis: Option<Blake2bHash>,
+
     #[clap(subcommand)]
     command: Command,
 }
@@ -62,6 +149,10 @@ enum Command {
     /// Shows the zkp information.
     #[clap(flatten)]
     Zkp(ZKPComponentCommand),
+
+    /// Builds and signs transactions locally, without connecting to a node.
+    #[clap(subcommand)]
+    SignOffline(OfflineCommand),
 }
 
 impl Command {
@@ -75,25 +166,122 @@ impl Command {
             Command::Mempool(command) => command.handle_subcommand(client).await,
             Command::Validator(command) => command.handle_subcommand(client).await,
             Command::Zkp(command) => command.handle_subcommand(client).await,
+            Command::SignOffline(_) => {
+                unreachable!("offline commands are run without connecting to a node")
+            }
         }
     }
 }
 
+/// Connection settings read from the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    url: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Config {
+    /// Returns the default location of the config file, i.e. `$HOME/.nimiq/rpc-client.toml`.
+    fn default_path() -> Option<PathBuf> {
+        UserDirs::new().map(|dirs| dirs.home_dir().join(".nimiq").join("rpc-client.toml"))
+    }
+
+    /// Loads the config file from the given path, which must exist. If no path is given, the
+    /// config file at the default location is loaded if it exists.
+    fn load(path: Option<&Path>) -> Result<Self, Error> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let config = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&config)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+}
+
+/// Returns the value given on the command line, falling back to the environment variable `env`
+/// and then to the value from the config file.
+fn setting(cli: Option<String>, env: &str, config: Option<String>) -> Option<String> {
+    cli.or_else(|| std::env::var(env).ok()).or(config)
+}
+
 pub struct Client {
-    pub ws_client: ArcClient<WebsocketClient>,
-    pub policy: PolicyProxy<ArcClient<WebsocketClient>>,
-    blockchain: BlockchainProxy<ArcClient<WebsocketClient>>,
-    pub consensus: ConsensusProxy<ArcClient<WebsocketClient>>,
-    pub mempool: MempoolProxy<ArcClient<WebsocketClient>>,
-    pub wallet: WalletProxy<ArcClient<WebsocketClient>>,
-    pub validator: ValidatorProxy<ArcClient<WebsocketClient>>,
-    pub network: NetworkProxy<ArcClient<WebsocketClient>>,
-    pub zkp_component: ZKPComponentProxy<ArcClient<WebsocketClient>>,
+    pub rpc_client: ArcClient<Transport>,
+    pub policy: PolicyProxy<ArcClient<Transport>>,
+    blockchain: BlockchainProxy<ArcClient<Transport>>,
+    pub consensus: ConsensusProxy<ArcClient<Transport>>,
+    pub mempool: MempoolProxy<ArcClient<Transport>>,
+    pub wallet: WalletProxy<ArcClient<Transport>>,
+    pub validator: ValidatorProxy<ArcClient<Transport>>,
+    pub network: NetworkProxy<ArcClient<Transport>>,
+    pub zkp_component: ZKPComponentProxy<ArcClient<Transport>>,
+    pub output: Output,
+    clock: Arc<dyn Clock>,
+    head_height: Arc<dyn HeadHeightProvider>,
+    custom_head_height: bool,
+    subscriptions: SubscriptionTracker,
+    timeout: Option<Duration>,
+    url: Url,
+    credentials: Option<Credentials>,
+    reconnect: bool,
+    supports_subscriptions: bool,
 }
 
 impl Client {
-    pub async fn new(url: Url, credentials: Option<Credentials>) -> Result<Self, Error> {
-        let client = ArcClient::new(WebsocketClient::new(url, credentials).await?);
+    /// Delay before the first attempt to reconnect after the connection was lost.
+    const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+    /// Maximum delay between two attempts to reconnect.
+    const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+    pub async fn new(
+        url: Url,
+        credentials: Option<Credentials>,
+        output: Output,
+    ) -> Result<Self, Error> {
+        Self::new_with_retries(url, credentials, output, None, 0, Duration::ZERO).await
+    }
+
+    /// Connects to the node like [`Self::new`], but retries up to `retries` times if the node
+    /// isn't reachable. The delay between two attempts starts at `delay` and doubles after each
+    /// attempt. If a `timeout` is given, each attempt and each request fails if the node doesn't
+    /// respond within it.
+    pub async fn new_with_retries(
+        url: Url,
+        credentials: Option<Credentials>,
+        output: Output,
+        timeout: Option<Duration>,
+        retries: u32,
+        mut delay: Duration,
+    ) -> Result<Self, Error> {
+        let mut attempt = 0;
+        let transport = loop {
+            match Transport::connect(url.clone(), credentials.clone(), timeout).await {
+                Ok(transport) => break transport,
+                Err(error) if attempt < retries && Transport::is_connection_error(&error) => {
+                    attempt += 1;
+                    log::debug!(
+                        %url,
+                        %error,
+                        attempt,
+                        retries,
+                        delay_secs = delay.as_secs_f32(),
+                        "Failed to connect, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(error) => return Err(error),
+            }
+        };
+        let supports_subscriptions = transport.supports_subscriptions();
+        let client = ArcClient::new(transport);
 
         Ok(Self {
             policy: PolicyProxy::new(client.clone()),
@@ -104,32 +292,260 @@ impl Client {
             validator: ValidatorProxy::new(client.clone()),
             network: NetworkProxy::new(client.clone()),
             zkp_component: ZKPComponentProxy::new(client.clone()),
-            ws_client: client,
+            clock: Arc::new(SystemClock),
+            head_height: Arc::new(NetworkHeadHeight::new(client.clone())),
+            custom_head_height: false,
+            subscriptions: SubscriptionTracker::default(),
+            timeout,
+            rpc_client: client,
+            output,
+            url,
+            credentials,
+            reconnect: true,
+            supports_subscriptions,
         })
     }
 
-    /// Closes the WS connection
+    /// Sets whether the client reconnects to the node if the connection is lost, see
+    /// [`Self::reconnect`].
+    pub fn with_reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Re-establishes the connection to the node after it was lost, e.g. because a subscription
+    /// ended. Attempts to reconnect are retried with an exponentially increasing delay until one
+    /// succeeds. Returns `false` without reconnecting if reconnecting is disabled.
+    pub async fn reconnect(&mut self) -> Result<bool, Error> {
+        if !self.reconnect {
+            return Ok(false);
+        }
+
+        let mut delay = Self::RECONNECT_INITIAL_DELAY;
+        loop {
+            self.output.info(format!(
+                "Connection to {} lost, reconnecting in {}s",
+                self.url,
+                delay.as_secs()
+            ));
+            tokio::time::sleep(delay).await;
+            match Transport::connect(self.url.clone(), self.credentials.clone(), self.timeout).await
+            {
+                Ok(transport) => {
+                    self.set_connection(ArcClient::new(transport));
+                    return Ok(true);
+                }
+                Err(error) => {
+                    self.output.info(format!("Reconnecting failed: {error}"));
+                    delay = (delay * 2).min(Self::RECONNECT_MAX_DELAY);
+                }
+            }
+        }
+    }
+
+    /// Replaces the connection used by all proxies.
+    fn set_connection(&mut self, client: ArcClient<Transport>) {
+        self.policy = PolicyProxy::new(client.clone());
+        self.blockchain = BlockchainProxy::new(client.clone());
+        self.consensus = ConsensusProxy::new(client.clone());
+        self.mempool = MempoolProxy::new(client.clone());
+        self.wallet = WalletProxy::new(client.clone());
+        self.validator = ValidatorProxy::new(client.clone());
+        self.network = NetworkProxy::new(client.clone());
+        self.zkp_component = ZKPComponentProxy::new(client.clone());
+        if !self.custom_head_height {
+            self.head_height = Arc::new(NetworkHeadHeight::new(client.clone()));
+        }
+        self.rpc_client = client;
+    }
+
+    /// Replaces the clock of this client, e.g. with a [`FixedClock`](clock::FixedClock) for tests.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Replaces the head height provider of this client, e.g. with a
+    /// [`FixedHeadHeight`](clock::FixedHeadHeight) for tests.
+    pub fn with_head_height(mut self, head_height: impl HeadHeightProvider + 'static) -> Self {
+        self.head_height = Arc::new(head_height);
+        self.custom_head_height = true;
+        self
+    }
+
+    /// Limits the number of subscriptions this client keeps open at the same time.
+    pub fn with_max_subscriptions(mut self, max_subscriptions: Option<usize>) -> Self {
+        self.subscriptions = SubscriptionTracker::new(max_subscriptions);
+        self
+    }
+
+    /// Registers a new subscription, failing if the maximum number of concurrent subscriptions
+    /// is reached. The returned guard must be held for as long as the subscription is in use.
+    pub fn acquire_subscription(&self) -> Result<SubscriptionGuard, Error> {
+        if !self.supports_subscriptions {
+            bail!(
+                "This command requires subscriptions, which are not supported over HTTP. Connect to \
+                 the websocket endpoint of the node instead, e.g. ws://127.0.0.1:8648/ws"
+            );
+        }
+        self.subscriptions.acquire()
+    }
+
+    /// Returns the number of subscriptions that are currently active.
+    pub fn active_subscriptions(&self) -> usize {
+        self.subscriptions.active()
+    }
+
+    /// Returns the current time in milliseconds since the unix epoch.
+    pub fn now_millis(&self) -> u64 {
+        self.clock.now_millis()
+    }
+
+    /// Returns the block number of the current head.
+    pub async fn head_height(&self) -> Result<u32, Error> {
+        self.head_height.head_height().await
+    }
+
+    /// Resolves the given validity start height against the current head, such that transactions
+    /// are always constructed with an absolute validity start height.
+    pub async fn validity_start_height(
+        &self,
+        validity_start_height: ValidityStartHeight,
+    ) -> Result<ValidityStartHeight, Error> {
+        Ok(ValidityStartHeight::Absolute(
+            self.head_height
+                .validity_start_height(validity_start_height)
+                .await?,
+        ))
+    }
+
+    /// Verifies that the node is on the expected network and has the expected genesis block, if
+    /// any of them are given, such that commands aren't run against a node of another network by
+    /// accident.
+    pub async fn check_network(
+        &mut self,
+        expected_network: Option<NetworkId>,
+        expected_genesis: Option<&Blake2bHash>,
+    ) -> Result<(), Error> {
+        if expected_network.is_none() && expected_genesis.is_none() {
+            return Ok(());
+        }
+
+        let genesis_block_number = self
+            .policy
+            .get_policy_constants()
+            .await?
+            .data
+            .genesis_block_number;
+        let genesis = self
+            .blockchain
+            .get_block_by_number(genesis_block_number, Some(false))
+            .await
+            .context("Failed to fetch the genesis block of the node")?
+            .data;
+
+        if let Some(network) = expected_network {
+            if genesis.network != network {
+                bail!(
+                    "The node is on network {}, but {network} was expected",
+                    genesis.network
+                );
+            }
+        }
+        if let Some(genesis_hash) = expected_genesis {
+            if &genesis.hash != genesis_hash {
+                bail!(
+                    "The node's genesis block is {}, but {genesis_hash} was expected",
+                    genesis.hash
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes the connection to the node
     pub async fn close(&mut self) {
-        self.ws_client.close().await;
+        self.rpc_client.close().await;
     }
 }
 
-async fn run_app(opt: Opt) -> Result<(), Error> {
-    let url = opt
-        .url
+async fn run_app(opt: Opt, output: Output) -> Result<(), AppError> {
+    // Offline signing, decoding and hashing must work without a node to connect to. They only
+    // fail because of invalid input.
+    let command = match opt.command {
+        Command::SignOffline(command) => {
+            return command.run(&output).map_err(AppError::InvalidInput)
+        }
+        Command::Transaction(TransactionCommand::DecodeTransaction { raw }) => {
+            return decode_transaction(&raw, &output).map_err(AppError::InvalidInput)
+        }
+        Command::Transaction(TransactionCommand::HtlcHash {
+            pre_image,
+            hash_count,
+            hash_algorithm,
+        }) => {
+            return htlc_hash(&pre_image, hash_count, &hash_algorithm, &output)
+                .map_err(AppError::InvalidInput)
+        }
+        Command::Transaction(TransactionCommand::VerifyHtlc {
+            pre_image,
+            hash_root,
+            hash_count,
+            hash_algorithm,
+        }) => {
+            return verify_htlc(&pre_image, hash_root, hash_count, &hash_algorithm, &output)
+                .map_err(AppError::InvalidInput)
+        }
+        command => command,
+    };
+
+    let config_path = opt
+        .config
+        .or_else(|| std::env::var_os("NIMIQ_RPC_CONFIG").map(PathBuf::from));
+    let config = Config::load(config_path.as_deref()).map_err(AppError::InvalidInput)?;
+
+    let url = setting(opt.url, "NIMIQ_RPC_URL", config.url)
         .as_deref()
         .unwrap_or("ws://127.0.0.1:8648/ws")
-        .parse()?;
+        .parse()
+        .map_err(|error: url::ParseError| AppError::InvalidInput(error.into()))?;
 
-    let credentials = match (&opt.username, &opt.password) {
+    let username = setting(opt.username, "NIMIQ_RPC_USERNAME", config.username);
+    let password = setting(opt.password, "NIMIQ_RPC_PASSWORD", config.password);
+    let credentials = match (&username, &password) {
         (Some(username), Some(password)) => Some(Credentials::new(username, password)),
         (None, None) => None,
-        _ => bail!("Both username and password needs to be specified."),
+        _ => {
+            return Err(AppError::InvalidInput(anyhow!(
+                "Both username and password needs to be specified."
+            )))
+        }
     };
 
-    let client = Client::new(url, credentials).await?;
+    let mut client = Client::new_with_retries(
+        url,
+        credentials,
+        output,
+        opt.timeout.map(Duration::from_secs),
+        opt.connect_retries,
+        Duration::from_secs(opt.connect_retry_delay),
+    )
+    .await
+    .map_err(|error| {
+        if Transport::is_connection_error(&error) {
+            AppError::Connection(error)
+        } else {
+            AppError::classify(error)
+        }
+    })?
+    .with_max_subscriptions(opt.max_subscriptions)
+    .with_reconnect(!opt.no_reconnect);
+    client
+        .check_network(opt.expect_network, opt.expect_genesis.as_ref())
+        .await
+        .map_err(AppError::classify)?;
 
-    let mut client = opt.command.run(client).await?;
+    let mut client = command.run(client).await.map_err(AppError::classify)?;
     client.close().await;
     Ok(())
 }
@@ -145,8 +561,25 @@ async fn main() {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    if let Err(e) = run_app(Opt::parse()).await {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+    let matches = Opt::command()
+        .try_get_matches()
+        .unwrap_or_else(|e| exit_with_usage_error(e));
+    let opt = Opt::from_arg_matches(&matches).unwrap_or_else(|e| exit_with_usage_error(e));
+    let command = matches.subcommand_name().map(String::from);
+    let output = Output::new(opt.output, command.filter(|_| opt.envelope));
+
+    if let Err(e) = run_app(opt, output.clone()).await {
+        output.print_error(e.inner());
+        std::process::exit(e.exit_code());
+    }
+}
+
+/// Prints the given command line parsing error and exits. Usage errors exit with the same code as
+/// other invalid input, while e.g. `--help` exits successfully.
+fn exit_with_usage_error(error: clap::Error) -> ! {
+    if error.use_stderr() {
+        let _ = error.print();
+        std::process::exit(AppError::USAGE_EXIT_CODE);
     }
+    error.exit()
 }
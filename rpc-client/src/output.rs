@@ -0,0 +1,130 @@
+use std::fmt::{Debug, Display};
+
+use anyhow::Error;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+/// The format command results are printed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// The debug representation of the results.
+    #[default]
+    Debug,
+    /// JSON, with every result printed on a single line.
+    Json,
+    /// Pretty printed JSON. Items of streaming commands are still printed on a single line each.
+    JsonPretty,
+}
+
+/// Prints command results either in their debug representation or as JSON.
+///
+/// In JSON mode the items of streaming commands are always printed as one JSON object per line
+/// (NDJSON), while single results are pretty printed if requested. If an envelope is requested, each result is
+/// additionally wrapped in an object carrying the command name and whether the command succeeded.
+#[derive(Clone, Debug, Default)]
+pub struct Output {
+    format: OutputFormat,
+    envelope: Option<String>,
+}
+
+impl Output {
+    /// Creates a new output. `envelope` is the name of the executed command if results should be
+    /// wrapped in an envelope, which is only done in JSON mode.
+    pub fn new(format: OutputFormat, envelope: Option<String>) -> Self {
+        Self {
+            format,
+            envelope: envelope.filter(|_| format != OutputFormat::Debug),
+        }
+    }
+
+    /// Whether results are printed as JSON.
+    pub fn is_json(&self) -> bool {
+        self.format != OutputFormat::Debug
+    }
+
+    /// Prints a command result.
+    pub fn print<T: Debug + Serialize + ?Sized>(&self, result: &T) -> Result<(), Error> {
+        if self.is_json() {
+            self.print_json(result, self.format == OutputFormat::JsonPretty)
+        } else {
+            println!("{result:#?}");
+            Ok(())
+        }
+    }
+
+    /// Prints a command result that has a custom human readable representation.
+    pub fn print_with<T: Serialize + ?Sized>(
+        &self,
+        result: &T,
+        text: impl Display,
+    ) -> Result<(), Error> {
+        if self.is_json() {
+            self.print_json(result, self.format == OutputFormat::JsonPretty)
+        } else {
+            println!("{text}");
+            Ok(())
+        }
+    }
+
+    /// Prints an item emitted by a streaming command. In JSON mode every item is printed on a
+    /// single line.
+    pub fn print_item<T: Debug + Serialize + ?Sized>(&self, item: &T) -> Result<(), Error> {
+        if self.is_json() {
+            self.print_json(item, false)
+        } else {
+            println!("{item:#?}");
+            Ok(())
+        }
+    }
+
+    /// Prints an item emitted by a streaming command that has a custom human readable
+    /// representation.
+    pub fn print_item_with<T: Serialize + ?Sized>(
+        &self,
+        item: &T,
+        text: impl Display,
+    ) -> Result<(), Error> {
+        if self.is_json() {
+            self.print_json(item, false)
+        } else {
+            println!("{text}");
+            Ok(())
+        }
+    }
+
+    /// Prints an informational message accompanying the result. In JSON mode the message is
+    /// written to stderr to keep stdout machine-readable.
+    pub fn info(&self, message: impl Display) {
+        if self.is_json() {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+
+    /// Prints the error a command failed with.
+    pub fn print_error(&self, error: &Error) {
+        match &self.envelope {
+            Some(command) => println!(
+                "{}",
+                json!({ "command": command, "ok": false, "error": error.to_string() })
+            ),
+            None => eprintln!("Error: {error}"),
+        }
+    }
+
+    fn print_json<T: Serialize + ?Sized>(&self, result: &T, pretty: bool) -> Result<(), Error> {
+        let value = match &self.envelope {
+            Some(command) => json!({ "command": command, "ok": true, "result": result }),
+            None => serde_json::to_value(result)?,
+        };
+        let json = if pretty {
+            serde_json::to_string_pretty(&value)?
+        } else {
+            serde_json::to_string(&value)?
+        };
+        println!("{json}");
+        Ok(())
+    }
+}
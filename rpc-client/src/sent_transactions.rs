@@ -0,0 +1,107 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Error};
+use directories::UserDirs;
+use nimiq_keys::Address;
+
+use crate::error::input_error;
+
+/// Small local store of the raw transactions sent through this client, keyed by sender.
+///
+/// Every sender gets its own file inside the store directory, containing one hex encoded
+/// transaction per line in the order they were sent. This allows re-broadcasting transactions
+/// that were dropped from the mempool (e.g. evicted during congestion). Failing to access a store
+/// whose location is given by the user is reported as invalid input.
+pub struct SentTransactionStore {
+    path: PathBuf,
+    /// Whether the location of the store was given by the user.
+    user_supplied: bool,
+}
+
+impl SentTransactionStore {
+    /// Environment variable that can be used to override the location of the store.
+    const PATH_ENV: &'static str = "NIMIQ_RPC_TX_STORE";
+
+    /// Opens the store at the default location, i.e. the path given by `NIMIQ_RPC_TX_STORE` or
+    /// `$HOME/.nimiq/rpc-client/sent-transactions` otherwise.
+    pub fn open_default() -> Result<Self, Error> {
+        if let Some(path) = std::env::var_os(Self::PATH_ENV) {
+            return Ok(Self {
+                path: PathBuf::from(path),
+                user_supplied: true,
+            });
+        }
+
+        let path = UserDirs::new()
+            .map(|dirs| {
+                dirs.home_dir()
+                    .join(".nimiq")
+                    .join("rpc-client")
+                    .join("sent-transactions")
+            })
+            .ok_or_else(|| anyhow!("Could not determine the home directory"))?;
+        Ok(Self {
+            path,
+            user_supplied: false,
+        })
+    }
+
+    /// Converts an error accessing the store, which is invalid input if the user gave its location.
+    fn io_error(&self, error: std::io::Error) -> Error {
+        if self.user_supplied {
+            input_error(error)
+        } else {
+            error.into()
+        }
+    }
+
+    fn sender_path(&self, sender: &Address) -> PathBuf {
+        self.path.join(sender.to_hex())
+    }
+
+    /// Appends a raw transaction to the list of transactions sent by `sender`.
+    pub fn push(&self, sender: &Address, raw_tx: &str) -> Result<(), Error> {
+        fs::create_dir_all(&self.path).map_err(|e| self.io_error(e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.sender_path(sender))
+            .map_err(|e| self.io_error(e))?;
+        writeln!(file, "{raw_tx}").map_err(|e| self.io_error(e))?;
+        Ok(())
+    }
+
+    /// Returns all raw transactions stored for `sender` in the order they were sent.
+    pub fn get(&self, sender: &Address) -> Result<Vec<String>, Error> {
+        match fs::read_to_string(self.sender_path(sender)) {
+            Ok(content) => Ok(content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(self.io_error(e)),
+        }
+    }
+
+    /// Replaces the raw transactions stored for `sender`.
+    pub fn replace(&self, sender: &Address, raw_txs: &[String]) -> Result<(), Error> {
+        if raw_txs.is_empty() {
+            match fs::remove_file(self.sender_path(sender)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(self.io_error(e)),
+                _ => return Ok(()),
+            }
+        }
+
+        fs::create_dir_all(&self.path).map_err(|e| self.io_error(e))?;
+        let mut content = raw_txs.join("\n");
+        content.push('\n');
+        fs::write(self.sender_path(sender), content).map_err(|e| self.io_error(e))?;
+        Ok(())
+    }
+}
@@ -1,10 +1,17 @@
-use anyhow::Error;
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context, Error};
 use async_trait::async_trait;
 use clap::Parser;
 use nimiq_keys::{Address, Ed25519PublicKey, Ed25519Signature};
 use nimiq_rpc_interface::{blockchain::BlockchainInterface, wallet::WalletInterface};
 
-use crate::Client;
+use super::blockchain_subcommands::validator_status;
+use crate::{error::input_error, Client};
 
 #[async_trait]
 pub trait HandleSubcommand {
@@ -22,19 +29,52 @@ pub enum AccountCommand {
 
     /// Creates a new account. This doesn't unlock the account automatically.
     New {
-        /// Encryption password.
+        /// Encryption password. If absent, the password is prompted for.
         #[clap(short = 'P', long)]
         password: Option<String>,
+
+        /// Creates the account without encrypting its key.
+        #[clap(long, conflicts_with = "password")]
+        no_password: bool,
     },
 
     /// Imports an existing account. The account remains locked after this operation.
     Import {
+        /// Encryption password. If absent, the password is prompted for.
         #[clap(short = 'P', long)]
         password: Option<String>,
+
+        /// Imports the account without encrypting its key.
+        #[clap(long, conflicts_with = "password")]
+        no_password: bool,
+
         /// The private key of the account to be unlocked.
         key_data: String,
     },
 
+    /// Exports the private key of an account in the format accepted by `import`. The account must
+    /// either be unlocked or its password must be given.
+    ///
+    /// With `--encrypted`, the key is exported as a keyfile encrypted with the given password
+    /// instead, e.g. for backups. The account must be unlocked then.
+    Export {
+        /// The account's address.
+        address: Address,
+
+        /// The account's password. Not needed if the account is unlocked. With `--encrypted`, the
+        /// password to encrypt the keyfile with. If absent, it is prompted for then.
+        #[clap(short = 'P', long)]
+        password: Option<String>,
+
+        /// Exports the key encrypted instead of in plain text.
+        #[clap(long)]
+        encrypted: bool,
+
+        /// Writes the encrypted keyfile to this file instead of printing it.
+        #[clap(long, requires = "encrypted")]
+        out_file: Option<PathBuf>,
+    },
+
     /// Checks if account is imported.
     IsImported {
         /// The account's address.
@@ -49,11 +89,18 @@ pub enum AccountCommand {
 
     /// Unlocks an account.
     Unlock {
+        /// The account's password. If absent, the password is prompted for.
         #[clap(short = 'P', long)]
         password: Option<String>,
 
         /// The account's address.
         address: Address,
+
+        /// Locks the account again after this duration, given in seconds or with a unit, e.g.
+        /// `90`, `10m`, `1h` or `1d`. With a duration of `0`, the account stays unlocked until it
+        /// is locked explicitly.
+        #[clap(long, value_parser = parse_duration, default_value = "1h")]
+        duration: u64,
     },
 
     /// Checks if account is unlocked.
@@ -99,6 +146,18 @@ pub enum AccountCommand {
     Get {
         /// The account's address.
         address: Address,
+
+        /// Fetches the account as it was at this block number instead of at the head. Only blocks
+        /// since the latest macro block can be queried, older states aren't retained by the node.
+        #[clap(long)]
+        at: Option<u32>,
+    },
+
+    /// Shows the stake of a staker: the validator it delegates to and the state of that
+    /// validator, as well as its active, inactive and retired balance.
+    StakerInfo {
+        /// The staker's address.
+        address: Address,
     },
 }
 
@@ -108,6 +167,19 @@ impl HandleSubcommand for AccountCommand {
         match self {
             AccountCommand::List { short } => {
                 let accounts = client.wallet.list_accounts().await?.data;
+                if client.output.is_json() {
+                    if short {
+                        client.output.print(&accounts)?;
+                    } else {
+                        let mut account_states = vec![];
+                        for address in accounts {
+                            account_states
+                                .push(client.blockchain.get_account_by_address(address).await?);
+                        }
+                        client.output.print(&account_states)?;
+                    }
+                    return Ok(client);
+                }
                 for address in &accounts {
                     if short {
                         println!("{}", address.to_user_friendly_address());
@@ -120,43 +192,116 @@ impl HandleSubcommand for AccountCommand {
                     }
                 }
             }
-            AccountCommand::New { password } => {
-                println!("{:#?}", client.wallet.create_account(password).await?);
+            AccountCommand::New {
+                password,
+                no_password,
+            } => {
+                let password = new_password_or_prompt(password, no_password)?;
+                client
+                    .output
+                    .print(&client.wallet.create_account(password).await?)?;
             }
-            AccountCommand::Import { password, key_data } => {
+            AccountCommand::Import {
+                password,
+                no_password,
+                key_data,
+            } => {
+                let password = new_password_or_prompt(password, no_password)?;
                 let address = client.wallet.import_raw_key(key_data, password).await?;
-                println!("{address:#?}");
+                client.output.print(&address)?;
+            }
+            AccountCommand::Export {
+                address,
+                password,
+                encrypted: true,
+                out_file,
+            } => {
+                if !client
+                    .wallet
+                    .is_account_unlocked(address.clone())
+                    .await?
+                    .data
+                {
+                    return Err(input_error(anyhow!(
+                        "Failed to export the key, account {address} is locked"
+                    )));
+                }
+                let password = match password {
+                    Some(password) => password,
+                    None => prompt_new_password()?,
+                };
+                if password.is_empty() {
+                    return Err(input_error(anyhow!(
+                        "Empty password, the keyfile must be encrypted with a password"
+                    )));
+                }
+                let key_data = client
+                    .wallet
+                    .export_encrypted_key(address, Some(password))
+                    .await
+                    .context("Failed to export the key")?
+                    .data;
+                match out_file {
+                    Some(path) => {
+                        fs::write(&path, format!("{key_data}\n"))
+                            .map_err(input_error)
+                            .with_context(|| {
+                                format!("Failed to write the keyfile to {}", path.display())
+                            })?;
+                        client
+                            .output
+                            .info(format!("Wrote encrypted keyfile to {}", path.display()));
+                    }
+                    None => client.output.print(&key_data)?,
+                }
+            }
+            AccountCommand::Export {
+                address, password, ..
+            } => {
+                let key_data = client.wallet.export_raw_key(address, password).await?;
+                client.output.info(
+                    "WARNING: The following private key gives full control over the account. \
+                     Keep it secret.",
+                );
+                client.output.print(&key_data)?;
             }
             AccountCommand::IsImported { address } => {
-                println!("{:#?}", client.wallet.is_account_imported(address).await?);
+                client
+                    .output
+                    .print(&client.wallet.is_account_imported(address).await?)?;
             }
             AccountCommand::Lock { address } => {
                 client.wallet.lock_account(address).await?;
+                if client.output.is_json() {
+                    client.output.print(&())?;
+                }
             }
             AccountCommand::Unlock {
-                address, password, ..
+                address,
+                password,
+                duration,
             } => {
-                // TODO: Duration
-                println!(
-                    "{:#?}",
-                    client
+                let password = password_or_prompt(password)?;
+                client.output.print(
+                    &client
                         .wallet
-                        .unlock_account(address, password, None)
-                        .await?
-                );
+                        .unlock_account(address, password, Some(duration))
+                        .await?,
+                )?;
             }
             AccountCommand::IsUnlocked { address } => {
-                println!("{:#?}", client.wallet.is_account_unlocked(address).await?);
+                client
+                    .output
+                    .print(&client.wallet.is_account_unlocked(address).await?)?;
             }
             AccountCommand::Sign {
                 message,
                 address,
                 is_hex,
             } => {
-                println!(
-                    "{:#?}",
-                    client.wallet.sign(message, address, None, is_hex).await?
-                );
+                client
+                    .output
+                    .print(&client.wallet.sign(message, address, None, is_hex).await?)?;
             }
             AccountCommand::VerifySignature {
                 message,
@@ -164,26 +309,176 @@ impl HandleSubcommand for AccountCommand {
                 signature,
                 is_hex,
             } => {
-                println!(
-                    "{:#?}",
-                    client
+                client.output.print(
+                    &client
                         .wallet
                         .verify_signature(message, public_key, signature, is_hex)
-                        .await?
-                );
+                        .await?,
+                )?;
             }
-            AccountCommand::Get { address } => {
-                println!(
-                    "{:#?}",
-                    client.blockchain.get_account_by_address(address).await?
-                );
+            AccountCommand::Get { address, at } => {
+                let account = match at {
+                    Some(block_number) => client
+                        .blockchain
+                        .get_account_by_address_at(address, block_number)
+                        .await
+                        .context("Failed to fetch the historical account state")?,
+                    None => client.blockchain.get_account_by_address(address).await?,
+                };
+                client.output.print(&account)?;
+            }
+
+            AccountCommand::StakerInfo { address } => {
+                let staker = client.blockchain.get_staker_by_address(address).await?;
+                let head = staker.metadata.block_number;
+                let staker = staker.data;
+                let delegation_status = match &staker.delegation {
+                    Some(validator_address) => {
+                        Some(validator_status(&mut client, validator_address).await?)
+                    }
+                    None => None,
+                };
+
+                let mut text = format!("Staker {}", staker.address);
+                text += &match (&staker.delegation, delegation_status) {
+                    (Some(validator_address), Some(status)) => {
+                        format!("\ndelegation: {validator_address} ({status})")
+                    }
+                    _ => "\ndelegation: none".to_string(),
+                };
+                text += &format!("\nactive balance: {} NIM", staker.balance);
+                text += &format!("\ninactive balance: {} NIM", staker.inactive_balance);
+                match staker.inactive_from {
+                    Some(inactive_from) if inactive_from > head => {
+                        text +=
+                            &format!(" (inactive from block #{inactive_from}, can be reactivated)");
+                    }
+                    Some(inactive_from) => {
+                        text += &format!(
+                            " (inactive since block #{inactive_from}, can be retired or reactivated)"
+                        );
+                    }
+                    None => {}
+                }
+                text += &format!("\nretired balance: {} NIM", staker.retired_balance);
+                if !staker.retired_balance.is_zero() {
+                    text += " (can be removed)";
+                }
+
+                let mut result = serde_json::to_value(&staker)?;
+                result["delegationStatus"] = serde_json::json!(delegation_status);
+                client.output.print_with(&result, text)?;
             }
 
             AccountCommand::GetAll {} => {
-                println!("{:#?}", client.blockchain.get_accounts().await?);
+                client
+                    .output
+                    .print(&client.blockchain.get_accounts().await?)?;
             }
         }
 
         Ok(client)
     }
 }
+
+/// Parses a duration given in seconds, optionally followed by one of the units `s`, `m`, `h` or
+/// `d`, into seconds.
+fn parse_duration(duration: &str) -> Result<u64, String> {
+    let duration = duration.trim();
+    let (value, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => duration.split_at(index),
+        None => (duration, "s"),
+    };
+    let multiplier = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit in `{duration}`, expected s, m, h or d"
+            ))
+        }
+    };
+    value
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid duration `{duration}`"))
+}
+
+/// Returns the given password or, if absent, prompts for it on the terminal without echoing it.
+/// If stdin is not a terminal, the password is read from the first line of stdin instead, such
+/// that it can be piped in by scripts. An empty password is treated as no password.
+fn password_or_prompt(password: Option<String>) -> Result<Option<String>, Error> {
+    if password.is_some() {
+        return Ok(password);
+    }
+
+    let password = if io::stdin().is_terminal() {
+        rpassword::prompt_password("Password: ")?
+    } else {
+        read_password_line()?
+    };
+    Ok(Some(password).filter(|password| !password.is_empty()))
+}
+
+/// Returns the password to encrypt a new or imported account with. Like [`password_or_prompt`],
+/// but the password must be entered twice on the terminal. To not create unencrypted keys by
+/// accident, an empty password is rejected unless `no_password` is set.
+fn new_password_or_prompt(
+    password: Option<String>,
+    no_password: bool,
+) -> Result<Option<String>, Error> {
+    if no_password {
+        return Ok(None);
+    }
+    if password.is_some() {
+        return Ok(password);
+    }
+
+    let password = prompt_new_password()?;
+    if password.is_empty() {
+        return Err(input_error(anyhow!(
+            "Empty password, use `--no-password` to store the key unencrypted"
+        )));
+    }
+    Ok(Some(password))
+}
+
+/// Prompts for a new password and its confirmation if stdin is a terminal, otherwise reads it from
+/// the first line of stdin.
+fn prompt_new_password() -> Result<String, Error> {
+    if !io::stdin().is_terminal() {
+        return read_password_line();
+    }
+    let password = rpassword::prompt_password("Password: ")?;
+    if rpassword::prompt_password("Confirm password: ")? != password {
+        return Err(input_error(anyhow!("The passwords don't match")));
+    }
+    Ok(password)
+}
+
+/// Reads a password from the first line of stdin.
+fn read_password_line() -> Result<String, Error> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("30s"), Ok(30));
+        assert_eq!(parse_duration("10m"), Ok(600));
+        assert_eq!(parse_duration("1h"), Ok(3600));
+        assert_eq!(parse_duration("2d"), Ok(172_800));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("10w").is_err());
+    }
+}
@@ -1,13 +1,67 @@
-use anyhow::Error;
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Context, Error};
 use async_trait::async_trait;
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Parser, ValueEnum};
 use futures::StreamExt;
+use hmac::{Hmac, Mac};
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
-use nimiq_rpc_interface::{blockchain::BlockchainInterface, types::LogType};
+use nimiq_primitives::coin::Coin;
+use nimiq_rpc_interface::{
+    blockchain::BlockchainInterface,
+    consensus::ConsensusInterface,
+    network::NetworkInterface,
+    policy::PolicyInterface,
+    types::{BlockAdditionalFields, BlockLog, Inherent, LogType, Validator},
+};
+use serde_json::json;
+use sha2::Sha256;
 
 use super::accounts_subcommands::HandleSubcommand;
-use crate::Client;
+use crate::{block_window::BlockWindow, output::Output, Client};
+
+/// Returns the status of the validator with the given address: `active`, `inactive`, `jailed`,
+/// `retired` or `dangling` if the validator doesn't exist.
+pub(crate) async fn validator_status(
+    client: &mut Client,
+    address: &Address,
+) -> Result<&'static str, Error> {
+    let validator = client
+        .blockchain
+        .find_validator_by_address(address.clone())
+        .await?
+        .data;
+
+    Ok(match validator {
+        None => "dangling",
+        Some(validator) if validator.retired => "retired",
+        Some(validator) if validator.jailed_from.is_some() => "jailed",
+        Some(validator) if validator.inactivity_flag.is_some() => "inactive",
+        Some(_) => "active",
+    })
+}
+
+/// Sorts the validators in the given order.
+fn sort_validators(validators: &mut [Validator], order: StakesOrder) {
+    match order {
+        StakesOrder::Stake => validators.sort_by(|a, b| b.balance.cmp(&a.balance)),
+        StakesOrder::Address => {
+            validators.sort_by_key(|validator| validator.address.to_user_friendly_address())
+        }
+    }
+}
+
+/// The order in which [`BlockchainCommand::Stakes`] and [`BlockchainCommand::Validators`] list
+/// the validators.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum StakesOrder {
+    /// By total stake, highest first.
+    #[default]
+    Stake,
+    /// By validator address.
+    Address,
+}
 
 #[derive(Debug, Parser)]
 pub enum BlockchainCommand {
@@ -41,7 +95,29 @@ pub enum BlockchainCommand {
         include_body: bool,
     },
 
-    /// Query a transaction from the blockchain.
+    /// Verifies a block against its parent: the link to the parent, the block number, the network,
+    /// the timestamp and, for micro blocks, the seed signed by the block producer.
+    /// The state root transition can't be verified by the client, since the node doesn't expose
+    /// the account proofs necessary to apply the block to its parent's state.
+    /// If omitted, the last block is verified.
+    #[clap(group(
+        ArgGroup::new("hash_or_number")
+        .required(false)
+        .args(&["block_hash", "block_number"]),
+        ))]
+    VerifyBlock {
+        /// The block hash of the block to verify.
+        #[clap(conflicts_with = "block_number", long)]
+        block_hash: Option<Blake2bHash>,
+
+        /// The block number of the block to verify.
+        #[clap(long)]
+        block_number: Option<u32>,
+    },
+
+    /// Query a transaction from the blockchain, including the block it was included in, its number
+    /// of confirmations and whether it was executed successfully. Fails if the transaction isn't
+    /// known to the node.
     Transaction {
         /// The transaction hash.
         hash: Blake2bHash,
@@ -97,6 +173,17 @@ pub enum BlockchainCommand {
         just_hash: bool,
     },
 
+    /// Lists the transactions sending from or to the given address, newest first. Requires the
+    /// node to keep a history index.
+    History {
+        /// The address to list the transactions of.
+        address: Address,
+
+        /// Max number of transactions to list. If absent it defaults to 500.
+        #[clap(short, long)]
+        max: Option<u16>,
+    },
+
     /// Returns the information for the slot owner at the given block height and offset. The
     /// offset is optional, it will default to the block number for micro blocks and to the round number for macro blocks.
     /// We only have this information available for the last 2 batches at most.
@@ -105,7 +192,7 @@ pub enum BlockchainCommand {
         block_number: u32,
 
         /// The offset to retrieve at the block height specified.
-        #[clap(short, long)]
+        #[clap(long)]
         offset: Option<u32>,
     },
 
@@ -123,9 +210,26 @@ pub enum BlockchainCommand {
         address: Address,
     },
 
-    /// Tries to fetch all validators in the staking contract.
+    /// Lists all validators in the staking contract as a table of their address, total stake,
+    /// number of stakers and reward address.
     /// IMPORTANT: This is a very expensive operation, iterating over all existing validators in the contract.
-    Validators {},
+    Validators {
+        /// Omits validators whose total stake is below this amount of NIM.
+        #[clap(long)]
+        min_stake: Option<Coin>,
+
+        /// Omits validators that are inactive, jailed or retired.
+        #[clap(long)]
+        active_only: bool,
+
+        /// The order in which the validators are listed.
+        #[clap(long, value_enum, default_value_t)]
+        sort_by: StakesOrder,
+
+        /// Shows all information about the validators instead of the table.
+        #[clap(short, long)]
+        verbose: bool,
+    },
 
     /// Tries to fetch all stakers of a given validator.
     /// IMPORTANT: This is a very expensive operation, iterating over all existing stakers in the contract.
@@ -140,14 +244,155 @@ pub enum BlockchainCommand {
         address: Address,
     },
 
-    /// Lists the current stakes from the staking contract.
-    Stakes {},
+    /// Checks whether the validator a staker delegates to still exists and is active.
+    CheckDelegation {
+        /// The staker address to query by.
+        staker_address: Address,
+    },
+
+    /// Lists the current stakes of the active validators as a table of their address, total
+    /// stake, number of stakers and whether they are active.
+    Stakes {
+        /// Omits validators whose total stake is below this amount of NIM.
+        #[clap(long)]
+        min_stake: Option<Coin>,
+
+        /// The order in which the validators are listed.
+        #[clap(long, value_enum, default_value_t)]
+        sort_by: StakesOrder,
+
+        /// Number of validators to skip.
+        #[clap(long, default_value_t = 0)]
+        offset: usize,
+
+        /// Maximum number of validators to list.
+        #[clap(long)]
+        limit: Option<usize>,
+
+        /// Shows all information about the validators instead of the table.
+        #[clap(short, long)]
+        verbose: bool,
+    },
+
+    /// Shows how the stake is distributed across the currently active validators: the Nakamoto
+    /// coefficients (the minimum number of validators controlling more than 1/3 and 1/2 of the
+    /// stake), the Gini coefficient and the share of stake held by the top 10 validators.
+    StakeDistribution {},
 
-    /// Follow the head of the blockchain.
+    /// Groups all validators in the staking contract by their reward address and lists the reward
+    /// addresses shared by more than one validator, together with their combined stake.
+    /// IMPORTANT: This is a very expensive operation, iterating over all existing validators in the contract.
+    GroupByRewardAddress {},
+
+    /// Estimates the annual staking yield for the given stake. The estimate extrapolates the
+    /// rewards distributed in the last macro block to a full year and relates them to the total
+    /// stake of the active validators, or to the stake of the given validator.
+    StakingYield {
+        /// The amount of NIM to stake.
+        stake: Coin,
+
+        /// Estimates the yield based on the rewards of this validator instead of all active validators.
+        #[clap(long)]
+        validator: Option<Address>,
+    },
+
+    /// Shows how the balance of a reward address developed over the recent past.
+    /// The balances are reconstructed from the transaction history of the address, so this
+    /// requires a node with a history index (i.e. an archive node).
+    RewardTrend {
+        /// The reward address to query by.
+        reward_address: Address,
+
+        /// Number of balance samples to show.
+        #[clap(long, default_value_t = 10)]
+        samples: u32,
+
+        /// Number of blocks between two consecutive samples.
+        #[clap(long, default_value_t = 43200)]
+        interval_blocks: u32,
+    },
+
+    /// Scans the recent batches for validators that were caught equivocating, i.e. producing
+    /// two blocks for the same slot, proposing two macro blocks in the same round or voting twice.
+    /// Equivocations are punished by jailing the offending validator, so this lists the jail
+    /// inherents of the scanned batches together with the block number of the offense.
+    CheckEquivocation {
+        /// Number of batches to scan, starting from the current one.
+        #[clap(long, default_value_t = 10)]
+        last_n_batches: u32,
+    },
+
+    /// Follow the head of the blockchain. If the connection is lost, the client reconnects and
+    /// resumes without printing the last head again, unless `--no-reconnect` is given.
     FollowHead {
         /// Show the full block instead of only the hash.
         #[clap(short)]
         block: bool,
+
+        /// Only follow the finalized chain, i.e. macro blocks, which can't be reverted. Each block
+        /// is printed together with whether it is a checkpoint or an election block.
+        #[clap(long)]
+        finalized: bool,
+    },
+
+    /// Follow the head of the blockchain and estimate how long each new block took to reach this
+    /// client after it was produced.
+    BlockLatency {
+        /// Offset in milliseconds of the local clock relative to the network time. It is subtracted
+        /// from the local receive time to compensate for clock drift. Defaults to the offset the
+        /// node measured to the clocks of its peers.
+        #[clap(long, allow_negative_numbers = true)]
+        clock_offset: Option<i64>,
+    },
+
+    /// Continuously checks that the node is healthy: that it has consensus, enough peers, a recent
+    /// head block and a clock in line with the network. Prints a status line per interval and
+    /// exits with an error once any of the checks stays unhealthy for longer than the grace period.
+    /// The clock offset is estimated from head blocks with a timestamp in the future of the local
+    /// clock.
+    Monitor {
+        /// Interval in milliseconds between two checks.
+        #[clap(long, default_value_t = 5000)]
+        interval_ms: u64,
+
+        /// Time in milliseconds a check may stay unhealthy before the monitor fails.
+        #[clap(long, default_value_t = 60000)]
+        grace_period_ms: u64,
+
+        /// Minimum number of peers the node should be connected to.
+        #[clap(long, default_value_t = 1)]
+        min_peers: usize,
+
+        /// Maximum age in milliseconds of the head block.
+        #[clap(long, default_value_t = 60000)]
+        max_head_age_ms: u64,
+
+        /// Maximum offset in milliseconds of the local clock behind the network time.
+        #[clap(long, default_value_t = 5000)]
+        max_clock_offset_ms: u64,
+    },
+
+    /// Follow the head of the blockchain and call a webhook whenever the balance of one of the
+    /// given addresses increases. The webhook receives a JSON payload via POST containing the
+    /// address, its old and new balance, the block number and the hashes of the transactions to
+    /// the address in that block.
+    WatchWebhook {
+        /// The addresses to watch.
+        #[clap(required = true, num_args = 1..)]
+        addresses: Vec<Address>,
+
+        /// The URL of the webhook.
+        #[clap(long)]
+        url: String,
+
+        /// Secret used to authenticate the payload. If given, the hex encoded HMAC-SHA256 of the
+        /// request body is sent in the `X-Nimiq-Signature` header.
+        #[clap(long)]
+        secret: Option<String>,
+
+        /// How often a failed webhook call is retried.
+        #[clap(long, default_value_t = 3)]
+        retries: u32,
     },
 
     /// Follow a validator state upon election blocks.
@@ -158,6 +403,8 @@ pub enum BlockchainCommand {
 
     /// Follow the logs associated with the specified addresses and of any of the log types given.
     /// If no addresses or no log types are provided it fetches all logs.
+    /// Logs of blocks that are delivered more than once are skipped, logs of reverted blocks are
+    /// marked as `REVERTED`.
     FollowLogsOfAddressesAndTypes {
         /// List of all address to follow. If empty it does not filter by address.
         #[clap(short = 'a', long)]
@@ -166,6 +413,19 @@ pub enum BlockchainCommand {
         /// List of all log types to select. If empty it does not filter by log type.
         #[clap(short = 'l', long, value_enum)]
         log_types: Vec<LogType>,
+
+        /// Number of recently applied blocks to remember for detecting duplicate deliveries and
+        /// reverts of previously printed logs.
+        #[clap(long, default_value_t = 100)]
+        revert_window: usize,
+    },
+
+    /// Follows the logs of the transaction with the given hash, i.e. prints its logs whenever a
+    /// block containing it is applied or reverted, e.g. during a rebranch. Reverted logs are marked
+    /// as `REVERTED`.
+    FollowLogsOfTransaction {
+        /// The hash of the transaction.
+        hash: Blake2bHash,
     },
 }
 
@@ -191,52 +451,132 @@ impl HandleSubcommand for BlockchainCommand {
                 } else {
                     client.blockchain.get_latest_block(Some(include_body)).await
                 }?;
-                println!("{block:#?}")
-            }
-            BlockchainCommand::BlockNumber {} => {
-                println!("{:#?}", client.blockchain.get_block_number().await?)
-            }
-            BlockchainCommand::BatchNumber {} => {
-                println!("{:#?}", client.blockchain.get_batch_number().await?)
+                client.output.print(&block)?
             }
-            BlockchainCommand::EpochNumber {} => {
-                println!("{:#?}", client.blockchain.get_epoch_number().await?)
+            BlockchainCommand::VerifyBlock {
+                block_hash,
+                block_number,
+            } => {
+                let block = if let Some(block_hash) = block_hash {
+                    client.blockchain.get_block_by_hash(block_hash, None).await
+                } else if let Some(block_number) = block_number {
+                    client
+                        .blockchain
+                        .get_block_by_number(block_number, None)
+                        .await
+                } else {
+                    client.blockchain.get_latest_block(None).await
+                }?
+                .data;
+                if block.number == 0 {
+                    bail!("Block {} has no parent to verify it against", block.number);
+                }
+                let parent = client
+                    .blockchain
+                    .get_block_by_number(block.number - 1, None)
+                    .await?
+                    .data;
+
+                let mut checks = vec![
+                    ("parentHash", Some(block.parent_hash == parent.hash)),
+                    ("blockNumber", Some(block.number == parent.number + 1)),
+                    ("network", Some(block.network == parent.network)),
+                    ("timestamp", Some(block.timestamp >= parent.timestamp)),
+                ];
+                let seed = match &block.additional_fields {
+                    BlockAdditionalFields::Micro { producer, .. } => {
+                        let signing_key = client
+                            .blockchain
+                            .get_validator_by_address(producer.validator.clone())
+                            .await?
+                            .data
+                            .signing_key;
+                        Some(block.seed.verify(&parent.seed, &signing_key).is_ok())
+                    }
+                    // The proposer of a macro block isn't part of the block.
+                    BlockAdditionalFields::Macro { .. } => None,
+                };
+                checks.push(("seed", seed));
+                checks.push(("stateRoot", None));
+
+                let passed = checks.iter().all(|(_, result)| *result != Some(false));
+                let describe = |result: &Option<bool>| match result {
+                    Some(true) => "passed",
+                    Some(false) => "failed",
+                    None => "skipped",
+                };
+
+                let mut result = serde_json::Map::new();
+                result.insert("hash".to_string(), json!(block.hash));
+                result.insert("number".to_string(), json!(block.number));
+                result.insert("passed".to_string(), json!(passed));
+                let mut text = format!(
+                    "Verification of block #{} ({}) {}",
+                    block.number,
+                    block.hash,
+                    if passed { "passed" } else { "failed" },
+                );
+                for (check, check_result) in &checks {
+                    result.insert(check.to_string(), json!(describe(check_result)));
+                    text += &format!("\n{check}: {}", describe(check_result));
+                }
+                client.output.print_with(&result, text)?;
             }
+            BlockchainCommand::BlockNumber {} => client
+                .output
+                .print(&client.blockchain.get_block_number().await?)?,
+            BlockchainCommand::BatchNumber {} => client
+                .output
+                .print(&client.blockchain.get_batch_number().await?)?,
+            BlockchainCommand::EpochNumber {} => client
+                .output
+                .print(&client.blockchain.get_epoch_number().await?)?,
             BlockchainCommand::SlotAt {
                 block_number,
                 offset,
-            } => {
-                println!(
-                    "{:#?}",
-                    client.blockchain.get_slot_at(block_number, offset).await?
-                )
-            }
+            } => client
+                .output
+                .print(&client.blockchain.get_slot_at(block_number, offset).await?)?,
             BlockchainCommand::Transaction { hash } => {
-                println!(
-                    "{:#?}",
-                    client.blockchain.get_transaction_by_hash(hash).await?
-                )
+                let Some(tx) = client
+                    .blockchain
+                    .find_transaction_by_hash(hash.clone())
+                    .await?
+                    .data
+                else {
+                    bail!("Transaction {hash} not found")
+                };
+
+                let execution = if tx.succeeded() {
+                    "succeeded"
+                } else {
+                    "failed"
+                };
+                let text = format!(
+                    "{tx:#?}\nIncluded in block #{} with {} confirmations, execution {execution}",
+                    tx.transaction().block_number.unwrap_or_default(),
+                    tx.transaction().confirmations.unwrap_or_default(),
+                );
+                client.output.print_with(&tx, text)?;
             }
             BlockchainCommand::Transactions {
                 block_number,
                 batch_number,
             } => {
                 if let Some(block_number) = block_number {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_transactions_by_block_number(block_number)
-                            .await?
-                    )
+                            .await?,
+                    )?
                 } else {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_transactions_by_batch_number(batch_number.unwrap())
-                            .await?
-                    )
+                            .await?,
+                    )?
                 }
             }
             BlockchainCommand::Inherents {
@@ -244,21 +584,19 @@ impl HandleSubcommand for BlockchainCommand {
                 batch_number,
             } => {
                 if let Some(block_number) = block_number {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_inherents_by_block_number(block_number)
-                            .await?
-                    )
+                            .await?,
+                    )?
                 } else {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_inherents_by_batch_number(batch_number.unwrap())
-                            .await?
-                    )
+                            .await?,
+                    )?
                 }
             }
 
@@ -268,103 +606,981 @@ impl HandleSubcommand for BlockchainCommand {
                 just_hash,
             } => {
                 if just_hash {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_transaction_hashes_by_address(address, max)
-                            .await?
-                    )
+                            .await?,
+                    )?
                 } else {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .blockchain
                             .get_transactions_by_address(address, max)
-                            .await?
-                    )
+                            .await?,
+                    )?
+                }
+            }
+            BlockchainCommand::History { address, max } => {
+                let mut transactions = client
+                    .blockchain
+                    .get_transactions_by_address(address.clone(), max)
+                    .await
+                    .context("Failed to fetch the transaction history, the node must keep a history index")?
+                    .data;
+                transactions.sort_by_key(|executed_tx| {
+                    std::cmp::Reverse(executed_tx.transaction().block_number)
+                });
+
+                let mut rows = vec![];
+                let mut text = format!(
+                    "{:<64}  {:<3}  {:<44}  {:>20}  {:>10}",
+                    "HASH", "DIR", "COUNTERPARTY", "VALUE (NIM)", "BLOCK"
+                );
+                for executed_tx in &transactions {
+                    let tx = executed_tx.transaction();
+                    let (direction, counterparty) = if tx.from == address {
+                        ("out", &tx.to)
+                    } else {
+                        ("in", &tx.from)
+                    };
+                    rows.push(json!({
+                        "hash": tx.hash,
+                        "direction": direction,
+                        "counterparty": counterparty,
+                        "value": tx.value,
+                        "blockNumber": tx.block_number,
+                        "succeeded": executed_tx.succeeded(),
+                    }));
+                    text += &format!(
+                        "\n{:<64}  {:<3}  {:<44}  {:>20}  {:>10}{}",
+                        tx.hash.to_string(),
+                        direction,
+                        counterparty.to_user_friendly_address(),
+                        tx.value.to_string(),
+                        tx.block_number.unwrap_or_default(),
+                        if executed_tx.succeeded() {
+                            ""
+                        } else {
+                            "  (failed)"
+                        },
+                    );
+                }
+                client.output.print_with(&rows, text)?;
+
+                let max = max.unwrap_or(500);
+                if transactions.len() >= usize::from(max) {
+                    client.output.info(format!(
+                        "Only the latest {max} transactions are shown, use `--max` to list more"
+                    ));
                 }
             }
             BlockchainCommand::PenalizedSlots { previous_penalized } => {
                 if previous_penalized {
-                    println!(
-                        "{:#?}",
-                        client.blockchain.get_current_penalized_slots().await?
-                    )
+                    client
+                        .output
+                        .print(&client.blockchain.get_current_penalized_slots().await?)?
                 } else {
-                    println!(
-                        "{:#?}",
-                        client.blockchain.get_previous_penalized_slots().await?
-                    )
+                    client
+                        .output
+                        .print(&client.blockchain.get_previous_penalized_slots().await?)?
                 }
             }
-            BlockchainCommand::ValidatorByAddress { address } => println!(
-                "{:#?}",
-                client.blockchain.get_validator_by_address(address).await?
-            ),
+            BlockchainCommand::ValidatorByAddress { address } => client
+                .output
+                .print(&client.blockchain.get_validator_by_address(address).await?)?,
 
-            BlockchainCommand::Validators {} => {
-                println!("{:#?}", client.blockchain.get_validators().await?)
+            BlockchainCommand::Validators {
+                min_stake,
+                active_only,
+                sort_by,
+                verbose,
+            } => {
+                let mut validators = client.blockchain.get_validators().await?.data;
+                if let Some(min_stake) = min_stake {
+                    validators.retain(|validator| validator.balance >= min_stake);
+                }
+                if active_only {
+                    validators.retain(|validator| {
+                        validator.inactivity_flag.is_none()
+                            && validator.jailed_from.is_none()
+                            && !validator.retired
+                    });
+                }
+                sort_validators(&mut validators, sort_by);
+
+                if verbose {
+                    client.output.print(&validators)?;
+                } else {
+                    let rows: Vec<_> = validators
+                        .iter()
+                        .map(|validator| {
+                            json!({
+                                "address": validator.address,
+                                "stake": validator.balance,
+                                "numStakers": validator.num_stakers,
+                                "rewardAddress": validator.reward_address,
+                            })
+                        })
+                        .collect();
+                    let mut text = format!(
+                        "{:<44}  {:>20}  {:>8}  {}",
+                        "ADDRESS", "STAKE (NIM)", "STAKERS", "REWARD ADDRESS"
+                    );
+                    for validator in &validators {
+                        text += &format!(
+                            "\n{:<44}  {:>20}  {:>8}  {}",
+                            validator.address.to_user_friendly_address(),
+                            validator.balance.to_string(),
+                            validator.num_stakers,
+                            validator.reward_address.to_user_friendly_address(),
+                        );
+                    }
+                    client.output.print_with(&rows, text)?;
+                }
             }
 
-            BlockchainCommand::StakersByValidator { address } => println!(
-                "{:#?}",
-                client
+            BlockchainCommand::StakersByValidator { address } => client.output.print(
+                &client
                     .blockchain
                     .get_stakers_by_validator_address(address)
+                    .await?,
+            )?,
+            BlockchainCommand::Staker { address } => client
+                .output
+                .print(&client.blockchain.get_staker_by_address(address).await?)?,
+            BlockchainCommand::CheckDelegation { staker_address } => {
+                let staker = client
+                    .blockchain
+                    .get_staker_by_address(staker_address.clone())
+                    .await?
+                    .data;
+                let Some(validator_address) = staker.delegation else {
+                    client.output.print_with(
+                        &json!({ "staker": staker_address, "delegation": null, "status": "none" }),
+                        format!("Staker {staker_address} does not delegate to any validator"),
+                    )?;
+                    return Ok(client);
+                };
+
+                let status = validator_status(&mut client, &validator_address).await?;
+                let message = match status {
+                    "active" => format!("Validator {validator_address} exists and is active"),
+                    "dangling" => format!(
+                        "Warning: validator {validator_address} does not exist anymore, consider re-delegating"
+                    ),
+                    _ => format!(
+                        "Warning: validator {validator_address} is {status}, consider re-delegating"
+                    ),
+                };
+                let result = json!({
+                    "staker": staker_address,
+                    "delegation": validator_address,
+                    "status": status,
+                });
+                client.output.print_with(&result, message)?;
+            }
+            BlockchainCommand::Stakes {
+                min_stake,
+                sort_by,
+                offset,
+                limit,
+                verbose,
+            } => {
+                let mut validators = client.blockchain.get_active_validators().await?.data;
+                let num_validators = validators.len();
+                if let Some(min_stake) = min_stake {
+                    validators.retain(|validator| validator.balance >= min_stake);
+                }
+                let num_omitted = num_validators - validators.len();
+
+                sort_validators(&mut validators, sort_by);
+                let validators: Vec<Validator> = validators
+                    .into_iter()
+                    .skip(offset)
+                    .take(limit.unwrap_or(usize::MAX))
+                    .collect();
+
+                if verbose {
+                    client.output.print(&validators)?;
+                } else {
+                    let rows: Vec<_> = validators
+                        .iter()
+                        .map(|validator| {
+                            json!({
+                                "address": validator.address,
+                                "stake": validator.balance,
+                                "numStakers": validator.num_stakers,
+                                "active": validator.inactivity_flag.is_none(),
+                            })
+                        })
+                        .collect();
+                    let mut text = format!(
+                        "{:<44}  {:>20}  {:>8}  {}",
+                        "ADDRESS", "STAKE (NIM)", "STAKERS", "ACTIVE"
+                    );
+                    for validator in &validators {
+                        text += &format!(
+                            "\n{:<44}  {:>20}  {:>8}  {}",
+                            validator.address.to_user_friendly_address(),
+                            validator.balance.to_string(),
+                            validator.num_stakers,
+                            if validator.inactivity_flag.is_none() {
+                                "yes"
+                            } else {
+                                "no"
+                            },
+                        );
+                    }
+                    client.output.print_with(&rows, text)?;
+                }
+                if let Some(min_stake) = min_stake {
+                    client.output.info(format!(
+                        "{num_omitted} validators with a stake below {min_stake} NIM were omitted"
+                    ));
+                }
+            }
+            BlockchainCommand::StakeDistribution {} => {
+                let mut stakes: Vec<u64> = client
+                    .blockchain
+                    .get_active_validators()
                     .await?
-            ),
-            BlockchainCommand::Staker { address } => {
-                println!(
-                    "{:#?}",
-                    client.blockchain.get_staker_by_address(address).await?
-                )
+                    .data
+                    .iter()
+                    .map(|validator| u64::from(validator.balance))
+                    .collect();
+                stakes.sort_unstable_by(|a, b| b.cmp(a));
+
+                let total: u128 = stakes.iter().map(|&stake| stake as u128).sum();
+                let top_10: u128 = stakes.iter().take(10).map(|&stake| stake as u128).sum();
+                let top_10_share = if total == 0 {
+                    0.0
+                } else {
+                    top_10 as f64 / total as f64
+                };
+                let nakamoto_third = nakamoto_coefficient(&stakes, 1, 3);
+                let nakamoto_half = nakamoto_coefficient(&stakes, 1, 2);
+                let gini = gini_coefficient(&stakes);
+                let total_stake = Coin::try_from(u64::try_from(total)?)?;
+
+                let result = json!({
+                    "validators": stakes.len(),
+                    "totalStake": total_stake,
+                    "nakamotoCoefficientThird": nakamoto_third,
+                    "nakamotoCoefficientHalf": nakamoto_half,
+                    "giniCoefficient": gini,
+                    "top10Share": top_10_share,
+                });
+                client.output.print_with(
+                    &result,
+                    format!(
+                        "Active validators:              {}\n\
+                         Total stake:                    {total_stake} NIM\n\
+                         Nakamoto coefficient (>1/3):    {nakamoto_third}\n\
+                         Nakamoto coefficient (>1/2):    {nakamoto_half}\n\
+                         Gini coefficient:               {gini:.4}\n\
+                         Stake held by top 10:           {:.2}%",
+                        stakes.len(),
+                        top_10_share * 100.0,
+                    ),
+                )?;
             }
-            BlockchainCommand::Stakes {} => {
-                println!("{:#?}", client.blockchain.get_active_validators().await?);
+            BlockchainCommand::StakingYield { stake, validator } => {
+                let constants = client.policy.get_policy_constants().await?.data;
+                let head = client.blockchain.get_block_number().await?.data;
+                let macro_block = client.policy.get_last_macro_block(head).await?.data;
+                let inherents = client
+                    .blockchain
+                    .get_inherents_by_block_number(macro_block)
+                    .await?
+                    .data;
+
+                let mut rewards = 0u64;
+                for inherent in inherents {
+                    if let Inherent::Reward {
+                        validator_address,
+                        value,
+                        ..
+                    } = inherent
+                    {
+                        if validator.as_ref().map_or(true, |v| *v == validator_address) {
+                            rewards += u64::from(value);
+                        }
+                    }
+                }
+                let total_stake = match &validator {
+                    Some(address) => u64::from(
+                        client
+                            .blockchain
+                            .get_validator_by_address(address.clone())
+                            .await?
+                            .data
+                            .balance,
+                    ),
+                    None => client
+                        .blockchain
+                        .get_active_validators()
+                        .await?
+                        .data
+                        .iter()
+                        .map(|validator| u64::from(validator.balance))
+                        .sum(),
+                };
+                if total_stake == 0 {
+                    bail!("There is no stake to compute the yield for");
+                }
+
+                const YEAR_MS: f64 = 365.25 * 24.0 * 60.0 * 60.0 * 1000.0;
+                let batch_duration =
+                    constants.blocks_per_batch as f64 * constants.block_separation_time as f64;
+                let annual_rewards = rewards as f64 * YEAR_MS / batch_duration;
+                let annual_yield = annual_rewards / total_stake as f64;
+                let expected_rewards =
+                    Coin::try_from((u64::from(stake) as f64 * annual_yield) as u64)?;
+
+                let result = json!({
+                    "stake": stake,
+                    "validator": validator,
+                    "macroBlock": macro_block,
+                    "batchRewards": rewards,
+                    "totalStake": total_stake,
+                    "annualYield": annual_yield,
+                    "expectedAnnualRewards": expected_rewards,
+                });
+                let staked_with = match &validator {
+                    Some(address) => format!("validator {address}"),
+                    None => "all active validators".to_string(),
+                };
+                let text = format!(
+                    "Expected annual yield: {:.2}% ({expected_rewards} NIM per year for {stake} NIM)\n\
+                     Assumptions:\n  \
+                     - Rewards stay at the level of the last batch (macro block #{macro_block}: {} NIM)\n  \
+                     - The stake of {staked_with} remains at {} NIM\n  \
+                     - Rewards are not compounded\n  \
+                     - Rewards are paid to the validator's reward address, payouts to stakers \
+                     and any commission are up to the validator",
+                    annual_yield * 100.0,
+                    Coin::try_from(rewards)?,
+                    Coin::try_from(total_stake)?,
+                );
+                client.output.print_with(&result, text)?;
             }
+            BlockchainCommand::GroupByRewardAddress {} => {
+                let mut groups: HashMap<Address, Vec<Validator>> = HashMap::new();
+                for validator in client.blockchain.get_validators().await?.data {
+                    groups
+                        .entry(validator.reward_address.clone())
+                        .or_default()
+                        .push(validator);
+                }
 
-            BlockchainCommand::FollowHead { block: show_block } => {
-                if show_block {
-                    let mut stream = client
+                let mut shared = vec![];
+                for (reward_address, validators) in groups {
+                    if validators.len() < 2 {
+                        continue;
+                    }
+                    let combined_stake = validators
+                        .iter()
+                        .map(|validator| u64::from(validator.balance))
+                        .sum::<u64>();
+                    let validators: Vec<Address> = validators
+                        .into_iter()
+                        .map(|validator| validator.address)
+                        .collect();
+                    shared.push((reward_address, validators, Coin::try_from(combined_stake)?));
+                }
+                shared.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+
+                let result: Vec<_> = shared
+                    .iter()
+                    .map(|(reward_address, validators, combined_stake)| {
+                        json!({
+                            "rewardAddress": reward_address,
+                            "validators": validators,
+                            "combinedStake": combined_stake,
+                        })
+                    })
+                    .collect();
+                let mut text = format!(
+                    "{} reward addresses are shared by more than one validator",
+                    shared.len()
+                );
+                for (reward_address, validators, combined_stake) in &shared {
+                    text += &format!(
+                        "\n{reward_address}: {} validators, combined stake {combined_stake} NIM",
+                        validators.len()
+                    );
+                    for validator in validators {
+                        text += &format!("\n    {validator}");
+                    }
+                }
+                client.output.print_with(&result, text)?;
+            }
+
+            BlockchainCommand::CheckEquivocation { last_n_batches } => {
+                let current_batch = client.blockchain.get_batch_number().await?.data;
+                let first_batch = current_batch.saturating_sub(last_n_batches.saturating_sub(1));
+
+                let mut offenses = vec![];
+                for batch_number in first_batch..=current_batch {
+                    let inherents = client
                         .blockchain
-                        .subscribe_for_head_block(Some(false))
-                        .await?;
+                        .get_inherents_by_batch_number(batch_number)
+                        .await?
+                        .data;
+                    for inherent in inherents {
+                        if let Inherent::Jail {
+                            block_number,
+                            validator_address,
+                            offense_event_block,
+                            ..
+                        } = inherent
+                        {
+                            offenses.push((validator_address, offense_event_block, block_number));
+                        }
+                    }
+                }
+
+                let result: Vec<_> = offenses
+                    .iter()
+                    .map(|(validator_address, offense_event_block, block_number)| {
+                        json!({
+                            "validatorAddress": validator_address,
+                            "offenseEventBlock": offense_event_block,
+                            "jailedAt": block_number,
+                        })
+                    })
+                    .collect();
+                let mut text = format!(
+                    "Found {} equivocations in batches {first_batch} to {current_batch}",
+                    offenses.len()
+                );
+                for (validator_address, offense_event_block, block_number) in &offenses {
+                    text += &format!(
+                        "\n{validator_address}: offense in block {offense_event_block}, jailed in block {block_number}"
+                    );
+                }
+                client.output.print_with(&result, text)?;
+            }
+
+            BlockchainCommand::RewardTrend {
+                reward_address,
+                samples,
+                interval_blocks,
+            } => {
+                if samples == 0 || interval_blocks == 0 {
+                    bail!("Both the number of samples and the interval must be greater than zero");
+                }
 
-                    while let Some(block) = stream.next().await {
-                        println!("{block:#?}");
+                let account = client
+                    .blockchain
+                    .get_account_by_address(reward_address.clone())
+                    .await?;
+                let head = account.metadata.block_number;
+                let transactions = client
+                    .blockchain
+                    .get_transactions_by_address(reward_address.clone(), Some(u16::MAX))
+                    .await?
+                    .data;
+                if transactions.len() == u16::MAX as usize {
+                    client.output.info(
+                        "Transaction history was truncated, older balances might be inaccurate",
+                    );
+                }
+
+                // Rewind the current balance through the history to get the balance at each sample height.
+                let mut series = vec![];
+                for i in (0..samples).rev() {
+                    let block_number = head.saturating_sub(i.saturating_mul(interval_blocks));
+                    let mut balance = i128::from(u64::from(account.data.balance));
+                    for executed_tx in &transactions {
+                        let tx = executed_tx.transaction();
+                        if tx.block_number.unwrap_or_default() <= block_number {
+                            continue;
+                        }
+                        if tx.to == reward_address && executed_tx.succeeded() {
+                            balance -= i128::from(u64::from(tx.value));
+                        }
+                        if tx.from == reward_address {
+                            balance += i128::from(u64::from(tx.fee));
+                            if executed_tx.succeeded() {
+                                balance += i128::from(u64::from(tx.value));
+                            }
+                        }
                     }
-                } else {
-                    let mut stream = client.blockchain.subscribe_for_head_block_hash().await?;
+                    let balance = Coin::try_from(u64::try_from(balance.max(0))?)?;
+                    series.push((block_number, balance));
+                }
+                series.dedup_by_key(|(block_number, _)| *block_number);
+
+                client.output.print(&series)?;
+                client.output.info(sparkline(
+                    series.iter().map(|(_, balance)| u64::from(*balance)),
+                ));
+            }
+            BlockchainCommand::FollowHead {
+                block: show_block,
+                finalized,
+            } => {
+                let _subscription = client.acquire_subscription()?;
+                // The last printed head, such that it isn't printed again after reconnecting.
+                let mut last_head = None;
+                loop {
+                    if finalized {
+                        let mut stream = client
+                            .blockchain
+                            .subscribe_for_head_block(Some(false))
+                            .await?;
+
+                        while let Some(block) = stream.next().await {
+                            let BlockAdditionalFields::Macro {
+                                is_election_block, ..
+                            } = &block.data.additional_fields
+                            else {
+                                continue;
+                            };
+                            if last_head.as_ref() == Some(&block.data.hash) {
+                                continue;
+                            }
+                            last_head = Some(block.data.hash.clone());
+
+                            let kind = if *is_election_block {
+                                "election"
+                            } else {
+                                "checkpoint"
+                            };
+                            if show_block {
+                                client.output.print_item_with(
+                                    &json!({ "kind": kind, "block": block }),
+                                    format!("{kind} {:#?}", block.data),
+                                )?;
+                            } else {
+                                client.output.print_item_with(
+                                    &json!({
+                                        "kind": kind,
+                                        "blockNumber": block.data.number,
+                                        "hash": block.data.hash,
+                                    }),
+                                    format!("#{} {} ({kind})", block.data.number, block.data.hash),
+                                )?;
+                            }
+                        }
+                    } else if show_block {
+                        let mut stream = client
+                            .blockchain
+                            .subscribe_for_head_block(Some(false))
+                            .await?;
+
+                        while let Some(block) = stream.next().await {
+                            if last_head.as_ref() == Some(&block.data.hash) {
+                                continue;
+                            }
+                            last_head = Some(block.data.hash.clone());
+                            client.output.print_item(&block)?;
+                        }
+                    } else {
+                        let mut stream = client.blockchain.subscribe_for_head_block_hash().await?;
 
-                    while let Some(block_hash) = stream.next().await {
-                        println!("{block_hash:#?}");
+                        while let Some(block_hash) = stream.next().await {
+                            if last_head.as_ref() == Some(&block_hash.data) {
+                                continue;
+                            }
+                            last_head = Some(block_hash.data.clone());
+                            client.output.print_item(&block_hash)?;
+                        }
+                    }
+
+                    // The subscription ended, which means that the connection was lost.
+                    if !client.reconnect().await? {
+                        break;
                     }
                 }
             }
-            BlockchainCommand::FollowValidator { address } => {
+            BlockchainCommand::BlockLatency { clock_offset } => {
+                // The node reports how far its peers' clocks are ahead of its own, so our clock is
+                // behind the network time by that amount.
+                let clock_offset = match clock_offset {
+                    Some(clock_offset) => clock_offset,
+                    None => -client.network.get_clock_offset().await?.data,
+                };
+
+                let _subscription = client.acquire_subscription()?;
                 let mut stream = client
                     .blockchain
-                    .subscribe_for_validator_election_by_address(address)
+                    .subscribe_for_head_block(Some(false))
                     .await?;
-                while let Some(validator) = stream.next().await {
-                    println!("{validator:#?}");
+
+                let mut num_blocks = 0i64;
+                let mut total_delay = 0i64;
+                while let Some(block) = stream.next().await {
+                    let block = block.data;
+                    let received = client.now_millis() as i64 - clock_offset;
+                    let delay = received - block.timestamp as i64;
+                    num_blocks += 1;
+                    total_delay += delay;
+                    let average = total_delay / num_blocks;
+                    client.output.print_item_with(
+                        &json!({ "blockNumber": block.number, "delay": delay, "average": average }),
+                        format!("#{}: delay={delay}ms, average={average}ms", block.number),
+                    )?;
                 }
             }
-            BlockchainCommand::FollowLogsOfAddressesAndTypes {
+            BlockchainCommand::Monitor {
+                interval_ms,
+                grace_period_ms,
+                min_peers,
+                max_head_age_ms,
+                max_clock_offset_ms,
+            } => {
+                if interval_ms == 0 {
+                    bail!("The interval must be greater than zero");
+                }
+
+                const CHECKS: [&str; 4] = ["consensus", "peers", "headAge", "clockOffset"];
+                let mut unhealthy_since: [Option<u64>; 4] = [None; 4];
+                let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+                loop {
+                    interval.tick().await;
+
+                    let consensus = client.consensus.is_consensus_established().await?.data;
+                    let peers = client.network.get_peer_count().await?.data;
+                    let head = client.blockchain.get_latest_block(None).await?.data;
+                    let now = client.now_millis();
+                    let head_age = now.saturating_sub(head.timestamp);
+                    let clock_offset = head.timestamp.saturating_sub(now);
+
+                    let healthy = [
+                        consensus,
+                        peers >= min_peers,
+                        head_age <= max_head_age_ms,
+                        clock_offset <= max_clock_offset_ms,
+                    ];
+                    for (since, healthy) in unhealthy_since.iter_mut().zip(healthy) {
+                        if healthy {
+                            *since = None;
+                        } else if since.is_none() {
+                            *since = Some(now);
+                        }
+                    }
+                    let unhealthy: Vec<&str> = CHECKS
+                        .iter()
+                        .zip(healthy)
+                        .filter(|(_, healthy)| !healthy)
+                        .map(|(check, _)| *check)
+                        .collect();
+                    let failed: Vec<&str> = CHECKS
+                        .iter()
+                        .zip(unhealthy_since)
+                        .filter(|(_, since)| {
+                            since.is_some_and(|since| now.saturating_sub(since) > grace_period_ms)
+                        })
+                        .map(|(check, _)| *check)
+                        .collect();
+
+                    let status = if unhealthy.is_empty() {
+                        "healthy".to_string()
+                    } else {
+                        format!("unhealthy ({})", unhealthy.join(", "))
+                    };
+                    client.output.print_item_with(
+                        &json!({
+                            "blockNumber": head.number,
+                            "consensus": consensus,
+                            "peers": peers,
+                            "headAge": head_age,
+                            "clockOffset": clock_offset,
+                            "unhealthy": unhealthy,
+                        }),
+                        format!(
+                            "#{}: consensus={consensus}, peers={peers}, head_age={head_age}ms, \
+                             clock_offset={clock_offset}ms, {status}",
+                            head.number,
+                        ),
+                    )?;
+
+                    if !failed.is_empty() {
+                        bail!(
+                            "ALERT: {} unhealthy for more than {grace_period_ms}ms",
+                            failed.join(", ")
+                        );
+                    }
+                }
+            }
+            BlockchainCommand::WatchWebhook {
                 addresses,
-                log_types,
+                url,
+                secret,
+                retries,
             } => {
+                let http = reqwest::Client::new();
+                let mut balances = HashMap::new();
+                for address in &addresses {
+                    let account = client
+                        .blockchain
+                        .get_account_by_address(address.clone())
+                        .await?;
+                    balances.insert(address.clone(), account.data.balance);
+                }
+
+                let _subscription = client.acquire_subscription()?;
                 let mut stream = client
                     .blockchain
-                    .subscribe_for_logs_by_addresses_and_types(addresses, log_types)
+                    .subscribe_for_head_block(Some(false))
                     .await?;
+                while let Some(block) = stream.next().await {
+                    let block_number = block.data.number;
+                    let mut transactions = None;
+                    for address in &addresses {
+                        let new_balance = client
+                            .blockchain
+                            .get_account_by_address(address.clone())
+                            .await?
+                            .data
+                            .balance;
+                        let old_balance = balances.insert(address.clone(), new_balance);
+                        let Some(old_balance) = old_balance.filter(|old| *old < new_balance) else {
+                            continue;
+                        };
 
-                while let Some(blocklog) = stream.next().await {
-                    println!("{blocklog:#?}");
+                        // Only fetch the transactions of the block if any balance increased.
+                        if transactions.is_none() {
+                            transactions = Some(
+                                client
+                                    .blockchain
+                                    .get_transactions_by_block_number(block_number)
+                                    .await?
+                                    .data,
+                            );
+                        }
+                        let hashes: Vec<&Blake2bHash> = transactions
+                            .iter()
+                            .flatten()
+                            .map(|executed_tx| executed_tx.transaction())
+                            .filter(|tx| tx.to == *address)
+                            .map(|tx| &tx.hash)
+                            .collect();
+
+                        let payload = json!({
+                            "address": address,
+                            "oldBalance": old_balance,
+                            "newBalance": new_balance,
+                            "blockNumber": block_number,
+                            "transactions": hashes,
+                        });
+                        if let Err(error) = call_webhook(
+                            &client.output,
+                            &http,
+                            &url,
+                            secret.as_deref(),
+                            &payload,
+                            retries,
+                        )
+                        .await
+                        {
+                            client
+                                .output
+                                .info(format!("Calling the webhook failed: {error}"));
+                        }
+                        client.output.print_item(&payload)?;
+                    }
+                }
+            }
+            BlockchainCommand::FollowValidator { address } => {
+                let _subscription = client.acquire_subscription()?;
+                // Elections happen in macro blocks, which are final. Elections up to this block
+                // were printed already and are skipped if delivered again after reconnecting.
+                let mut last_election = None;
+                loop {
+                    let mut stream = client
+                        .blockchain
+                        .subscribe_for_validator_election_by_address(address.clone())
+                        .await?;
+                    while let Some(validator) = stream.next().await {
+                        let block_number = validator.metadata.block_number;
+                        if last_election.is_some_and(|last| block_number <= last) {
+                            continue;
+                        }
+                        last_election = Some(block_number);
+                        client.output.print_item(&validator)?;
+                    }
+
+                    if !client.reconnect().await? {
+                        break;
+                    }
+                }
+            }
+            BlockchainCommand::FollowLogsOfAddressesAndTypes {
+                addresses,
+                log_types,
+                revert_window,
+            } => {
+                let _subscription = client.acquire_subscription()?;
+                let mut window = BlockWindow::new(revert_window);
+                loop {
+                    let mut stream = client
+                        .blockchain
+                        .subscribe_for_logs_by_addresses_and_types(
+                            addresses.clone(),
+                            log_types.clone(),
+                        )
+                        .await?;
+
+                    while let Some(blocklog) = stream.next().await {
+                        let block_hash = &blocklog.metadata.block_hash;
+                        match blocklog.data {
+                            BlockLog::AppliedBlock { .. } => {
+                                if window.apply(block_hash) {
+                                    client.output.print_item(&blocklog)?;
+                                }
+                            }
+                            BlockLog::RevertedBlock { .. } => {
+                                window.revert(block_hash);
+                                client.output.print_item_with(
+                                    &json!({ "reverted": true, "blockLog": blocklog }),
+                                    format!("REVERTED {blocklog:?}"),
+                                )?;
+                            }
+                        }
+                    }
+
+                    if !client.reconnect().await? {
+                        break;
+                    }
+                }
+            }
+            BlockchainCommand::FollowLogsOfTransaction { hash } => {
+                let _subscription = client.acquire_subscription()?;
+                // Blocks are remembered for a batch, which is as far as a rebranch can go.
+                let mut window = BlockWindow::new(
+                    client
+                        .policy
+                        .get_policy_constants()
+                        .await?
+                        .data
+                        .blocks_per_batch as usize,
+                );
+                loop {
+                    let mut stream = client
+                        .blockchain
+                        .subscribe_for_logs_by_transaction(hash.clone())
+                        .await?;
+
+                    while let Some(blocklog) = stream.next().await {
+                        let block_hash = &blocklog.metadata.block_hash;
+                        match blocklog.data {
+                            BlockLog::AppliedBlock { .. } => {
+                                if window.apply(block_hash) {
+                                    client.output.print_item(&blocklog)?;
+                                }
+                            }
+                            BlockLog::RevertedBlock { .. } => {
+                                window.revert(block_hash);
+                                client.output.print_item_with(
+                                    &json!({ "reverted": true, "blockLog": blocklog }),
+                                    format!("REVERTED {blocklog:?}"),
+                                )?;
+                            }
+                        }
+                    }
+
+                    if !client.reconnect().await? {
+                        break;
+                    }
                 }
             }
         }
         Ok(client)
     }
 }
+
+/// POSTs the given payload to a webhook, retrying up to `retries` times if the call fails. If a
+/// secret is given, the request is authenticated with an HMAC-SHA256 of the body.
+async fn call_webhook(
+    output: &Output,
+    http: &reqwest::Client,
+    url: &str,
+    secret: Option<&str>,
+    payload: &serde_json::Value,
+    retries: u32,
+) -> Result<(), Error> {
+    let body = serde_json::to_vec(payload)?;
+    let signature = match secret {
+        Some(secret) => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC can take a key of any size");
+            mac.update(&body);
+            Some(hex::encode(mac.finalize().into_bytes()))
+        }
+        None => None,
+    };
+
+    let mut attempt = 0;
+    loop {
+        let mut request = http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header("X-Nimiq-Signature", signature);
+        }
+
+        let error = match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => return Ok(()),
+            Err(error) => error,
+        };
+        if attempt == retries {
+            return Err(error.into());
+        }
+        attempt += 1;
+        output.info(format!("Calling the webhook failed: {error}. Retrying..."));
+        tokio::time::sleep(Duration::from_secs(1 << attempt.min(6))).await;
+    }
+}
+
+/// Returns the minimum number of validators that together control more than `num / den` of the
+/// total stake. The stakes must be sorted in descending order.
+fn nakamoto_coefficient(stakes: &[u64], num: u128, den: u128) -> usize {
+    let total: u128 = stakes.iter().map(|&stake| stake as u128).sum();
+    let mut controlled = 0u128;
+    for (i, &stake) in stakes.iter().enumerate() {
+        controlled += stake as u128;
+        if controlled * den > total * num {
+            return i + 1;
+        }
+    }
+    stakes.len()
+}
+
+/// Computes the Gini coefficient of the given stakes, ranging from 0 (all validators have the same
+/// stake) to almost 1 (a single validator holds all the stake). The stakes must be sorted in
+/// descending order.
+fn gini_coefficient(stakes: &[u64]) -> f64 {
+    let n = stakes.len() as f64;
+    let total: f64 = stakes.iter().map(|&stake| stake as f64).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    // With the stakes x_1 <= ... <= x_n sorted ascendingly, G = 2 * sum(i * x_i) / (n * sum(x_i)) - (n + 1) / n.
+    let weighted: f64 = stakes
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &stake)| (i + 1) as f64 * stake as f64)
+        .sum();
+    2.0 * weighted / (n * total) - (n + 1.0) / n
+}
+
+/// Renders the given values as a single line of block characters scaled between their minimum and
+/// maximum.
+fn sparkline(values: impl Iterator<Item = u64> + Clone) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let min = values.clone().min().unwrap_or_default();
+    let max = values.clone().max().unwrap_or_default();
+    let range = (max - min).max(1);
+    values
+        .map(|value| {
+            BARS[((value - min) as u128 * (BARS.len() - 1) as u128 / range as u128) as usize]
+        })
+        .collect()
+}
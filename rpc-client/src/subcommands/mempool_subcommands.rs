@@ -1,11 +1,39 @@
-use anyhow::Error;
+use anyhow::{bail, Error};
 use async_trait::async_trait;
-use clap::Parser;
-use nimiq_rpc_interface::mempool::MempoolInterface;
+use clap::{Parser, ValueEnum};
+use futures::StreamExt;
+use nimiq_keys::Address;
+use nimiq_primitives::{coin::Coin, networks::NetworkId};
+use nimiq_rpc_interface::{mempool::MempoolInterface, policy::PolicyInterface, types::HashOrTx};
+use nimiq_serde::Serialize;
+use nimiq_transaction::{SignatureProof, Transaction};
+use serde_json::json;
 
 use super::accounts_subcommands::HandleSubcommand;
 use crate::Client;
 
+/// How soon a transaction should be included, see [`MempoolCommand::FeeEstimate`].
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum FeePriority {
+    /// Inclusion within 10 blocks.
+    Low,
+    /// Inclusion within 3 blocks.
+    Medium,
+    /// Inclusion in the next block.
+    High,
+}
+
+impl FeePriority {
+    /// Number of blocks within which a transaction paying the estimated fee should be included.
+    fn target_blocks(self) -> usize {
+        match self {
+            FeePriority::Low => 10,
+            FeePriority::Medium => 3,
+            FeePriority::High => 1,
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 pub enum MempoolCommand {
     /// Pushes the given serialized transaction to the local mempool with normal or high priority.
@@ -19,17 +47,46 @@ pub enum MempoolCommand {
     },
 
     /// Returns the hashes or the full transactions of the local mempool.
+    #[clap(visible_alias = "mempool-list")]
     MempoolContent {
+        /// Includes the full transactions.
+        #[clap(short = 't', long, visible_alias = "full")]
+        include_transactions: bool,
+    },
+
+    /// Follows the transactions added to the local mempool and prints their hashes or the full
+    /// transactions.
+    FollowMempool {
         /// Includes the full transactions.
         #[clap(short = 't', long)]
         include_transactions: bool,
     },
 
-    /// Returns information about the local mempool.
+    /// Returns information about the local mempool: the total number of transactions and the
+    /// number of transactions per fee per byte bucket.
     MempoolInfo {},
 
     /// Returns the minimum fee per byte of the local mempool.
     MinFeePerByte {},
+
+    /// Suggests a fee per byte for a transaction to be included within a number of blocks given by
+    /// the priority, based on the transactions currently in the local mempool. Transactions paying
+    /// a higher fee per byte are assumed to be included first. Also shows the resulting fee of a
+    /// basic transaction.
+    FeeEstimate {
+        /// How soon the transaction should be included.
+        #[clap(short, long, value_enum, default_value_t = FeePriority::Medium)]
+        priority: FeePriority,
+    },
+
+    /// Bins the transactions of the local mempool into equally sized fee per byte buckets and shows
+    /// the number of transactions and the cumulative size of all transactions paying at least the
+    /// bucket's fee per byte, starting with the highest paying bucket.
+    FeeHistogram {
+        /// Number of buckets.
+        #[clap(short, long, default_value_t = 10)]
+        buckets: u32,
+    },
 }
 
 #[async_trait]
@@ -41,30 +98,208 @@ impl HandleSubcommand for MempoolCommand {
                 high_priority,
             } => {
                 if high_priority {
-                    println!(
-                        "{:#?}",
-                        client
+                    client.output.print(
+                        &client
                             .mempool
                             .push_high_priority_transaction(raw_tx)
-                            .await?
-                    );
+                            .await?,
+                    )?;
                 } else {
-                    println!("{:#?}", client.mempool.push_transaction(raw_tx).await?);
+                    client
+                        .output
+                        .print(&client.mempool.push_transaction(raw_tx).await?)?;
                 }
             }
             MempoolCommand::MempoolContent {
                 include_transactions,
             } => {
-                println!(
-                    "{:#?}",
-                    client.mempool.mempool_content(include_transactions).await?
-                );
+                let content = client
+                    .mempool
+                    .mempool_content(include_transactions)
+                    .await?
+                    .data;
+                let text = content
+                    .iter()
+                    .map(|tx| match tx {
+                        HashOrTx::Hash(hash) => hash.to_string(),
+                        HashOrTx::Tx(tx) => format!("{tx:#?}"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                client.output.print_with(&content, text)?;
+            }
+            MempoolCommand::FollowMempool {
+                include_transactions,
+            } => {
+                let _subscription = client.acquire_subscription()?;
+                loop {
+                    let mut stream = client
+                        .mempool
+                        .subscribe_for_mempool_transactions(include_transactions)
+                        .await?;
+
+                    while let Some(tx) = stream.next().await {
+                        client.output.print_item(&tx)?;
+                    }
+
+                    if !client.reconnect().await? {
+                        break;
+                    }
+                }
             }
             MempoolCommand::MempoolInfo {} => {
-                println!("{:#?}", client.mempool.mempool().await?);
+                let info = client.mempool.mempool().await?.data;
+                let buckets = [
+                    (0, info._0),
+                    (1, info._1),
+                    (2, info._2),
+                    (5, info._5),
+                    (10, info._10),
+                    (20, info._20),
+                    (50, info._50),
+                    (100, info._100),
+                    (200, info._200),
+                    (500, info._500),
+                    (1000, info._1000),
+                    (2000, info._2000),
+                    (5000, info._5000),
+                    (10000, info._10000),
+                ];
+                let mut text = format!("{} transactions in the mempool", info.total);
+                for (fee_per_byte, count) in buckets {
+                    if let Some(count) = count {
+                        text += &format!("\nfee per byte >= {fee_per_byte}: {count}");
+                    }
+                }
+                client.output.print_with(&info, text)?;
             }
             MempoolCommand::MinFeePerByte {} => {
-                println!("{:#?}", client.mempool.get_min_fee_per_byte().await?);
+                client
+                    .output
+                    .print(&client.mempool.get_min_fee_per_byte().await?)?;
+            }
+            MempoolCommand::FeeEstimate { priority } => {
+                let min_fee_per_byte = client.mempool.get_min_fee_per_byte().await?.data;
+                let block_capacity = client
+                    .policy
+                    .get_policy_constants()
+                    .await?
+                    .data
+                    .max_size_micro_body;
+
+                // Size of a signed basic transaction.
+                let mut basic_tx = Transaction::new_basic(
+                    Address::default(),
+                    Address::default(),
+                    Coin::ZERO,
+                    Coin::ZERO,
+                    0,
+                    NetworkId::Main,
+                );
+                basic_tx.proof = SignatureProof::default().serialize_to_vec();
+                let size = basic_tx.serialized_size();
+
+                let mut fees_per_byte: Vec<(f64, usize)> = client
+                    .mempool
+                    .mempool_content(true)
+                    .await?
+                    .data
+                    .into_iter()
+                    .filter_map(|tx| match tx {
+                        HashOrTx::Tx(tx) => {
+                            Some((u64::from(tx.fee) as f64 / tx.size as f64, tx.size))
+                        }
+                        HashOrTx::Hash(_) => None,
+                    })
+                    .collect();
+                fees_per_byte.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+                // Find the fee per byte of the first transaction that doesn't fit into the target
+                // number of blocks anymore together with ours. We need to outbid it.
+                let capacity = priority.target_blocks() * block_capacity;
+                let mut size_ahead = 0;
+                let mut competing_fee_per_byte = None;
+                for (fee_per_byte, tx_size) in fees_per_byte {
+                    size_ahead += tx_size;
+                    if size_ahead + size > capacity {
+                        competing_fee_per_byte = Some(fee_per_byte);
+                        break;
+                    }
+                }
+
+                let (fee_per_byte, fee) = match competing_fee_per_byte {
+                    Some(competing) if competing >= min_fee_per_byte => {
+                        (competing, (competing * size as f64).floor() as u64 + 1)
+                    }
+                    _ => (
+                        min_fee_per_byte,
+                        (min_fee_per_byte * size as f64).ceil() as u64,
+                    ),
+                };
+                let fee = Coin::from_u64_unchecked(fee);
+
+                client.output.print_with(
+                    &json!({
+                        "targetBlocks": priority.target_blocks(),
+                        "feePerByte": fee_per_byte,
+                        "basicTransactionSize": size,
+                        "basicTransactionFee": fee,
+                    }),
+                    format!(
+                        "{fee_per_byte:.3} Luna/byte for inclusion within {} block(s), {fee} NIM \
+                         for a basic transaction of {size} bytes",
+                        priority.target_blocks()
+                    ),
+                )?;
+            }
+            MempoolCommand::FeeHistogram { buckets } => {
+                if buckets == 0 {
+                    bail!("The number of buckets must be greater than zero");
+                }
+
+                let fees_per_byte: Vec<(f64, usize)> = client
+                    .mempool
+                    .mempool_content(true)
+                    .await?
+                    .data
+                    .into_iter()
+                    .filter_map(|tx| match tx {
+                        HashOrTx::Tx(tx) => {
+                            Some((u64::from(tx.fee) as f64 / tx.size as f64, tx.size))
+                        }
+                        HashOrTx::Hash(_) => None,
+                    })
+                    .collect();
+                let max_fee_per_byte = fees_per_byte
+                    .iter()
+                    .map(|(fee_per_byte, _)| *fee_per_byte)
+                    .fold(0.0, f64::max);
+                let bucket_width = (max_fee_per_byte / buckets as f64).max(f64::MIN_POSITIVE);
+
+                let mut histogram = vec![(0usize, 0usize); buckets as usize];
+                for (fee_per_byte, size) in fees_per_byte {
+                    let bucket = ((fee_per_byte / bucket_width) as usize).min(buckets as usize - 1);
+                    histogram[bucket].0 += 1;
+                    histogram[bucket].1 += size;
+                }
+
+                let mut result = vec![];
+                let mut text = vec![];
+                let mut cumulative_size = 0;
+                for (i, (count, size)) in histogram.into_iter().enumerate().rev() {
+                    let min_fee_per_byte = i as f64 * bucket_width;
+                    cumulative_size += size;
+                    result.push(json!({
+                        "minFeePerByte": min_fee_per_byte,
+                        "maxFeePerByte": min_fee_per_byte + bucket_width,
+                        "count": count,
+                        "cumulativeSize": cumulative_size,
+                    }));
+                    text.push(format!(
+                        ">= {min_fee_per_byte:>10.3} Luna/byte: {count:>6} txs, {cumulative_size:>9} bytes cumulative"
+                    ));
+                }
+                client.output.print_with(&result, text.join("\n"))?;
             }
         }
         Ok(client)
@@ -2,8 +2,11 @@ pub use accounts_subcommands::{AccountCommand, HandleSubcommand};
 pub use blockchain_subcommands::BlockchainCommand;
 pub use mempool_subcommands::MempoolCommand;
 pub use network_subcommands::NetworkCommand;
+pub use offline_subcommands::OfflineCommand;
 pub use policy_subcommands::PolicyCommand;
-pub use transactions_subcommands::TransactionCommand;
+pub use transactions_subcommands::{
+    decode_transaction, htlc_hash, verify_htlc, TransactionCommand,
+};
 pub use validator_subcommands::ValidatorCommand;
 pub use zkp_component_subcommands::ZKPComponentCommand;
 
@@ -11,6 +14,7 @@ mod accounts_subcommands;
 mod blockchain_subcommands;
 mod mempool_subcommands;
 mod network_subcommands;
+mod offline_subcommands;
 mod policy_subcommands;
 mod transactions_subcommands;
 mod validator_subcommands;
@@ -16,7 +16,25 @@ pub enum NetworkCommand {
         /// To display only the number of peers.
         #[clap(short, long)]
         count: bool,
+
+        /// To display the peers we know a contact for but are not connected to instead.
+        #[clap(long)]
+        unconnected: bool,
+
+        /// To display the contents of the peer contact book instead, i.e. the addresses, services
+        /// and timestamp of every peer we know a contact for.
+        #[clap(long, conflicts_with = "unconnected")]
+        contacts: bool,
+
+        /// Only display the contacts of peers providing all of these services, e.g.
+        /// `--required-services history,full-blocks`.
+        #[clap(long, value_delimiter = ',', requires = "contacts")]
+        required_services: Vec<String>,
     },
+
+    /// Returns the addresses our local peer is listening on, including our peer ID such that they
+    /// can be shared with other nodes, e.g. as seeds.
+    ListenAddrs {},
 }
 
 #[async_trait]
@@ -24,15 +42,69 @@ impl HandleSubcommand for NetworkCommand {
     async fn handle_subcommand(self, mut client: Client) -> Result<Client, Error> {
         match self {
             NetworkCommand::PeerId {} => {
-                println!("{:#?}", client.network.get_peer_id().await?);
+                client.output.print(&client.network.get_peer_id().await?)?;
             }
-            NetworkCommand::Peers { count } => {
-                if count {
-                    println!("{:#?}", client.network.get_peer_count().await?);
+            NetworkCommand::Peers {
+                count,
+                unconnected,
+                contacts,
+                required_services,
+            } => {
+                if contacts {
+                    let contacts = client
+                        .network
+                        .get_peer_contacts(required_services)
+                        .await?
+                        .data;
+                    if count {
+                        client.output.print(&contacts.len())?;
+                    } else {
+                        let mut text = format!(
+                            "{:<52}  {:<10}  {:<24}  {}",
+                            "PEER ID", "TIMESTAMP", "SERVICES", "ADDRESSES"
+                        );
+                        for contact in &contacts {
+                            text += &format!(
+                                "\n{:<52}  {:<10}  {:<24}  {}",
+                                contact.peer_id,
+                                contact
+                                    .timestamp
+                                    .map_or("seed".to_string(), |timestamp| timestamp.to_string()),
+                                contact.services.join(","),
+                                contact.addresses.join(" "),
+                            );
+                        }
+                        client.output.print_with(&contacts, text)?;
+                    }
+                } else if unconnected {
+                    let peers = client.network.get_unconnected_peer_list().await?;
+                    if count {
+                        client.output.print(&peers.data.len())?;
+                    } else {
+                        client.output.print(&peers)?;
+                    }
+                } else if count {
+                    client
+                        .output
+                        .print(&client.network.get_peer_count().await?)?;
                 } else {
-                    println!("{:#?}", client.network.get_peer_list().await?);
+                    client
+                        .output
+                        .print(&client.network.get_peer_list().await?)?;
                 }
             }
+            NetworkCommand::ListenAddrs {} => {
+                let peer_id = client.network.get_peer_id().await?.data;
+                let addresses: Vec<String> = client
+                    .network
+                    .get_listen_addresses()
+                    .await?
+                    .data
+                    .into_iter()
+                    .map(|address| format!("{address}/p2p/{peer_id}"))
+                    .collect();
+                client.output.print_with(&addresses, addresses.join("\n"))?;
+            }
         }
         Ok(client)
     }
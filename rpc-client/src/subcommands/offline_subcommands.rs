@@ -0,0 +1,163 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Error};
+use clap::{Args, Parser};
+use nimiq_keys::{Address, KeyPair, PrivateKey};
+use nimiq_primitives::{coin::Coin, networks::NetworkId};
+use nimiq_serde::Serialize;
+use nimiq_transaction::Transaction;
+use nimiq_transaction_builder::TransactionBuilder;
+
+use crate::{error::input_error, output::Output};
+
+/// The key a transaction is signed with offline.
+#[derive(Debug, Args)]
+pub struct OfflineKey {
+    /// The hex encoded private key to sign the transaction with. Prefer `--key-file`, since keys
+    /// given on the command line end up in the shell history.
+    #[clap(
+        long,
+        conflicts_with = "key_file",
+        required_unless_present = "key_file"
+    )]
+    pub private_key: Option<String>,
+
+    /// Reads the private key to sign the transaction with from this file. The file must contain
+    /// the hex encoded private key.
+    #[clap(long)]
+    pub key_file: Option<PathBuf>,
+}
+
+impl OfflineKey {
+    /// Reads the key pair from the given private key or key file.
+    fn key_pair(&self) -> Result<KeyPair, Error> {
+        let key_data = match (&self.private_key, &self.key_file) {
+            (Some(key_data), _) => key_data.trim().to_owned(),
+            (None, Some(path)) => fs::read_to_string(path)
+                .map_err(input_error)?
+                .trim()
+                .to_owned(),
+            (None, None) => bail!("Either a private key or a key file must be given"),
+        };
+        let private_key: PrivateKey = key_data.parse().map_err(input_error)?;
+        Ok(KeyPair::from(private_key))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct OfflineTxCommon {
+    #[clap(flatten)]
+    pub key: OfflineKey,
+
+    /// The associated transaction fee to be paid. If absent it defaults to 0 NIM.
+    #[clap(short, long, default_value = "0")]
+    pub fee: Coin,
+
+    /// The block height from which on the transaction could be applied. Since there is no node
+    /// to ask for the current block height, it must be given explicitly.
+    #[clap(short, long)]
+    pub validity_start_height: u32,
+
+    /// The network the transaction is meant for.
+    #[clap(long, default_value = "main-albatross")]
+    pub network: NetworkId,
+}
+
+/// Builds and signs transactions locally without connecting to a node, such that the signing
+/// keys don't need to be stored on the node. The signed transactions are printed in hex and can
+/// be broadcast later on from an online machine.
+#[derive(Debug, Parser)]
+pub enum OfflineCommand {
+    /// Signs a simple transaction from the account of the given key to a basic `recipient`.
+    Basic {
+        /// Recipient for this transaction. This must be a basic account.
+        recipient: Address,
+
+        /// The amount of NIM to send.
+        value: Coin,
+
+        #[clap(flatten)]
+        tx_commons: OfflineTxCommon,
+    },
+
+    /// Signs a `new_staker` transaction. The initial stake is paid from the account of the given
+    /// key, which also becomes the staker.
+    NewStaker {
+        /// The validator to delegate the stake to.
+        #[clap(long)]
+        delegation: Option<Address>,
+
+        /// The amount of NIM to stake.
+        value: Coin,
+
+        #[clap(flatten)]
+        tx_commons: OfflineTxCommon,
+    },
+
+    /// Signs a `stake` transaction, adding stake from the account of the given key to the staker
+    /// `staker_address`.
+    AddStake {
+        /// The address of the staker to add the stake to.
+        staker_address: Address,
+
+        /// The amount of NIM to add.
+        value: Coin,
+
+        #[clap(flatten)]
+        tx_commons: OfflineTxCommon,
+    },
+}
+
+impl OfflineCommand {
+    pub fn run(self, output: &Output) -> Result<(), Error> {
+        let tx = match self {
+            OfflineCommand::Basic {
+                recipient,
+                value,
+                tx_commons,
+            } => TransactionBuilder::new_basic(
+                &tx_commons.key.key_pair()?,
+                recipient,
+                value,
+                tx_commons.fee,
+                tx_commons.validity_start_height,
+                tx_commons.network,
+            )?,
+            OfflineCommand::NewStaker {
+                delegation,
+                value,
+                tx_commons,
+            } => {
+                let key_pair = tx_commons.key.key_pair()?;
+                TransactionBuilder::new_create_staker(
+                    &key_pair,
+                    &key_pair,
+                    delegation,
+                    value,
+                    tx_commons.fee,
+                    tx_commons.validity_start_height,
+                    tx_commons.network,
+                )?
+            }
+            OfflineCommand::AddStake {
+                staker_address,
+                value,
+                tx_commons,
+            } => TransactionBuilder::new_add_stake(
+                &tx_commons.key.key_pair()?,
+                staker_address,
+                value,
+                tx_commons.fee,
+                tx_commons.validity_start_height,
+                tx_commons.network,
+            )?,
+        };
+        print_signed_transaction(output, &tx)
+    }
+}
+
+/// Prints the signed transaction in hex, such that it can be sent with `send-raw`.
+fn print_signed_transaction(output: &Output, tx: &Transaction) -> Result<(), Error> {
+    let raw_tx = hex::encode(tx.serialize_to_vec());
+    output.print_with(&raw_tx, &raw_tx)
+}
@@ -1,7 +1,9 @@
 use anyhow::Error;
 use async_trait::async_trait;
 use clap::Parser;
-use nimiq_rpc_interface::policy::PolicyInterface;
+use nimiq_rpc_interface::{blockchain::BlockchainInterface, policy::PolicyInterface};
+use serde_json::json;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 use super::accounts_subcommands::HandleSubcommand;
 use crate::Client;
@@ -11,6 +13,13 @@ pub enum PolicyCommand {
     /// Returns a bundle of policy constants.
     PolicyConstants {},
 
+    /// Returns the block at which a transaction with the given validity start height expires and an
+    /// estimate of when that block will be produced.
+    ValidityWindow {
+        /// The validity start height of the transaction.
+        start_height: u32,
+    },
+
     /// Returns the epoch number at a given block number (height).
     EpochAt {
         /// The block number to fetch its epoch from.
@@ -149,116 +158,140 @@ impl HandleSubcommand for PolicyCommand {
     async fn handle_subcommand(self, mut client: Client) -> Result<Client, Error> {
         match self {
             PolicyCommand::PolicyConstants {} => {
-                println!("{:#?}", client.policy.get_policy_constants().await?);
+                client
+                    .output
+                    .print(&client.policy.get_policy_constants().await?)?;
+            }
+            PolicyCommand::ValidityWindow { start_height } => {
+                let policy_constants = client.policy.get_policy_constants().await?.data;
+                let head = client.blockchain.get_latest_block(Some(false)).await?.data;
+
+                let expiry_block = start_height + policy_constants.transaction_validity_window;
+                let remaining_blocks = expiry_block.saturating_sub(head.number);
+                let expiry_time = head.timestamp
+                    + remaining_blocks as u64 * policy_constants.block_separation_time;
+
+                let result = json!({
+                    "expiryBlock": expiry_block,
+                    "remainingBlocks": remaining_blocks,
+                    "estimatedExpiryTime": expiry_time,
+                });
+                let text = if remaining_blocks == 0 {
+                    format!("Expired at block #{expiry_block}")
+                } else {
+                    let expiry_time =
+                        OffsetDateTime::from_unix_timestamp_nanos(expiry_time as i128 * 1_000_000)?
+                            .format(&Rfc3339)?;
+                    format!(
+                        "Expires at block #{expiry_block} in {remaining_blocks} blocks, approximately at {expiry_time}"
+                    )
+                };
+                client.output.print_with(&result, text)?;
             }
             PolicyCommand::EpochAt { block_number } => {
-                println!("{:#?}", client.policy.get_epoch_at(block_number).await?);
+                client
+                    .output
+                    .print(&client.policy.get_epoch_at(block_number).await?)?;
             }
             PolicyCommand::EpochIndexAt { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_epoch_index_at(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_epoch_index_at(block_number).await?)?;
             }
             PolicyCommand::BatchAt { block_number } => {
-                println!("{:#?}", client.policy.get_batch_at(block_number).await?);
+                client
+                    .output
+                    .print(&client.policy.get_batch_at(block_number).await?)?;
             }
             PolicyCommand::BatchIndexAt { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_batch_index_at(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_batch_index_at(block_number).await?)?;
             }
             PolicyCommand::ElectionBlockAfter { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_election_block_after(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_election_block_after(block_number).await?)?;
             }
             PolicyCommand::ElectionBlockBefore { block_number } => {
-                println!(
-                    "{:#?}",
-                    client
+                client.output.print(
+                    &client
                         .policy
                         .get_election_block_before(block_number)
-                        .await?
-                );
+                        .await?,
+                )?;
             }
             PolicyCommand::LastElectionBlock { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_last_election_block(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_last_election_block(block_number).await?)?;
             }
             PolicyCommand::IsElectionBlockAt { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.is_election_block_at(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.is_election_block_at(block_number).await?)?;
             }
             PolicyCommand::MacroBlockAfter { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_macro_block_after(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_macro_block_after(block_number).await?)?;
             }
             PolicyCommand::MacroBlockBefore { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_macro_block_before(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_macro_block_before(block_number).await?)?;
             }
             PolicyCommand::LastMacroBlock { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_last_macro_block(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_last_macro_block(block_number).await?)?;
             }
             PolicyCommand::IsMacroBlockAt { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.is_macro_block_at(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.is_macro_block_at(block_number).await?)?;
             }
             PolicyCommand::IsMicroBlockAt { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.is_micro_block_at(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.is_micro_block_at(block_number).await?)?;
             }
             PolicyCommand::FirstBlockOf { epoch } => {
-                println!("{:#?}", client.policy.get_first_block_of(epoch).await?);
+                client
+                    .output
+                    .print(&client.policy.get_first_block_of(epoch).await?)?;
             }
             PolicyCommand::FirstBlockOfBatch { batch } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_first_block_of_batch(batch).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_first_block_of_batch(batch).await?)?;
             }
             PolicyCommand::ElectionBlockOf { epoch } => {
-                println!("{:#?}", client.policy.get_election_block_of(epoch).await?);
+                client
+                    .output
+                    .print(&client.policy.get_election_block_of(epoch).await?)?;
             }
             PolicyCommand::MacroBlockOf { batch } => {
-                println!("{:#?}", client.policy.get_macro_block_of(batch).await?);
+                client
+                    .output
+                    .print(&client.policy.get_macro_block_of(batch).await?)?;
             }
             PolicyCommand::FirstBatchOfEpoch { block_number } => {
-                println!(
-                    "{:#?}",
-                    client.policy.get_first_batch_of_epoch(block_number).await?
-                );
+                client
+                    .output
+                    .print(&client.policy.get_first_batch_of_epoch(block_number).await?)?;
             }
             PolicyCommand::SupplyAt {
                 genesis_supply,
                 genesis_time,
                 current_time,
             } => {
-                println!(
-                    "{:#?}",
-                    client
+                client.output.print(
+                    &client
                         .policy
                         .get_supply_at(genesis_supply, genesis_time, current_time)
-                        .await?
-                );
+                        .await?,
+                )?;
             }
         }
         Ok(client)
@@ -1,25 +1,67 @@
-use std::str::FromStr;
+use std::{
+    future::Future,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::Error;
+use anyhow::{anyhow, bail, Error};
 use async_trait::async_trait;
-use clap::{Args, Parser};
+use clap::{ArgGroup, Args, Parser};
+use futures::StreamExt;
+use nimiq_hash::{sha512::Sha512Hasher, Blake2bHash, Blake2bHasher, Hash, Sha256Hasher};
+use nimiq_jsonrpc_client::ArcClient;
 use nimiq_keys::Address;
-use nimiq_primitives::coin::Coin;
+use nimiq_primitives::{
+    account::AccountType,
+    coin::Coin,
+    networks::NetworkId,
+    policy::{Policy, TEST_POLICY},
+};
 use nimiq_rpc_interface::{
-    consensus::ConsensusInterface,
-    types::{HashAlgorithm, ValidityStartHeight},
+    blockchain::BlockchainInterface,
+    consensus::{ConsensusInterface, ConsensusProxy},
+    mempool::MempoolInterface,
+    policy::PolicyInterface,
+    types::{HashAlgorithm, HashOrTx, RPCData, ValidityStartHeight},
+};
+use nimiq_serde::Deserialize;
+use nimiq_transaction::{
+    account::{
+        htlc_contract::{self, AnyHash, AnyHash32, AnyHash64, PreImage},
+        staking_contract::{IncomingStakingTransactionData, OutgoingStakingTransactionData},
+        vesting_contract,
+    },
+    Transaction, TransactionError, TransactionFlags,
 };
-use nimiq_transaction::account::htlc_contract::{AnyHash, AnyHash32, AnyHash64, PreImage};
+use serde_json::json;
 
 use super::accounts_subcommands::HandleSubcommand;
-use crate::Client;
+use crate::{
+    batch::{read_batch_file, read_payout_file, BatchTransaction, BatchTransactionType},
+    error::input_error,
+    output::Output,
+    sent_transactions::SentTransactionStore,
+    transport::Transport,
+    Client,
+};
 
 #[derive(Debug, Args)]
+#[clap(group(
+    ArgGroup::new("dry_run")
+    .multiple(true)
+    .args(&["dry", "dry_verbose"]),
+    ))]
 pub struct TxCommon {
     /// The associated transaction fee to be paid. If absent it defaults to 0 NIM.
     #[clap(short, long, default_value = "0")]
     pub fee: Coin,
 
+    /// Pays a fee of this many Luna per byte of the serialized transaction instead of a flat fee.
+    #[clap(long, value_name = "LUNA", conflicts_with = "fee")]
+    pub fee_per_byte: Option<u64>,
+
     /// The block height from which on the transaction could be applied. The maximum amount of blocks the transaction is valid for
     /// is specified in `TRANSACTION_VALIDITY_WINDOW`.
     /// If absent it defaults to the current block height at time of processing.
@@ -29,6 +71,124 @@ pub struct TxCommon {
     /// Don't actually send the transaction, but output the transaction as hex string.
     #[clap(long)]
     pub dry: bool,
+
+    /// Like `--dry`, but additionally prints the serialized size of the transaction, the fee per
+    /// byte implied by the fee and a warning if the fee is below the minimum fee per byte the node
+    /// accepts into its mempool.
+    #[clap(long)]
+    pub dry_verbose: bool,
+
+    /// Together with `--dry` or `--dry-verbose`, additionally estimates in how many blocks the
+    /// transaction would be included, based on the transactions in the mempool paying at least the
+    /// same fee per byte.
+    #[clap(long, requires = "dry_run")]
+    pub estimate_inclusion: bool,
+
+    /// Prints the size, fee, fee per byte and the end of the validity window of the transaction
+    /// before it is sent. Unless `--yes` is given, asks for confirmation before sending.
+    #[clap(long)]
+    pub show_summary: bool,
+
+    /// Sends the transaction without asking for confirmation after showing the summary.
+    #[clap(long, requires = "show_summary")]
+    pub yes: bool,
+
+    /// After sending, waits until the transaction is included in a block that is at least this many
+    /// blocks deep and prints its final status. Fails if this doesn't happen within
+    /// `--confirmation-timeout` blocks. If given without a value, waits for a single confirmation.
+    #[clap(
+        long,
+        visible_alias = "confirm",
+        num_args = 0..=1,
+        default_missing_value = "1",
+        conflicts_with_all = ["dry", "dry_verbose"]
+    )]
+    pub confirmations: Option<u32>,
+
+    /// Number of blocks to wait for the requested confirmations before giving up.
+    #[clap(
+        long,
+        visible_alias = "confirm-timeout",
+        default_value_t = 120,
+        requires = "confirmations"
+    )]
+    pub confirmation_timeout: u32,
+}
+
+impl TxCommon {
+    /// Returns whether the transaction should only be printed instead of sent.
+    pub fn is_dry(&self) -> bool {
+        self.dry || self.dry_verbose
+    }
+
+    /// Creates a transaction with `create`, which is given a consensus proxy and the fee to create
+    /// the transaction with. With `--fee-per-byte`, the transaction is first created without a fee
+    /// to determine its serialized size, which doesn't depend on the fee, and then created again,
+    /// such that the node signs it with the fee for that size.
+    pub async fn create_transaction<F, Fut, E>(
+        &self,
+        client: &Client,
+        create: F,
+    ) -> Result<RPCData<String, ()>, Error>
+    where
+        F: Fn(ConsensusProxy<ArcClient<Transport>>, Coin) -> Fut,
+        Fut: Future<Output = Result<RPCData<String, ()>, E>>,
+        Error: From<E>,
+    {
+        let consensus = || ConsensusProxy::new(client.rpc_client.clone());
+        let Some(fee_per_byte) = self.fee_per_byte else {
+            return Ok(create(consensus(), self.fee).await?);
+        };
+
+        let size = hex::decode(create(consensus(), Coin::ZERO).await?.data)?.len();
+        let fee = Coin::try_from(fee_per_byte)
+            .ok()
+            .and_then(|fee_per_byte| fee_per_byte.checked_mul(size as u64))
+            .ok_or_else(|| {
+                input_error(anyhow!(
+                    "A fee of {fee_per_byte} Luna per byte exceeds the maximum amount for a \
+                     transaction of {size} bytes"
+                ))
+            })?;
+        Ok(create(consensus(), fee).await?)
+    }
+
+    /// Computes the cost summary printed by `--dry-verbose` for a transaction of the given
+    /// serialized size paying the given fee. Returns the summary as JSON and as text.
+    async fn cost_summary(
+        client: &mut Client,
+        size: usize,
+        fee: Coin,
+    ) -> Result<(serde_json::Value, String), Error> {
+        let min_fee_per_byte = client.mempool.get_min_fee_per_byte().await?.data;
+        let fee_per_byte = u64::from(fee) as f64 / size as f64;
+        let recommended_fee =
+            Coin::from_u64_unchecked((min_fee_per_byte * size as f64).ceil() as u64);
+
+        let mut text = format!(
+            "size: {size} bytes\n\
+             fee: {} NIM\n\
+             fee per byte: {fee_per_byte:.3} Luna/byte",
+            fee,
+        );
+        if fee_per_byte < min_fee_per_byte {
+            text += &format!(
+                "\nwarning: the fee is below the minimum of {min_fee_per_byte:.3} Luna/byte, \
+                 the transaction would not be accepted into the mempool. Pay at least {recommended_fee} NIM."
+            );
+        }
+
+        Ok((
+            json!({
+                "size": size,
+                "fee": fee,
+                "feePerByte": fee_per_byte,
+                "minFeePerByte": min_fee_per_byte,
+                "recommendedFee": recommended_fee,
+            }),
+            text,
+        ))
+    }
 }
 
 #[derive(Debug, Args)]
@@ -43,6 +203,7 @@ pub struct TxCommonWithValue {
 #[derive(Debug, Parser)]
 pub enum TransactionCommand {
     /// Sends a simple transaction from the wallet `wallet` to a basic `recipient`.
+    /// Sent transactions are remembered locally, such that they can be resubmitted later on.
     Basic {
         /// Transaction will be sent from this address. The sender wallet must be unlocked prior to this action.
         sender_wallet: Address,
@@ -54,6 +215,82 @@ pub enum TransactionCommand {
         tx_commons: TxCommonWithValue,
     },
 
+    /// Pays several recipients from `sender_wallet`.
+    /// Albatross transactions have exactly one recipient, so this can't be done in a single
+    /// transaction. The outputs are validated and the command fails with an explanation, pointing
+    /// to sending one `basic` transaction per recipient instead.
+    MultiSend {
+        /// Transactions would be sent from this address.
+        sender_wallet: Address,
+
+        /// The recipients and the amount of NIM to send to each of them, given as `<address>=<value>`.
+        #[clap(required = true, num_args = 1.., value_parser = parse_output)]
+        outputs: Vec<(Address, Coin)>,
+    },
+
+    /// Sends the transactions described in a JSON or CSV file one after the other and prints a
+    /// summary of the sent transactions and failures at the end.
+    /// Each transaction is described by its `type` (`basic` or `stake`), `sender`, `recipient`,
+    /// `value` and optional `fee` in NIM. CSV files need a header naming these columns, JSON
+    /// files contain an array of objects with these fields. The sender wallets must be unlocked
+    /// prior to this action.
+    Batch {
+        /// The file describing the transactions. Files ending in `.csv` are parsed as CSV, all
+        /// other files as JSON.
+        path: PathBuf,
+
+        /// Don't actually send the transactions, but output each of them as hex string.
+        #[clap(long)]
+        dry: bool,
+    },
+
+    /// Sends basic transactions from `sender_wallet` to all recipients listed in a CSV file, one
+    /// after the other, and prints the txid or error per recipient and a summary at the end.
+    /// Each line of the file contains the `address,value,fee` of a recipient, where the fee is
+    /// optional. Values and fees are given in NIM. The wallet must be unlocked prior to this action.
+    BatchPay {
+        /// The wallet paying the recipients.
+        sender_wallet: Address,
+
+        /// The CSV file listing the recipients.
+        recipients_file: PathBuf,
+
+        /// Stop at the first transaction that fails instead of continuing with the next recipient.
+        #[clap(long)]
+        fail_fast: bool,
+
+        /// Don't actually send the transactions, but output each of them as hex string.
+        #[clap(long)]
+        dry: bool,
+    },
+
+    /// Re-broadcasts the locally remembered transactions of `sender_wallet` that are neither confirmed
+    /// nor in the mempool anymore (e.g. because they were evicted during congestion).
+    /// Confirmed and expired transactions are removed from the local store.
+    Resubmit {
+        /// The sender whose transactions should be resubmitted.
+        sender_wallet: Address,
+    },
+
+    /// Decodes a raw transaction given in hex, e.g. as printed by `--dry`, and verifies it.
+    /// Prints the sender, recipient, value, fee, validity window and the data of the transaction,
+    /// decoded for contract transactions, together with the result of verifying the transaction
+    /// including its signature proofs. This doesn't need a connection to a node.
+    #[clap(alias = "decode")]
+    DecodeTransaction {
+        /// The serialized transaction in hex. Use `-` to read it from stdin.
+        raw: String,
+    },
+
+    /// Sends a pre-signed transaction given in hex, e.g. as printed by `--dry` or `sign-offline`,
+    /// to the network. This allows signing transactions on an offline machine and broadcasting
+    /// them from an online node later on. The transaction and its signature are verified before
+    /// sending it.
+    SendRaw {
+        /// The serialized transaction in hex, or `-` to read it from stdin.
+        tx_hex: String,
+    },
+
     /* Staker transactions */
     /// Sends a `new_staker` transaction to the network. You need to provide the address of a basic
     /// account (the sender wallet) to pay the transaction fee.
@@ -207,6 +444,38 @@ pub enum TransactionCommand {
     },
 
     /* HTLC transactions */
+    /// Computes the `hash_root` of a HTLC by hashing the pre-image `hash_count` times, the same way
+    /// the HTLC contract does when it is redeemed. This doesn't need a connection to a node.
+    HtlcHash {
+        /// The pre-image, 32 or 64 bytes in hex.
+        pre_image: PreImage,
+
+        /// Number of times the pre-image is hashed.
+        hash_count: u8,
+
+        /// The hashing algorithm to use.
+        #[clap(value_enum)]
+        hash_algorithm: HashAlgorithm,
+    },
+
+    /// Verifies that hashing the pre-image `hash_count` times yields the `hash_root` of a HTLC, i.e.
+    /// that the pre-image can redeem it with `RedeemRegularHTLC`. This doesn't need a connection to
+    /// a node.
+    VerifyHtlc {
+        /// The pre-image, 32 or 64 bytes in hex.
+        pre_image: PreImage,
+
+        /// The `hash_root` of the HTLC contract.
+        hash_root: String,
+
+        /// Number of times the pre-image is hashed.
+        hash_count: u8,
+
+        /// The hashing algorithm of the HTLC contract.
+        #[clap(value_enum)]
+        hash_algorithm: HashAlgorithm,
+    },
+
     /// Sends a transaction creating a new HTLC contract to the network.
     CreateHTLC {
         /// The wallet to sign the transaction. The HTLC contract value is sent from the basic account belonging to this wallet.
@@ -330,6 +599,55 @@ pub enum TransactionCommand {
     },
 }
 
+/// How often a transaction is sent before giving up.
+const SEND_ATTEMPTS: usize = 3;
+
+/// How long to wait before retrying to send a transaction.
+const SEND_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Sends a raw transaction, retrying if the request fails because of the connection to the node.
+/// Errors reported by the node, e.g. the mempool rejecting the transaction, are returned right
+/// away.
+///
+/// A failed request doesn't necessarily mean that the node didn't accept the transaction, e.g. if
+/// the connection dropped before the response arrived. Thus, before every retry the transaction
+/// hash is looked up in the mempool and in the chain and the transaction is only sent again if it
+/// is in neither of them.
+async fn send_idempotent(client: &mut Client, raw_tx: String) -> Result<Blake2bHash, Error> {
+    let hash: Blake2bHash = Transaction::deserialize_from_vec(&hex::decode(&raw_tx)?)?.hash();
+    let mut attempt = 1;
+    loop {
+        let error = match client.consensus.send_raw_transaction(raw_tx.clone()).await {
+            Ok(txid) => return Ok(txid.data),
+            Err(error) if error.is_connection_error() => error,
+            Err(error) => return Err(error.into()),
+        };
+
+        if client
+            .mempool
+            .get_transaction_from_mempool(hash.clone())
+            .await
+            .is_ok()
+            || client
+                .blockchain
+                .get_transaction_by_hash(hash.clone())
+                .await
+                .is_ok()
+        {
+            return Ok(hash);
+        }
+
+        if attempt == SEND_ATTEMPTS {
+            return Err(error.into());
+        }
+        attempt += 1;
+        client.output.info(format!(
+            "Sending transaction {hash} failed: {error}. Retrying..."
+        ));
+        tokio::time::sleep(SEND_RETRY_DELAY).await;
+    }
+}
+
 impl TransactionCommand {
     fn parse_hash(hash_algorithm: &HashAlgorithm, hash_str: String) -> Result<AnyHash, Error> {
         match hash_algorithm {
@@ -340,6 +658,527 @@ impl TransactionCommand {
     }
 }
 
+/// Prints the result of a dry run, i.e. the raw transaction, optionally together with its cost
+/// summary and an estimate of the number of blocks until it would be included.
+async fn print_dry_run(
+    client: &mut Client,
+    raw_tx: RPCData<String, ()>,
+    tx_common: &TxCommon,
+) -> Result<(), Error> {
+    if !tx_common.estimate_inclusion && !tx_common.dry_verbose {
+        return client.output.print(&raw_tx);
+    }
+
+    let raw_tx = raw_tx.data;
+    let size = hex::decode(&raw_tx)?.len();
+    let tx = Transaction::deserialize_from_vec(&hex::decode(&raw_tx)?)?;
+    let mut json = json!({ "transaction": raw_tx });
+    let mut text = raw_tx.clone();
+
+    if tx_common.dry_verbose {
+        let (summary_json, summary_text) = TxCommon::cost_summary(client, size, tx.fee).await?;
+        json["cost"] = summary_json;
+        text += &format!("\n{summary_text}");
+    }
+    if !tx_common.estimate_inclusion {
+        return client.output.print_with(&json, text);
+    }
+
+    let fee_per_byte = u64::from(tx.fee) as f64 / size as f64;
+
+    // Transactions paying at least the same fee per byte are assumed to be included first.
+    let size_ahead: usize = client
+        .mempool
+        .mempool_content(true)
+        .await?
+        .data
+        .into_iter()
+        .filter_map(|tx| match tx {
+            HashOrTx::Tx(tx) if u64::from(tx.fee) as f64 / tx.size as f64 >= fee_per_byte => {
+                Some(tx.size)
+            }
+            _ => None,
+        })
+        .sum();
+    let block_capacity = client
+        .policy
+        .get_policy_constants()
+        .await?
+        .data
+        .max_size_micro_body;
+    let blocks = (size_ahead + size).div_ceil(block_capacity);
+
+    json["feePerByte"] = json!(fee_per_byte);
+    json["sizeAhead"] = json!(size_ahead);
+    json["estimatedInclusionBlocks"] = json!(blocks);
+    text += &format!(
+        "\nEstimated inclusion within {blocks} block(s) at {fee_per_byte:.3} Luna/byte, \
+         {size_ahead} bytes of pending transactions pay at least the same fee per byte"
+    );
+    client.output.print_with(&json, text)
+}
+
+/// Sends the given transaction, or prints it if `--dry` is set. With `--show-summary`, a summary
+/// of the transaction is printed first and, unless `--yes` is given, the user is asked to confirm
+/// sending it. Returns the hash of the sent transaction, or `None` for a dry run.
+pub(crate) async fn send_transaction(
+    client: &mut Client,
+    tx: RPCData<String, ()>,
+    tx_common: &TxCommon,
+) -> Result<Option<Blake2bHash>, Error> {
+    send_transaction_with(client, tx, tx_common, false).await
+}
+
+/// Sends the given transaction like [`send_transaction`], but retries sending it on connection
+/// errors, see [`send_idempotent`].
+pub(crate) async fn send_transaction_idempotent(
+    client: &mut Client,
+    tx: RPCData<String, ()>,
+    tx_common: &TxCommon,
+) -> Result<Option<Blake2bHash>, Error> {
+    send_transaction_with(client, tx, tx_common, true).await
+}
+
+async fn send_transaction_with(
+    client: &mut Client,
+    tx: RPCData<String, ()>,
+    tx_common: &TxCommon,
+    idempotent: bool,
+) -> Result<Option<Blake2bHash>, Error> {
+    if tx_common.show_summary {
+        print_summary(client, &tx.data).await?;
+    }
+    if tx_common.is_dry() {
+        print_dry_run(client, tx, tx_common).await?;
+        return Ok(None);
+    }
+    if tx_common.show_summary && !tx_common.yes && !confirm("Send this transaction?")? {
+        bail!("Transaction was not sent");
+    }
+
+    let raw_tx = tx.data;
+    let txid = if idempotent {
+        send_idempotent(client, raw_tx.clone()).await?
+    } else {
+        client
+            .consensus
+            .send_raw_transaction(raw_tx.clone())
+            .await?
+            .data
+    };
+    remember_sent_transaction(client, &raw_tx);
+    client.output.print(&txid)?;
+    Ok(Some(txid))
+}
+
+/// Stores the sent raw transaction in the [`SentTransactionStore`], such that it can be
+/// resubmitted if it is dropped from the mempool. Failing to store it doesn't fail sending.
+fn remember_sent_transaction(client: &Client, raw_tx: &str) {
+    let result = hex::decode(raw_tx)
+        .map_err(Error::from)
+        .and_then(|bytes| Ok(Transaction::deserialize_from_vec(&bytes)?))
+        .and_then(|tx| {
+            SentTransactionStore::open_default().and_then(|store| store.push(&tx.sender, raw_tx))
+        });
+    if let Err(error) = result {
+        client
+            .output
+            .info(format!("Could not remember sent transaction: {error}"));
+    }
+}
+
+/// Prints the serialized size, fee, fee per byte and the end of the validity window of the given
+/// raw transaction.
+async fn print_summary(client: &mut Client, raw_tx: &str) -> Result<(), Error> {
+    let bytes = hex::decode(raw_tx)?;
+    let tx = Transaction::deserialize_from_vec(&bytes)?;
+    let validity_window = client
+        .policy
+        .get_policy_constants()
+        .await?
+        .data
+        .transaction_validity_window;
+    let fee_per_byte = u64::from(tx.fee) as f64 / bytes.len() as f64;
+
+    client.output.info(format!(
+        "size: {} bytes\n\
+         fee: {} NIM\n\
+         fee per byte: {fee_per_byte:.3} Luna/byte\n\
+         valid until block: #{}",
+        bytes.len(),
+        tx.fee,
+        tx.validity_start_height.saturating_add(validity_window),
+    ));
+    Ok(())
+}
+
+/// Asks the user the given yes/no question on the terminal. Returns whether it was answered with
+/// yes.
+fn confirm(question: &str) -> Result<bool, Error> {
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Waits until the transaction with the given hash is included in a block that is at least as many
+/// blocks deep as requested by `tx_common`, if confirmations were requested at all, and prints its
+/// final status. Fails if the transaction isn't confirmed within the configured timeout.
+pub(crate) async fn wait_for_confirmations(
+    client: &mut Client,
+    hash: &Blake2bHash,
+    tx_common: &TxCommon,
+) -> Result<(), Error> {
+    let Some(confirmations) = tx_common.confirmations else {
+        return Ok(());
+    };
+
+    let _subscription = client.acquire_subscription()?;
+    let mut stream = client
+        .blockchain
+        .subscribe_for_head_block(Some(false))
+        .await?;
+    let start = client.head_height().await?;
+    while let Some(block) = stream.next().await {
+        let head = block.data.number;
+        let tx = client
+            .blockchain
+            .find_transaction_by_hash(hash.clone())
+            .await?
+            .data;
+        if let Some(tx) = tx {
+            if let Some(block_number) = tx.transaction().block_number {
+                let depth = head.saturating_sub(block_number) + 1;
+                if depth >= confirmations {
+                    let execution = if tx.succeeded() {
+                        "succeeded"
+                    } else {
+                        "failed"
+                    };
+                    client.output.print_with(
+                        &json!({
+                            "hash": hash,
+                            "blockNumber": block_number,
+                            "confirmations": depth,
+                            "executionResult": tx.succeeded(),
+                        }),
+                        format!(
+                            "Transaction {hash} was included in block #{block_number} and has \
+                             {depth} confirmations, execution {execution}"
+                        ),
+                    )?;
+                    return Ok(());
+                }
+            }
+        }
+        if head.saturating_sub(start) >= tx_common.confirmation_timeout {
+            bail!(
+                "Transaction {hash} wasn't confirmed within {} blocks",
+                tx_common.confirmation_timeout
+            );
+        }
+    }
+    bail!("The head block subscription ended before transaction {hash} was confirmed")
+}
+
+/// Returns the number of blocks a transaction is valid on the given network. Unlike
+/// [`Policy::transaction_validity_window_blocks`], this doesn't depend on the policy of this
+/// process, since transactions can be decoded for any network.
+fn validity_window_blocks(network_id: NetworkId) -> u32 {
+    let policy = match network_id {
+        NetworkId::UnitAlbatross => TEST_POLICY,
+        _ => Policy::default(),
+    };
+    policy.transaction_validity_window * policy.blocks_per_batch
+}
+
+/// Reads a serialized transaction given in hex, or from stdin if `tx_hex` is `-`. Returns the
+/// trimmed hex string together with the deserialized transaction.
+fn read_raw_transaction(tx_hex: &str) -> Result<(String, Transaction), Error> {
+    let raw_tx = if tx_hex == "-" {
+        io::read_to_string(io::stdin())?.trim().to_string()
+    } else {
+        tx_hex.trim().to_string()
+    };
+    let tx = hex::decode(&raw_tx)
+        .map_err(Error::from)
+        .and_then(|bytes| Ok(Transaction::deserialize_from_vec(&bytes)?))
+        .map_err(|error| anyhow!("The given hex string is not a valid transaction: {error}"))?;
+    Ok((raw_tx, tx))
+}
+
+/// Hashes the pre-image `hash_count` times with the given algorithm, like the HTLC contract does
+/// to verify a pre-image when redeeming it.
+fn htlc_hash_root(
+    pre_image: &PreImage,
+    hash_count: u8,
+    hash_algorithm: &HashAlgorithm,
+) -> PreImage {
+    match hash_algorithm {
+        HashAlgorithm::Blake2b => pre_image.hash_repeatedly::<Blake2bHasher>(hash_count),
+        HashAlgorithm::Sha256 => pre_image.hash_repeatedly::<Sha256Hasher>(hash_count),
+        HashAlgorithm::Sha512 => pre_image.hash_repeatedly::<Sha512Hasher>(hash_count),
+    }
+}
+
+/// Computes and prints the hash root of a HTLC, see [`TransactionCommand::HtlcHash`].
+pub fn htlc_hash(
+    pre_image: &PreImage,
+    hash_count: u8,
+    hash_algorithm: &HashAlgorithm,
+    output: &Output,
+) -> Result<(), Error> {
+    if hash_count == 0 {
+        bail!("The hash count must be at least 1");
+    }
+    let hash_root = htlc_hash_root(pre_image, hash_count, hash_algorithm).to_hex();
+    output.print_with(
+        &json!({
+            "hashRoot": hash_root,
+            "hashCount": hash_count,
+            "hashAlgorithm": hash_algorithm,
+        }),
+        &hash_root,
+    )
+}
+
+/// Verifies a pre-image against the hash root of a HTLC and prints the result, see
+/// [`TransactionCommand::VerifyHtlc`]. Fails if the pre-image doesn't match.
+pub fn verify_htlc(
+    pre_image: &PreImage,
+    hash_root: String,
+    hash_count: u8,
+    hash_algorithm: &HashAlgorithm,
+    output: &Output,
+) -> Result<(), Error> {
+    if hash_count == 0 {
+        bail!("The hash count must be at least 1");
+    }
+    let hash_root = TransactionCommand::parse_hash(hash_algorithm, hash_root)?;
+    let computed = htlc_hash_root(pre_image, hash_count, hash_algorithm);
+    if computed.as_bytes() != hash_root.as_bytes() {
+        bail!(
+            "The pre-image doesn't match the hash root, hashing it {hash_count} times with \
+             {hash_algorithm:?} yields {}",
+            computed.to_hex()
+        );
+    }
+    output.print_with(
+        &json!({
+            "matches": true,
+            "hashRoot": hash_root.to_hex(),
+        }),
+        "The pre-image matches the hash root",
+    )
+}
+
+/// Decodes and verifies the transaction given in hex, see [`TransactionCommand::DecodeTransaction`].
+pub fn decode_transaction(raw: &str, output: &Output) -> Result<(), Error> {
+    let (_, tx) = read_raw_transaction(raw)?;
+    let mut data = decode_transaction_data(&tx);
+    if data.is_empty() && !tx.recipient_data.is_empty() {
+        data.push(("data", describe_raw_data(&tx.recipient_data)));
+    }
+    let verification = if tx.proof.is_empty() {
+        "unsigned".to_string()
+    } else {
+        match tx.verify(tx.network_id) {
+            Ok(()) => "valid".to_string(),
+            Err(error) => format!("invalid ({error})"),
+        }
+    };
+    let valid_until = tx
+        .validity_start_height
+        .saturating_add(validity_window_blocks(tx.network_id));
+
+    let mut text = format!(
+        "Transaction {} ({:?})\n\
+         sender: {} ({:?})\n\
+         recipient: {} ({:?})\n\
+         value: {} NIM\n\
+         fee: {} NIM\n\
+         validity window: #{} to #{}\n\
+         network: {}",
+        tx.hash::<Blake2bHash>(),
+        tx.format(),
+        tx.sender,
+        tx.sender_type,
+        tx.recipient,
+        tx.recipient_type,
+        tx.value,
+        tx.fee,
+        tx.validity_start_height,
+        valid_until,
+        tx.network_id,
+    );
+    for (field, value) in &data {
+        text += &format!("\n{field}: {value}");
+    }
+    text += &format!("\nverification: {verification}");
+
+    let mut result = serde_json::to_value(
+        nimiq_rpc_interface::types::Transaction::from_transaction(tx),
+    )?;
+    let decoded: serde_json::Map<_, _> = data
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), json!(value)))
+        .collect();
+    result["decoded"] = decoded.into();
+    result["validUntil"] = json!(valid_until);
+    result["verification"] = json!(verification);
+    output.print_with(&result, text)
+}
+
+/// Describes the data of a basic transaction, i.e. its message, as text if it is printable and in
+/// hex otherwise.
+fn describe_raw_data(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("{text:?}"),
+        _ => hex::encode(data),
+    }
+}
+
+/// Decodes the recipient data, sender data and proof of contract transactions, returning them
+/// together with the name of the field they were decoded from. Data that doesn't belong to a
+/// contract transaction is left as is.
+fn decode_transaction_data(tx: &Transaction) -> Vec<(&'static str, String)> {
+    fn describe<T: std::fmt::Debug>(data: Result<T, TransactionError>) -> String {
+        match data {
+            Ok(data) => format!("{data:?}"),
+            Err(error) => format!("invalid ({error})"),
+        }
+    }
+
+    let mut data = vec![];
+    let contract_creation = tx.flags.contains(TransactionFlags::CONTRACT_CREATION);
+    match tx.recipient_type {
+        AccountType::Staking => data.push((
+            "recipientData",
+            describe(IncomingStakingTransactionData::parse(tx)),
+        )),
+        AccountType::Vesting if contract_creation => data.push((
+            "recipientData",
+            describe(vesting_contract::CreationTransactionData::parse(tx)),
+        )),
+        AccountType::HTLC if contract_creation => data.push((
+            "recipientData",
+            describe(htlc_contract::CreationTransactionData::parse(tx)),
+        )),
+        _ => {}
+    }
+    match tx.sender_type {
+        AccountType::Staking => data.push((
+            "senderData",
+            describe(OutgoingStakingTransactionData::parse(tx)),
+        )),
+        AccountType::HTLC => data.push((
+            "proof",
+            describe(htlc_contract::OutgoingHTLCTransactionProof::parse(tx)),
+        )),
+        _ => {}
+    }
+    data
+}
+
+/// Creates the given transaction of a batch and sends it unless `dry` is set. Returns the hash
+/// of the sent transaction, or the signed transaction in hex for a dry run.
+async fn send_batch_transaction(
+    client: &mut Client,
+    transaction: BatchTransaction,
+    dry: bool,
+) -> Result<String, Error> {
+    let validity_start_height = client
+        .validity_start_height(ValidityStartHeight::default())
+        .await?;
+    let raw_tx = match transaction.tx_type {
+        BatchTransactionType::Basic => {
+            client
+                .consensus
+                .create_basic_transaction(
+                    transaction.sender.clone(),
+                    transaction.recipient,
+                    transaction.value,
+                    transaction.fee,
+                    validity_start_height,
+                )
+                .await?
+        }
+        BatchTransactionType::Stake => {
+            client
+                .consensus
+                .create_stake_transaction(
+                    transaction.sender.clone(),
+                    transaction.recipient,
+                    transaction.value,
+                    transaction.fee,
+                    validity_start_height,
+                )
+                .await?
+        }
+    }
+    .data;
+    if dry {
+        return Ok(raw_tx);
+    }
+
+    let txid = client
+        .consensus
+        .send_raw_transaction(raw_tx.clone())
+        .await?
+        .data;
+    remember_sent_transaction(client, &raw_tx);
+    Ok(txid.to_string())
+}
+
+/// Prints a line per transaction of a batch with the hash of the sent transaction, or the signed
+/// transaction for a dry run, and a summary at the end. Fails if any of the transactions failed.
+fn print_batch_results(
+    client: &Client,
+    label: &str,
+    results: Vec<(serde_json::Value, Result<String, Error>)>,
+    dry: bool,
+) -> Result<(), Error> {
+    let key = if dry { "transaction" } else { "txid" };
+    let mut summary = vec![];
+    let mut text = format!("{:<44}  {}", label.to_uppercase(), key.to_uppercase());
+    for (id, result) in &results {
+        let id_text = id.as_str().map_or(id.to_string(), str::to_string);
+        match result {
+            Ok(value) => {
+                summary.push(json!({ (label): id, (key): value }));
+                text += &format!("\n{id_text:<44}  {value}");
+            }
+            Err(error) => {
+                summary.push(json!({ (label): id, "error": format!("{error:#}") }));
+                text += &format!("\n{id_text:<44}  FAILED: {error:#}");
+            }
+        }
+    }
+    let failures = results.iter().filter(|(_, result)| result.is_err()).count();
+    text += &format!(
+        "\n{} of {} transactions {}, {failures} failed",
+        results.len() - failures,
+        results.len(),
+        if dry { "signed" } else { "sent" },
+    );
+    client.output.print_with(&summary, text)?;
+    if failures > 0 {
+        bail!("{failures} of {} transactions failed", results.len());
+    }
+    Ok(())
+}
+
+/// Parses a transaction output given as `<address>=<value>`.
+fn parse_output(output: &str) -> Result<(Address, Coin), String> {
+    let (address, value) = output
+        .split_once('=')
+        .ok_or_else(|| format!("Expected `<address>=<value>`, got `{output}`"))?;
+    let address = Address::from_any_str(address.trim()).map_err(|error| error.to_string())?;
+    let value = Coin::from_str(value.trim()).map_err(|error| error.to_string())?;
+    Ok((address, value))
+}
+
 #[async_trait]
 impl HandleSubcommand for TransactionCommand {
     async fn handle_subcommand(self, mut client: Client) -> Result<Client, Error> {
@@ -349,64 +1188,202 @@ impl HandleSubcommand for TransactionCommand {
                 recipient,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_basic_transaction(
-                            sender_wallet,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_basic_transaction(
-                            sender_wallet,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let recipient = recipient.clone();
+                        async move {
+                            consensus
+                                .create_basic_transaction(
+                                    sender_wallet,
+                                    recipient,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
+            TransactionCommand::MultiSend {
+                sender_wallet,
+                outputs,
+            } => {
+                let total = outputs
+                    .iter()
+                    .try_fold(Coin::ZERO, |total, (_, value)| total.checked_add(*value))
+                    .ok_or_else(|| anyhow::anyhow!("The total value of the outputs overflows"))?;
+                bail!(
+                    "Transactions can only have a single recipient, so paying {} recipients a total \
+                     of {total} NIM from {sender_wallet} requires one `basic` transaction per recipient",
+                    outputs.len(),
+                );
+            }
+            TransactionCommand::Batch { path, dry } => {
+                let mut results = vec![];
+                for (index, transaction) in read_batch_file(&path)?.into_iter().enumerate() {
+                    let result = send_batch_transaction(&mut client, transaction, dry).await;
+                    results.push((json!(index + 1), result));
+                }
+                print_batch_results(&client, "row", results, dry)?;
+            }
+            TransactionCommand::BatchPay {
+                sender_wallet,
+                recipients_file,
+                fail_fast,
+                dry,
+            } => {
+                let mut results = vec![];
+                for transaction in read_payout_file(&recipients_file, &sender_wallet)? {
+                    let recipient = json!(transaction.recipient);
+                    let result = send_batch_transaction(&mut client, transaction, dry).await;
+                    let failed = result.is_err();
+                    results.push((recipient, result));
+                    if failed && fail_fast {
+                        break;
+                    }
+                }
+                print_batch_results(&client, "recipient", results, dry)?;
+            }
+            TransactionCommand::DecodeTransaction { .. }
+            | TransactionCommand::HtlcHash { .. }
+            | TransactionCommand::VerifyHtlc { .. } => {
+                unreachable!("offline commands are run without connecting to a node")
+            }
+            TransactionCommand::SendRaw { tx_hex } => {
+                let (raw_tx, tx) = read_raw_transaction(&tx_hex)?;
+                if let Err(error) = tx.verify(tx.network_id) {
+                    bail!("The given transaction is invalid: {error}");
+                }
+                let txid = client
+                    .consensus
+                    .send_raw_transaction(raw_tx.clone())
+                    .await?;
+                remember_sent_transaction(&client, &raw_tx);
+                client.output.print(&txid)?;
+            }
+            TransactionCommand::Resubmit { sender_wallet } => {
+                let store = SentTransactionStore::open_default()?;
+                let block_number = client.head_height().await?;
+                let validity_window = client
+                    .policy
+                    .get_policy_constants()
+                    .await?
+                    .data
+                    .transaction_validity_window;
+
+                let mut transactions = vec![];
+                for raw_tx in store.get(&sender_wallet)? {
+                    // Corrupt entries are dropped from the store instead of failing the resubmission
+                    // of the remaining transactions.
+                    let tx = hex::decode(&raw_tx)
+                        .map_err(Error::from)
+                        .and_then(|bytes| Ok(Transaction::deserialize_from_vec(&bytes)?));
+                    match tx {
+                        Ok(tx) => transactions.push((tx, raw_tx)),
+                        Err(error) => client
+                            .output
+                            .info(format!("Skipping invalid stored transaction: {error}")),
+                    }
+                }
+                // Resubmit in the order in which the transactions become valid.
+                transactions.sort_by_key(|(tx, _)| tx.validity_start_height);
+
+                let mut remaining = vec![];
+                for (tx, raw_tx) in transactions {
+                    let hash: Blake2bHash = tx.hash();
+                    if client
+                        .blockchain
+                        .find_transaction_by_hash(hash.clone())
+                        .await?
+                        .data
+                        .is_some()
+                    {
+                        client.output.print_with(
+                            &json!({ "hash": hash, "status": "confirmed" }),
+                            format!("{hash}: confirmed"),
+                        )?;
+                        continue;
+                    }
+                    if block_number >= tx.validity_start_height.saturating_add(validity_window) {
+                        client.output.print_with(
+                            &json!({ "hash": hash, "status": "expired" }),
+                            format!("{hash}: expired"),
+                        )?;
+                        continue;
+                    }
+                    if client
+                        .mempool
+                        .find_transaction_in_mempool(hash.clone())
+                        .await?
+                        .data
+                        .is_some()
+                    {
+                        client.output.print_with(
+                            &json!({ "hash": hash, "status": "pending" }),
+                            format!("{hash}: still pending"),
+                        )?;
+                    } else {
+                        match client.consensus.send_raw_transaction(raw_tx.clone()).await {
+                            Ok(_) => client.output.print_with(
+                                &json!({ "hash": hash, "status": "resubmitted" }),
+                                format!("{hash}: resubmitted"),
+                            )?,
+                            Err(error) => client.output.print_with(
+                                &json!({ "hash": hash, "status": "failed", "error": error.to_string() }),
+                                format!("{hash}: failed to resubmit: {error}"),
+                            )?,
+                        }
+                    }
+                    remaining.push(raw_tx);
+                }
+                store.replace(&sender_wallet, &remaining)?;
+            }
             TransactionCommand::NewStaker {
                 sender_wallet,
                 staker_wallet,
                 delegation,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_new_staker_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            delegation,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_new_staker_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            delegation,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let staker_wallet = staker_wallet.clone();
+                        let delegation = delegation.clone();
+                        async move {
+                            consensus
+                                .create_new_staker_transaction(
+                                    sender_wallet,
+                                    staker_wallet,
+                                    delegation,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::AddStake {
@@ -414,30 +1391,32 @@ impl HandleSubcommand for TransactionCommand {
                 staker_address,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_stake_transaction(
-                            sender_wallet,
-                            staker_address,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_stake_transaction(
-                            sender_wallet,
-                            staker_address,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let staker_address = staker_address.clone();
+                        async move {
+                            consensus
+                                .create_stake_transaction(
+                                    sender_wallet,
+                                    staker_address,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::UpdateStaker {
@@ -447,32 +1426,30 @@ impl HandleSubcommand for TransactionCommand {
                 reactivate_all_stake,
                 tx_commons,
             } => {
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_update_staker_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            new_delegation,
-                            reactivate_all_stake,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_update_staker_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            new_delegation,
-                            reactivate_all_stake,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let staker_wallet = staker_wallet.clone();
+                        let new_delegation = new_delegation.clone();
+                        async move {
+                            consensus
+                                .create_update_staker_transaction(
+                                    sender_wallet,
+                                    staker_wallet,
+                                    new_delegation,
+                                    reactivate_all_stake,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) = send_transaction(&mut client, tx, &tx_commons).await? {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
             TransactionCommand::SetActiveStake {
@@ -481,30 +1458,28 @@ impl HandleSubcommand for TransactionCommand {
                 new_active_balance,
                 tx_commons,
             } => {
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_set_active_stake_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            new_active_balance,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_set_active_stake_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            new_active_balance,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let staker_wallet = staker_wallet.clone();
+                        async move {
+                            consensus
+                                .create_set_active_stake_transaction(
+                                    sender_wallet,
+                                    staker_wallet,
+                                    new_active_balance,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) = send_transaction(&mut client, tx, &tx_commons).await? {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
             TransactionCommand::RetireStake {
@@ -514,30 +1489,28 @@ impl HandleSubcommand for TransactionCommand {
                 tx_commons,
             } => {
                 eprintln! {"a {:?}\n{:?}\n{:?}\n{:?}",sender_wallet,staker_wallet,retire_stake,tx_commons};
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_retire_stake_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            retire_stake,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_retire_stake_transaction(
-                            sender_wallet,
-                            staker_wallet,
-                            retire_stake,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let staker_wallet = staker_wallet.clone();
+                        async move {
+                            consensus
+                                .create_retire_stake_transaction(
+                                    sender_wallet,
+                                    staker_wallet,
+                                    retire_stake,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) = send_transaction(&mut client, tx, &tx_commons).await? {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
             TransactionCommand::RemoveStake {
@@ -545,30 +1518,32 @@ impl HandleSubcommand for TransactionCommand {
                 recipient,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_remove_stake_transaction(
-                            staker_wallet,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_remove_stake_transaction(
-                            staker_wallet,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let staker_wallet = staker_wallet.clone();
+                        let recipient = recipient.clone();
+                        async move {
+                            consensus
+                                .create_remove_stake_transaction(
+                                    staker_wallet,
+                                    recipient,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
 
@@ -580,36 +1555,35 @@ impl HandleSubcommand for TransactionCommand {
                 num_steps,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_new_vesting_transaction(
-                            sender_wallet,
-                            owner,
-                            start_time,
-                            time_step,
-                            num_steps,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_new_vesting_transaction(
-                            sender_wallet,
-                            owner,
-                            start_time,
-                            time_step,
-                            num_steps,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let owner = owner.clone();
+                        async move {
+                            consensus
+                                .create_new_vesting_transaction(
+                                    sender_wallet,
+                                    owner,
+                                    start_time,
+                                    time_step,
+                                    num_steps,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::VestingRedeem {
@@ -618,32 +1592,34 @@ impl HandleSubcommand for TransactionCommand {
                 recipient,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_redeem_vesting_transaction(
-                            sender_wallet,
-                            contract_address,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_redeem_vesting_transaction(
-                            sender_wallet,
-                            contract_address,
-                            recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let contract_address = contract_address.clone();
+                        let recipient = recipient.clone();
+                        async move {
+                            consensus
+                                .create_redeem_vesting_transaction(
+                                    sender_wallet,
+                                    contract_address,
+                                    recipient,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
 
@@ -657,38 +1633,39 @@ impl HandleSubcommand for TransactionCommand {
                 timeout,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_new_htlc_transaction(
-                            sender_wallet,
-                            htlc_sender,
-                            htlc_recipient,
-                            Self::parse_hash(&hash_algorithm, hash_root)?,
-                            hash_count,
-                            timeout,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_new_htlc_transaction(
-                            sender_wallet,
-                            htlc_sender,
-                            htlc_recipient,
-                            Self::parse_hash(&hash_algorithm, hash_root)?,
-                            hash_count,
-                            timeout,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let hash_root = Self::parse_hash(&hash_algorithm, hash_root)?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let htlc_sender = htlc_sender.clone();
+                        let htlc_recipient = htlc_recipient.clone();
+                        let hash_root = hash_root.clone();
+                        async move {
+                            consensus
+                                .create_new_htlc_transaction(
+                                    sender_wallet,
+                                    htlc_sender,
+                                    htlc_recipient,
+                                    hash_root,
+                                    hash_count,
+                                    timeout,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::RedeemRegularHTLC {
@@ -701,38 +1678,40 @@ impl HandleSubcommand for TransactionCommand {
                 hash_algorithm,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_redeem_regular_htlc_transaction(
-                            sender_wallet,
-                            contract_address,
-                            htlc_recipient,
-                            pre_image,
-                            Self::parse_hash(&hash_algorithm, hash_root)?,
-                            hash_count,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_redeem_regular_htlc_transaction(
-                            sender_wallet,
-                            contract_address,
-                            htlc_recipient,
-                            pre_image,
-                            Self::parse_hash(&hash_algorithm, hash_root)?,
-                            hash_count,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let hash_root = Self::parse_hash(&hash_algorithm, hash_root)?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let contract_address = contract_address.clone();
+                        let htlc_recipient = htlc_recipient.clone();
+                        let pre_image = pre_image.clone();
+                        let hash_root = hash_root.clone();
+                        async move {
+                            consensus
+                                .create_redeem_regular_htlc_transaction(
+                                    sender_wallet,
+                                    contract_address,
+                                    htlc_recipient,
+                                    pre_image,
+                                    hash_root,
+                                    hash_count,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::RedeemHTLCTimeout {
@@ -741,32 +1720,34 @@ impl HandleSubcommand for TransactionCommand {
                 htlc_recipient,
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_redeem_timeout_htlc_transaction(
-                            sender_wallet,
-                            contract_address,
-                            htlc_recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_redeem_timeout_htlc_transaction(
-                            sender_wallet,
-                            contract_address,
-                            htlc_recipient,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let contract_address = contract_address.clone();
+                        let htlc_recipient = htlc_recipient.clone();
+                        async move {
+                            consensus
+                                .create_redeem_timeout_htlc_transaction(
+                                    sender_wallet,
+                                    contract_address,
+                                    htlc_recipient,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::RedeemHTLCEarly {
@@ -777,34 +1758,36 @@ impl HandleSubcommand for TransactionCommand {
 
                 tx_commons,
             } => {
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_redeem_early_htlc_transaction(
-                            contract_address,
-                            htlc_recipient,
-                            htlc_sender_signature,
-                            htlc_recipient_signature,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_redeem_early_htlc_transaction(
-                            contract_address,
-                            htlc_recipient,
-                            htlc_sender_signature,
-                            htlc_recipient_signature,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let contract_address = contract_address.clone();
+                        let htlc_recipient = htlc_recipient.clone();
+                        let htlc_sender_signature = htlc_sender_signature.clone();
+                        let htlc_recipient_signature = htlc_recipient_signature.clone();
+                        async move {
+                            consensus
+                                .create_redeem_early_htlc_transaction(
+                                    contract_address,
+                                    htlc_recipient,
+                                    htlc_sender_signature,
+                                    htlc_recipient_signature,
+                                    tx_commons.value,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
             TransactionCommand::SignRedeemHTLCEarly {
@@ -815,6 +1798,8 @@ impl HandleSubcommand for TransactionCommand {
                 fee,
                 validity_start_height,
             } => {
+                let validity_start_height =
+                    client.validity_start_height(validity_start_height).await?;
                 let tx = client
                     .consensus
                     .sign_redeem_early_htlc_transaction(
@@ -826,9 +1811,76 @@ impl HandleSubcommand for TransactionCommand {
                         validity_start_height,
                     )
                     .await?;
-                println!("{tx:#?}");
+                client.output.print(&tx)?;
             }
         }
         Ok(client)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nimiq_hash::Hasher;
+
+    use super::*;
+
+    #[test]
+    fn estimates_inclusion_only_for_dry_runs() {
+        let address = Address::default().to_user_friendly_address();
+        let parse = |flags: &[&str]| {
+            let args = [
+                "transaction",
+                "basic",
+                address.as_str(),
+                address.as_str(),
+                "1",
+            ];
+            TransactionCommand::try_parse_from(args.iter().chain(flags))
+        };
+
+        assert!(parse(&["--dry", "--estimate-inclusion"]).is_ok());
+        assert!(parse(&["--dry-verbose", "--estimate-inclusion"]).is_ok());
+        assert!(parse(&["--estimate-inclusion"]).is_err());
+    }
+
+    #[test]
+    fn hashes_htlc_pre_image_repeatedly() {
+        let pre_image = PreImage::from(Blake2bHash::from([1u8; 32]));
+        let once = Sha256Hasher::default().digest(pre_image.as_bytes());
+        let twice = Sha256Hasher::default().digest(once.as_bytes());
+
+        assert_eq!(
+            htlc_hash_root(&pre_image, 2, &HashAlgorithm::Sha256),
+            PreImage::from(twice)
+        );
+        assert!(matches!(
+            htlc_hash_root(&pre_image, 1, &HashAlgorithm::Sha512),
+            PreImage::PreImage64(_)
+        ));
+    }
+
+    #[test]
+    fn verifies_htlc_pre_image() {
+        let output = Output::default();
+        let pre_image = PreImage::from(Blake2bHash::from([1u8; 32]));
+        let hash_root = htlc_hash_root(&pre_image, 3, &HashAlgorithm::Blake2b).to_hex();
+
+        assert!(verify_htlc(
+            &pre_image,
+            hash_root.clone(),
+            3,
+            &HashAlgorithm::Blake2b,
+            &output
+        )
+        .is_ok());
+        assert!(verify_htlc(
+            &pre_image,
+            hash_root.clone(),
+            2,
+            &HashAlgorithm::Blake2b,
+            &output
+        )
+        .is_err());
+        assert!(verify_htlc(&pre_image, hash_root, 3, &HashAlgorithm::Sha256, &output).is_err());
+    }
+}
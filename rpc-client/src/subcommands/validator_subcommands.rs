@@ -1,14 +1,72 @@
-use anyhow::Error;
+use std::{fs, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Error};
 use async_trait::async_trait;
 use clap::Parser;
-use nimiq_keys::Address;
-use nimiq_rpc_interface::{consensus::ConsensusInterface, validator::ValidatorInterface};
+use futures::StreamExt;
+use nimiq_account::Log;
+use nimiq_hash::Blake2bHash;
+use nimiq_keys::{Address, Ed25519PublicKey, KeyPair, PrivateKey};
+use nimiq_primitives::coin::Coin;
+use nimiq_rpc_interface::{
+    blockchain::BlockchainInterface,
+    consensus::ConsensusInterface,
+    policy::PolicyInterface,
+    types::{BlockLog, RPCData},
+    validator::ValidatorInterface,
+    wallet::{WalletInterface, WalletProxy},
+};
+use nimiq_serde::Serialize;
+use nimiq_transaction_builder::{Recipient, Sender, TransactionBuilder, TransactionProofBuilder};
+use serde_json::json;
 
 use super::{
     accounts_subcommands::HandleSubcommand,
-    transactions_subcommands::{TxCommon, TxCommonWithValue},
+    transactions_subcommands::{
+        send_transaction, send_transaction_idempotent, wait_for_confirmations, TxCommon,
+        TxCommonWithValue,
+    },
 };
-use crate::Client;
+use crate::{block_window::BlockWindow, error::input_error, Client};
+
+/// Number of recently applied blocks `follow-my-validator` remembers, such that blocks delivered
+/// again after reconnecting are skipped.
+const FOLLOW_REVERT_WINDOW: usize = 100;
+
+/// The signal data of a validator, given as 32 bytes in hex with an optional `0x` prefix. The
+/// empty string stands for no signal data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignalData(Option<Blake2bHash>);
+
+impl SignalData {
+    /// Encodes the signal data as expected by the RPC server: the hex without prefix, or the
+    /// empty string for no signal data.
+    fn to_rpc_string(&self) -> String {
+        self.0.as_ref().map(Blake2bHash::to_hex).unwrap_or_default()
+    }
+}
+
+impl FromStr for SignalData {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let hex_data = s.strip_prefix("0x").unwrap_or(s);
+        if hex_data.is_empty() {
+            return Ok(Self(None));
+        }
+
+        let bytes = hex::decode(hex_data)
+            .map_err(|error| anyhow!("Invalid signal data `{s}`, expected hex: {error}"))?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            anyhow!(
+                "Invalid signal data `{s}`, expected 32 bytes but got {}",
+                bytes.len()
+            )
+        })?;
+        Ok(Self(Some(Blake2bHash::from(bytes))))
+    }
+}
 
 #[derive(Debug, Parser)]
 pub enum ValidatorCommand {
@@ -28,6 +86,45 @@ pub enum ValidatorCommand {
     /// Returns the voting key of the local validator.
     ValidatorVotingKey {},
 
+    /// Follows the logs related to the local validator and its reward address and prints a
+    /// readable line for each of them, e.g. received rewards, penalties for missed slots, jailing
+    /// and changes of the validator state. In JSON mode the raw logs are printed.
+    /// Elections and produced blocks don't emit logs, use `follow-validator` to follow elections.
+    FollowMyValidator {},
+
+    /// Prints metrics of a validator and the chain in the Prometheus text exposition format.
+    /// This is meant to be run once per scrape, e.g. by writing the output into the directory of
+    /// the node exporter's textfile collector.
+    Metrics {
+        /// The validator to export metrics for. Defaults to the local validator.
+        #[clap(long)]
+        validator: Option<Address>,
+    },
+
+    /// Shows a summary of the state of a validator: whether it is active, inactive, jailed or
+    /// retired, whether it is elected, its stake, number of stakers and reward address, and which
+    /// action is needed to get it back to producing blocks, if any.
+    ValidatorStatus {
+        /// The validator to show the status of. Defaults to the local validator.
+        address: Option<Address>,
+    },
+
+    /// Lists the stakers delegating to a validator together with their active stake, sorted by
+    /// stake, largest first.
+    /// IMPORTANT: This is a very expensive operation, iterating over all existing stakers in the contract.
+    ValidatorStakers {
+        /// The validator to list the stakers of. Defaults to the local validator.
+        address: Option<Address>,
+
+        /// Lists at most this many stakers.
+        #[clap(short, long)]
+        limit: Option<usize>,
+
+        /// Only prints the total stake and number of stakers.
+        #[clap(long, conflicts_with = "limit")]
+        total: bool,
+    },
+
     /// Sends a `new_validator` transaction to the network. You need to provide the address of a basic
     /// account (the sender wallet) to pay the transaction fee and the validator deposit. The sender wallet must be unlocked
     /// prior to this command.
@@ -54,8 +151,8 @@ pub enum ValidatorCommand {
         /// The address to which the staking rewards are sent.
         reward_address: Address,
 
-        /// The signal data showed by the validator.
-        signal_data: String,
+        /// The signal data showed by the validator, 32 bytes in hex. Empty for no signal data.
+        signal_data: SignalData,
 
         #[clap(flatten)]
         tx_commons: TxCommon,
@@ -84,9 +181,10 @@ pub enum ValidatorCommand {
         #[clap(long)]
         new_reward_address: Option<Address>,
 
-        /// The new signal data showed by the validator.
+        /// The new signal data showed by the validator, 32 bytes in hex. Empty to remove the
+        /// signal data.
         #[clap(short = 'd', long)]
-        new_signal_data: Option<String>,
+        new_signal_data: Option<SignalData>,
 
         #[clap(flatten)]
         tx_commons: TxCommon,
@@ -100,6 +198,12 @@ pub enum ValidatorCommand {
         /// The fee will be paid from this address. This wallet must be already unlocked.
         sender_wallet: Address,
 
+        /// Reads the signing key of the validator from this file instead of fetching it from the
+        /// node. The file must contain the hex encoded private key. The staking proof is signed
+        /// locally, the key is never sent to the node.
+        #[clap(long)]
+        signing_key_file: Option<PathBuf>,
+
         #[clap(flatten)]
         tx_commons: TxCommon,
     },
@@ -111,6 +215,12 @@ pub enum ValidatorCommand {
         /// The fee will be paid from this address. This wallet must be already unlocked.
         sender_wallet: Address,
 
+        /// Reads the signing key of the validator from this file instead of fetching it from the
+        /// node. The file must contain the hex encoded private key. The staking proof is signed
+        /// locally, the key is never sent to the node.
+        #[clap(long)]
+        signing_key_file: Option<PathBuf>,
+
         #[clap(flatten)]
         tx_commons: TxCommon,
     },
@@ -131,15 +241,21 @@ impl HandleSubcommand for ValidatorCommand {
     async fn handle_subcommand(self, mut client: Client) -> Result<Client, Error> {
         match self {
             ValidatorCommand::ValidatorAddress {} => {
-                println!("{:#?}", client.validator.get_address().await?);
+                client
+                    .output
+                    .print(&client.validator.get_address().await?)?;
             }
 
             ValidatorCommand::ValidatorSigningKey {} => {
-                println!("{:#?}", client.validator.get_signing_key().await?);
+                client
+                    .output
+                    .print(&client.validator.get_signing_key().await?)?;
             }
 
             ValidatorCommand::ValidatorVotingKey {} => {
-                println!("{:#?}", client.validator.get_voting_key().await?);
+                client
+                    .output
+                    .print(&client.validator.get_voting_key().await?)?;
             }
 
             ValidatorCommand::SetAutoReactivateValidator {
@@ -149,7 +265,291 @@ impl HandleSubcommand for ValidatorCommand {
                     .validator
                     .set_automatic_reactivation(automatic_reactivate)
                     .await?;
-                println!("Auto reactivate set to {automatic_reactivate}");
+                client.output.print_with(
+                    &automatic_reactivate,
+                    format!("Auto reactivate set to {automatic_reactivate}"),
+                )?;
+            }
+
+            ValidatorCommand::FollowMyValidator {} => {
+                let validator_address = client.validator.get_address().await?.data;
+                let reward_address = client
+                    .blockchain
+                    .get_validator_by_address(validator_address.clone())
+                    .await?
+                    .data
+                    .reward_address;
+                let staking_contract = Address::from_any_str(
+                    &client
+                        .policy
+                        .get_policy_constants()
+                        .await?
+                        .data
+                        .staking_contract_address,
+                )?;
+
+                let _subscription = client.acquire_subscription()?;
+                let mut window = BlockWindow::new(FOLLOW_REVERT_WINDOW);
+                loop {
+                    let mut stream = client
+                        .blockchain
+                        .subscribe_for_logs_by_addresses_and_types(
+                            vec![
+                                validator_address.clone(),
+                                reward_address.clone(),
+                                staking_contract.clone(),
+                            ],
+                            vec![],
+                        )
+                        .await?;
+
+                    while let Some(block_log) = stream.next().await {
+                        let block_number = block_log.metadata.block_number;
+                        let block_hash = &block_log.metadata.block_hash;
+                        let (reverted, inherent_logs, tx_logs) = match block_log.data {
+                            BlockLog::AppliedBlock {
+                                inherent_logs,
+                                tx_logs,
+                                ..
+                            } => {
+                                // Skip blocks delivered again after reconnecting.
+                                if !window.apply(block_hash) {
+                                    continue;
+                                }
+                                (false, inherent_logs, tx_logs)
+                            }
+                            BlockLog::RevertedBlock {
+                                inherent_logs,
+                                tx_logs,
+                            } => {
+                                window.revert(block_hash);
+                                (true, inherent_logs, tx_logs)
+                            }
+                        };
+
+                        let logs = inherent_logs
+                            .into_iter()
+                            .chain(tx_logs.into_iter().flat_map(|tx_log| tx_log.logs));
+                        for log in logs {
+                            // The staking contract logs concern all validators and stakers.
+                            if !log.is_related_to_address(&validator_address)
+                                && !log.is_related_to_address(&reward_address)
+                            {
+                                continue;
+                            }
+                            let mut line = format!(
+                                "#{block_number}: {}",
+                                describe_validator_log(&log, &validator_address, &reward_address)
+                            );
+                            if reverted {
+                                line += " (reverted)";
+                            }
+                            client.output.print_item_with(
+                                &json!({ "blockNumber": block_number, "reverted": reverted, "log": log }),
+                                line,
+                            )?;
+                        }
+                    }
+
+                    // The subscription ended, which means that the connection was lost.
+                    if !client.reconnect().await? {
+                        break;
+                    }
+                }
+            }
+
+            ValidatorCommand::Metrics { validator } => {
+                let address = match validator {
+                    Some(address) => address,
+                    None => client.validator.get_address().await?.data,
+                };
+                let validator = client
+                    .blockchain
+                    .get_validator_by_address(address.clone())
+                    .await?;
+                let head_block = validator.metadata.block_number;
+                let validator = validator.data;
+                let elected = client
+                    .blockchain
+                    .get_active_validators()
+                    .await?
+                    .data
+                    .iter()
+                    .any(|active| active.address == address);
+                let active = !validator.retired
+                    && validator.jailed_from.is_none()
+                    && validator.inactivity_flag.is_none();
+
+                let label = format!("{{address=\"{}\"}}", address.to_user_friendly_address());
+                let metrics = [
+                    (
+                        "nimiq_head_block",
+                        "Block number of the current head.",
+                        String::new(),
+                        u64::from(head_block),
+                    ),
+                    (
+                        "nimiq_validator_stake",
+                        "Total stake of the validator in Luna.",
+                        label.clone(),
+                        u64::from(validator.balance),
+                    ),
+                    (
+                        "nimiq_validator_num_stakers",
+                        "Number of stakers delegating to the validator.",
+                        label.clone(),
+                        validator.num_stakers,
+                    ),
+                    (
+                        "nimiq_validator_active",
+                        "Whether the validator is neither inactive, jailed nor retired.",
+                        label.clone(),
+                        u64::from(active),
+                    ),
+                    (
+                        "nimiq_validator_elected",
+                        "Whether the validator is part of the current validator set.",
+                        label.clone(),
+                        u64::from(elected),
+                    ),
+                    (
+                        "nimiq_validator_jailed",
+                        "Whether the validator is jailed.",
+                        label.clone(),
+                        u64::from(validator.jailed_from.is_some()),
+                    ),
+                    (
+                        "nimiq_validator_retired",
+                        "Whether the validator is retired.",
+                        label,
+                        u64::from(validator.retired),
+                    ),
+                ];
+
+                let mut text = String::new();
+                for (name, help, labels, value) in &metrics {
+                    text += &format!(
+                        "# HELP {name} {help}\n# TYPE {name} gauge\n{name}{labels} {value}\n"
+                    );
+                }
+                let result: serde_json::Map<_, _> = metrics
+                    .iter()
+                    .map(|(name, _, _, value)| (name.to_string(), json!(value)))
+                    .collect();
+                client.output.print_with(&result, text.trim_end())?;
+            }
+
+            ValidatorCommand::ValidatorStatus { address } => {
+                let address = match address {
+                    Some(address) => address,
+                    None => client.validator.get_address().await?.data,
+                };
+                let validator = client
+                    .blockchain
+                    .get_validator_by_address(address.clone())
+                    .await?
+                    .data;
+                let elected = client
+                    .blockchain
+                    .get_active_validators()
+                    .await?
+                    .data
+                    .iter()
+                    .any(|active| active.address == address);
+
+                let (state, action) = if validator.retired {
+                    (
+                        "retired".to_string(),
+                        Some("none possible, the stake can be withdrawn by deleting the validator"),
+                    )
+                } else if let Some(jailed_from) = validator.jailed_from {
+                    (
+                        format!("jailed since block #{jailed_from}"),
+                        Some("wait for the jail to be released, then reactivate the validator"),
+                    )
+                } else if let Some(inactive_from) = validator.inactivity_flag {
+                    (
+                        format!("inactive since block #{inactive_from}"),
+                        Some("reactivate the validator (reactivate-validator)"),
+                    )
+                } else {
+                    ("active".to_string(), None)
+                };
+
+                let text = format!(
+                    "Validator {}\n\
+                     State:          {state}\n\
+                     Elected:        {elected}\n\
+                     Stake:          {}\n\
+                     Stakers:        {}\n\
+                     Reward address: {}\n\
+                     Action needed:  {}",
+                    address.to_user_friendly_address(),
+                    validator.balance,
+                    validator.num_stakers,
+                    validator.reward_address.to_user_friendly_address(),
+                    action.unwrap_or("none"),
+                );
+                client.output.print_with(
+                    &json!({
+                        "address": address,
+                        "state": state,
+                        "elected": elected,
+                        "stake": validator.balance,
+                        "numStakers": validator.num_stakers,
+                        "rewardAddress": validator.reward_address,
+                        "actionNeeded": action,
+                    }),
+                    text,
+                )?;
+            }
+
+            ValidatorCommand::ValidatorStakers {
+                address,
+                limit,
+                total,
+            } => {
+                let address = match address {
+                    Some(address) => address,
+                    None => client.validator.get_address().await?.data,
+                };
+                let mut stakers = client
+                    .blockchain
+                    .get_stakers_by_validator_address(address.clone())
+                    .await?
+                    .data;
+
+                if total {
+                    let total_stake = Coin::from_u64_unchecked(
+                        stakers.iter().map(|staker| u64::from(staker.balance)).sum(),
+                    );
+                    client.output.print_with(
+                        &json!({
+                            "validator": address,
+                            "totalStake": total_stake,
+                            "numStakers": stakers.len(),
+                        }),
+                        format!(
+                            "{} stakers with a total stake of {total_stake} NIM",
+                            stakers.len()
+                        ),
+                    )?;
+                    return Ok(client);
+                }
+
+                stakers.sort_by(|a, b| b.balance.cmp(&a.balance));
+                if let Some(limit) = limit {
+                    stakers.truncate(limit);
+                }
+                let mut text = format!("{:<44}  {:>20}", "staker", "stake");
+                for staker in &stakers {
+                    text += &format!(
+                        "\n{:<44}  {:>20}",
+                        staker.address.to_user_friendly_address(),
+                        staker.balance.to_string(),
+                    );
+                }
+                client.output.print_with(&stakers, text)?;
             }
 
             ValidatorCommand::CreateNewValidator {
@@ -161,36 +561,35 @@ impl HandleSubcommand for ValidatorCommand {
                 signal_data,
                 tx_commons,
             } => {
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_new_validator_transaction(
-                            sender_wallet,
-                            validator_wallet,
-                            signing_secret_key,
-                            voting_secret_key,
-                            reward_address,
-                            signal_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_new_validator_transaction(
-                            sender_wallet,
-                            validator_wallet,
-                            signing_secret_key,
-                            voting_secret_key,
-                            reward_address,
-                            signal_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let validator_wallet = validator_wallet.clone();
+                        let signing_secret_key = signing_secret_key.clone();
+                        let voting_secret_key = voting_secret_key.clone();
+                        let reward_address = reward_address.clone();
+                        let signal_data = signal_data.to_rpc_string();
+                        async move {
+                            consensus
+                                .create_new_validator_transaction(
+                                    sender_wallet,
+                                    validator_wallet,
+                                    signing_secret_key,
+                                    voting_secret_key,
+                                    reward_address,
+                                    signal_data,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) = send_transaction(&mut client, tx, &tx_commons).await? {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
 
@@ -203,102 +602,138 @@ impl HandleSubcommand for ValidatorCommand {
                 tx_commons,
             } => {
                 let validator_address = client.validator.get_address().await?.data;
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_update_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            new_signing_secret_key,
-                            new_voting_secret_key,
-                            new_reward_address,
-                            new_signal_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_update_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            new_signing_secret_key,
-                            new_voting_secret_key,
-                            new_reward_address,
-                            new_signal_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let sender_wallet = sender_wallet.clone();
+                        let validator_address = validator_address.clone();
+                        let new_signing_secret_key = new_signing_secret_key.clone();
+                        let new_voting_secret_key = new_voting_secret_key.clone();
+                        let new_reward_address = new_reward_address.clone();
+                        let new_signal_data =
+                            new_signal_data.as_ref().map(SignalData::to_rpc_string);
+                        async move {
+                            consensus
+                                .create_update_validator_transaction(
+                                    sender_wallet,
+                                    validator_address,
+                                    new_signing_secret_key,
+                                    new_voting_secret_key,
+                                    new_reward_address,
+                                    new_signal_data,
+                                    fee,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) = send_transaction(&mut client, tx, &tx_commons).await? {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
 
             ValidatorCommand::DeactivateValidator {
                 sender_wallet,
+                signing_key_file,
                 tx_commons,
             } => {
                 let validator_address = client.validator.get_address().await?.data;
-                let key_data = client.validator.get_signing_key().await?.data;
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_deactivate_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            key_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_deactivate_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            key_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let tx =
+                    match signing_key(&mut client, &validator_address, signing_key_file).await? {
+                        SigningKey::Node(key_data) => {
+                            let validity_start_height = client
+                                .validity_start_height(tx_commons.validity_start_height)
+                                .await?;
+                            tx_commons
+                                .create_transaction(&client, |mut consensus, fee| {
+                                    let sender_wallet = sender_wallet.clone();
+                                    let validator_address = validator_address.clone();
+                                    let key_data = key_data.clone();
+                                    async move {
+                                        consensus
+                                            .create_deactivate_validator_transaction(
+                                                sender_wallet,
+                                                validator_address,
+                                                key_data,
+                                                fee,
+                                                validity_start_height,
+                                            )
+                                            .await
+                                    }
+                                })
+                                .await?
+                        }
+                        SigningKey::Local(key_pair) => {
+                            let mut recipient = Recipient::new_staking_builder();
+                            recipient.deactivate_validator(validator_address);
+                            create_locally_signed_transaction(
+                                &mut client,
+                                &tx_commons,
+                                sender_wallet,
+                                recipient.generate().expect("staking recipient is complete"),
+                                &key_pair,
+                            )
+                            .await?
+                        }
+                    };
+                if let Some(txid) =
+                    send_transaction_idempotent(&mut client, tx, &tx_commons).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
 
             ValidatorCommand::ReactivateValidator {
                 sender_wallet,
+                signing_key_file,
                 tx_commons,
             } => {
                 let validator_address = client.validator.get_address().await?.data;
-                let key_data = client.validator.get_signing_key().await?.data;
-                if tx_commons.dry {
-                    let tx = client
-                        .consensus
-                        .create_reactivate_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            key_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_reactivate_validator_transaction(
-                            sender_wallet,
-                            validator_address,
-                            key_data,
-                            tx_commons.fee,
-                            tx_commons.validity_start_height,
-                        )
-                        .await?;
-                    println!("{txid:#?}");
+                let tx =
+                    match signing_key(&mut client, &validator_address, signing_key_file).await? {
+                        SigningKey::Node(key_data) => {
+                            let validity_start_height = client
+                                .validity_start_height(tx_commons.validity_start_height)
+                                .await?;
+                            tx_commons
+                                .create_transaction(&client, |mut consensus, fee| {
+                                    let sender_wallet = sender_wallet.clone();
+                                    let validator_address = validator_address.clone();
+                                    let key_data = key_data.clone();
+                                    async move {
+                                        consensus
+                                            .create_reactivate_validator_transaction(
+                                                sender_wallet,
+                                                validator_address,
+                                                key_data,
+                                                fee,
+                                                validity_start_height,
+                                            )
+                                            .await
+                                    }
+                                })
+                                .await?
+                        }
+                        SigningKey::Local(key_pair) => {
+                            let mut recipient = Recipient::new_staking_builder();
+                            recipient.reactivate_validator(validator_address);
+                            create_locally_signed_transaction(
+                                &mut client,
+                                &tx_commons,
+                                sender_wallet,
+                                recipient.generate().expect("staking recipient is complete"),
+                                &key_pair,
+                            )
+                            .await?
+                        }
+                    };
+                if let Some(txid) =
+                    send_transaction_idempotent(&mut client, tx, &tx_commons).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons).await?;
                 }
             }
 
@@ -307,33 +742,210 @@ impl HandleSubcommand for ValidatorCommand {
                 tx_commons,
             } => {
                 let validator_address = client.validator.get_address().await?.data;
-                if tx_commons.common_tx_fields.dry {
-                    let tx = client
-                        .consensus
-                        .create_delete_validator_transaction(
-                            validator_address,
-                            recipient_address,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
-                        .await?;
-                    println!("{tx:#?}");
-                } else {
-                    let txid = client
-                        .consensus
-                        .send_delete_validator_transaction(
-                            validator_address,
-                            recipient_address,
-                            tx_commons.common_tx_fields.fee,
-                            tx_commons.value,
-                            tx_commons.common_tx_fields.validity_start_height,
-                        )
+                let validity_start_height = client
+                    .validity_start_height(tx_commons.common_tx_fields.validity_start_height)
+                    .await?;
+                let tx = tx_commons
+                    .common_tx_fields
+                    .create_transaction(&client, |mut consensus, fee| {
+                        let validator_address = validator_address.clone();
+                        let recipient_address = recipient_address.clone();
+                        async move {
+                            consensus
+                                .create_delete_validator_transaction(
+                                    validator_address,
+                                    recipient_address,
+                                    fee,
+                                    tx_commons.value,
+                                    validity_start_height,
+                                )
+                                .await
+                        }
+                    })
+                    .await?;
+                if let Some(txid) =
+                    send_transaction(&mut client, tx, &tx_commons.common_tx_fields).await?
+                {
+                    wait_for_confirmations(&mut client, &txid, &tx_commons.common_tx_fields)
                         .await?;
-                    println!("{txid:#?}");
                 }
             }
         }
         Ok(client)
     }
 }
+
+/// The signing key of a validator, used to sign the staking proof of (de)activation
+/// transactions.
+enum SigningKey {
+    /// The node holds the key, it is passed back to the node as hex encoded key data.
+    Node(String),
+    /// The key was read from a local file and must never be sent to the node.
+    Local(KeyPair),
+}
+
+/// Returns the signing key of the given validator. The key is either read from
+/// `signing_key_file` and checked against the signing key registered in the staking contract,
+/// or fetched from the node if no file is given.
+async fn signing_key(
+    client: &mut Client,
+    validator_address: &Address,
+    signing_key_file: Option<PathBuf>,
+) -> Result<SigningKey, Error> {
+    let Some(path) = signing_key_file else {
+        return Ok(SigningKey::Node(
+            client.validator.get_signing_key().await?.data,
+        ));
+    };
+
+    let private_key: PrivateKey = fs::read_to_string(&path)
+        .map_err(input_error)?
+        .trim()
+        .parse()
+        .map_err(input_error)?;
+    let registered_key = client
+        .blockchain
+        .get_validator_by_address(validator_address.clone())
+        .await?
+        .data
+        .signing_key;
+    if Ed25519PublicKey::from(&private_key) != registered_key {
+        return Err(input_error(anyhow!(
+            "The key in {} does not match the signing key of validator {validator_address}",
+            path.display()
+        )));
+    }
+    Ok(SigningKey::Local(KeyPair::from(private_key)))
+}
+
+/// Creates a transaction to the staking contract whose staking proof is signed locally with
+/// `signing_key_pair`. Only the unsigned transaction is sent to the node, which adds the proof
+/// of the sender wallet paying the fee.
+async fn create_locally_signed_transaction(
+    client: &mut Client,
+    tx_commons: &TxCommon,
+    sender_wallet: Address,
+    recipient: Recipient,
+    signing_key_pair: &KeyPair,
+) -> Result<RPCData<String, ()>, Error> {
+    let validity_start_height = client
+        .validity_start_height(tx_commons.validity_start_height)
+        .await?;
+    let network_id = client.blockchain.get_latest_block(None).await?.data.network;
+
+    let staking_signed_tx = |fee| -> Result<String, Error> {
+        let mut builder = TransactionBuilder::new();
+        builder
+            .with_sender(Sender::new_basic(sender_wallet.clone()))
+            .with_recipient(recipient.clone())
+            .with_value(Coin::ZERO)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        let TransactionProofBuilder::InStaking(mut proof_builder) = builder.generate()? else {
+            unreachable!("transactions to the staking contract need a staking proof");
+        };
+        proof_builder.sign_with_key_pair(signing_key_pair);
+        let tx = proof_builder
+            .generate()
+            .expect("staking proof is signed")
+            .unwrap_basic()
+            .transaction;
+        Ok(hex::encode(tx.serialize_to_vec()))
+    };
+
+    let client: &Client = client;
+    tx_commons
+        .create_transaction(client, |_consensus, fee| {
+            let raw_tx = staking_signed_tx(fee);
+            let mut wallet = WalletProxy::new(client.rpc_client.clone());
+            async move { Ok::<_, Error>(wallet.sign_transaction(raw_tx?).await?) }
+        })
+        .await
+}
+
+/// Returns a readable description of a log related to a validator or its reward address.
+fn describe_validator_log(
+    log: &Log,
+    validator_address: &Address,
+    reward_address: &Address,
+) -> String {
+    match log {
+        Log::PayoutReward { to, value } if to == reward_address => {
+            format!("Received a reward of {value} NIM")
+        }
+        Log::Penalize {
+            offense_event_block,
+            slot,
+            newly_deactivated,
+            ..
+        } => {
+            let mut description =
+                format!("Penalized for missing slot {slot} in block #{offense_event_block}");
+            if *newly_deactivated {
+                description += ", the validator was deactivated";
+            }
+            description
+        }
+        Log::Jail { event_block, .. } => {
+            format!("Jailed for an offense in block #{event_block}")
+        }
+        Log::JailValidator { jailed_from, .. } => format!("Jailed from block #{jailed_from}"),
+        Log::DeactivateValidator { inactive_from, .. } => {
+            format!("Deactivated, inactive from block #{inactive_from}")
+        }
+        Log::ReactivateValidator { .. } => "Reactivated".to_string(),
+        Log::RetireValidator { .. } => "Retired".to_string(),
+        Log::UpdateValidator { .. } => "Validator updated".to_string(),
+        Log::ValidatorFeeDeduction { fee, .. } => format!("Paid a fee of {fee} NIM"),
+        Log::Stake {
+            staker_address,
+            value,
+            ..
+        }
+        | Log::CreateStaker {
+            staker_address,
+            value,
+            ..
+        } => format!("Staker {staker_address} added a stake of {value} NIM"),
+        Log::UpdateStaker {
+            staker_address,
+            new_validator_address,
+            ..
+        } => {
+            if new_validator_address.as_ref() == Some(validator_address) {
+                format!("Staker {staker_address} delegated to the validator")
+            } else {
+                format!("Staker {staker_address} removed its delegation from the validator")
+            }
+        }
+        Log::Transfer {
+            from, to, amount, ..
+        } => format!("Transfer of {amount} NIM from {from} to {to}"),
+        log => format!("{log:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signal_data() {
+        let hash = Blake2bHash::from([0x29; 32]);
+        assert_eq!("".parse::<SignalData>().unwrap(), SignalData(None));
+        assert_eq!("0x".parse::<SignalData>().unwrap(), SignalData(None));
+        assert_eq!(
+            format!("0x{}", hash.to_hex())
+                .parse::<SignalData>()
+                .unwrap(),
+            SignalData(Some(hash.clone()))
+        );
+        assert_eq!(
+            hash.to_hex().parse::<SignalData>().unwrap().to_rpc_string(),
+            hash.to_hex()
+        );
+        assert!("0x29a4".parse::<SignalData>().is_err());
+        assert!("xyz".parse::<SignalData>().is_err());
+    }
+}
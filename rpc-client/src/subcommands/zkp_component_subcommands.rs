@@ -17,7 +17,9 @@ impl HandleSubcommand for ZKPComponentCommand {
     async fn handle_subcommand(self, mut client: Client) -> Result<Client, Error> {
         match self {
             ZKPComponentCommand::ZkpState {} => {
-                println!("{:?}", client.zkp_component.get_zkp_state().await?);
+                client
+                    .output
+                    .print(&client.zkp_component.get_zkp_state().await?)?;
             }
         }
         Ok(client)
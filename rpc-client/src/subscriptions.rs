@@ -0,0 +1,79 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use anyhow::{bail, Error};
+
+/// Keeps track of the subscriptions a client currently has open and enforces an optional limit on
+/// their number.
+#[derive(Clone, Debug, Default)]
+pub struct SubscriptionTracker {
+    active: Arc<AtomicUsize>,
+    max: Option<usize>,
+}
+
+impl SubscriptionTracker {
+    /// Creates a new tracker allowing at most `max` concurrent subscriptions, or an unlimited
+    /// number if `max` is `None`.
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            active: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    /// Returns the number of currently active subscriptions.
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Registers a new subscription. The returned guard must be kept alive for as long as the
+    /// subscription is in use, dropping it releases the subscription again.
+    pub fn acquire(&self) -> Result<SubscriptionGuard, Error> {
+        let max = self.max.unwrap_or(usize::MAX);
+        let result = self
+            .active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < max).then_some(active + 1)
+            });
+        if result.is_err() {
+            bail!("Maximum number of concurrent subscriptions ({max}) reached");
+        }
+        Ok(SubscriptionGuard {
+            active: Arc::clone(&self.active),
+        })
+    }
+}
+
+/// Guard representing an active subscription. The subscription is released when it is dropped.
+#[derive(Debug)]
+pub struct SubscriptionGuard {
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enforces_the_subscription_limit() {
+        let tracker = SubscriptionTracker::new(Some(2));
+
+        let first = tracker.acquire().unwrap();
+        let _second = tracker.acquire().unwrap();
+        assert_eq!(tracker.active(), 2);
+        assert!(tracker.acquire().is_err());
+
+        drop(first);
+        assert_eq!(tracker.active(), 1);
+        let _third = tracker.acquire().unwrap();
+        assert_eq!(tracker.active(), 2);
+    }
+}
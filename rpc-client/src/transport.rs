@@ -0,0 +1,178 @@
+use std::{fmt::Debug, future::Future, io, time::Duration};
+
+use anyhow::{anyhow, Error};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use nimiq_jsonrpc_client::{
+    http::{self, HttpClient},
+    websocket::{self, WebsocketClient},
+    Client, Credentials,
+};
+use nimiq_jsonrpc_core::{RpcError, SubscriptionId};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::error::input_error;
+
+/// Transport used to talk to the node, chosen by the scheme of its URL.
+pub struct Transport {
+    connection: Connection,
+    /// Requests the node doesn't respond to within this duration fail, if set.
+    timeout: Option<Duration>,
+}
+
+enum Connection {
+    /// A websocket connection for `ws://` and `wss://` URLs. Supports subscriptions.
+    Websocket(WebsocketClient),
+    /// An HTTP client for `http://` and `https://` URLs, e.g. for nodes behind load-balanced HTTP
+    /// front-ends. Doesn't support subscriptions.
+    Http(HttpClient),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Websocket(#[from] websocket::Error),
+    #[error(transparent)]
+    Http(#[from] http::Error),
+    #[error("Request timed out after {}s", .0.as_secs_f32())]
+    Timeout(Duration),
+}
+
+impl TransportError {
+    /// Returns whether the request failed because the node couldn't be reached or didn't respond
+    /// in time, as opposed to the node responding with an error.
+    pub fn is_connection_error(&self) -> bool {
+        let error: &(dyn std::error::Error + 'static) = match self {
+            Self::Websocket(error) => error,
+            Self::Http(error) => error,
+            Self::Timeout(_) => return true,
+        };
+        std::iter::successors(Some(error), |error| error.source())
+            .any(|error| error.is::<io::Error>())
+    }
+
+    /// Returns the error the node responded with, if any.
+    pub fn rpc_error(&self) -> Option<&RpcError> {
+        let error: &(dyn std::error::Error + 'static) = match self {
+            Self::Websocket(error) => error,
+            Self::Http(error) => error,
+            Self::Timeout(_) => return None,
+        };
+        std::iter::successors(Some(error), |error| error.source())
+            .find_map(|error| error.downcast_ref::<RpcError>())
+    }
+}
+
+impl Transport {
+    /// Connects to the node at the given URL with the transport matching its scheme. If a
+    /// `timeout` is given, connecting and each request sent over the connection fail if they
+    /// don't complete within it. Items streamed by subscriptions are not subject to the timeout.
+    pub async fn connect(
+        url: Url,
+        credentials: Option<Credentials>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        let connection = match url.scheme() {
+            "ws" | "wss" => {
+                let connect = WebsocketClient::new(url, credentials);
+                let client = match timeout {
+                    Some(timeout) => {
+                        tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                format!("Connecting timed out after {}s", timeout.as_secs_f32()),
+                            )
+                        })?
+                    }
+                    None => connect.await,
+                };
+                Connection::Websocket(client?)
+            }
+            "http" | "https" => Connection::Http(HttpClient::new(url, credentials)),
+            scheme => {
+                return Err(input_error(anyhow!(
+                    "Unsupported URL scheme `{scheme}`, expected `ws(s)` or `http(s)`"
+                )))
+            }
+        };
+        Ok(Self {
+            connection,
+            timeout,
+        })
+    }
+
+    /// Returns whether the given error returned by [`Self::connect`] is a connection-level error,
+    /// e.g. because the node isn't up yet, such that connecting again may succeed. Errors like
+    /// authentication failures or invalid URLs are not.
+    pub fn is_connection_error(error: &Error) -> bool {
+        error
+            .chain()
+            .any(|cause| cause.downcast_ref::<io::Error>().is_some())
+    }
+
+    /// Returns whether the transport supports subscriptions, i.e. streaming notifications.
+    pub fn supports_subscriptions(&self) -> bool {
+        matches!(self.connection, Connection::Websocket(_))
+    }
+
+    /// Awaits the given request, failing if it doesn't complete within the timeout.
+    async fn with_timeout<T>(
+        &self,
+        request: impl Future<Output = Result<T, TransportError>>,
+    ) -> Result<T, TransportError> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, request)
+                .await
+                .map_err(|_| TransportError::Timeout(timeout))?,
+            None => request.await,
+        }
+    }
+}
+
+#[async_trait]
+impl Client for Transport {
+    type Error = TransportError;
+
+    async fn send_request<P, R>(&self, method: &str, params: &P) -> Result<R, Self::Error>
+    where
+        P: Serialize + Debug + Send + Sync,
+        R: for<'de> Deserialize<'de> + Debug + Send + Sync,
+    {
+        self.with_timeout(async {
+            match &self.connection {
+                Connection::Websocket(client) => Ok(client.send_request(method, params).await?),
+                Connection::Http(client) => Ok(client.send_request(method, params).await?),
+            }
+        })
+        .await
+    }
+
+    async fn connect_stream<T: Unpin + 'static>(&self, id: SubscriptionId) -> BoxStream<'static, T>
+    where
+        T: for<'de> Deserialize<'de> + Debug + Send + Sync,
+    {
+        match &self.connection {
+            Connection::Websocket(client) => client.connect_stream(id).await,
+            // Subscriptions are rejected before subscribing, see `Client::acquire_subscription`.
+            Connection::Http(_) => stream::empty().boxed(),
+        }
+    }
+
+    async fn disconnect_stream(&self, id: SubscriptionId) -> Result<(), Self::Error> {
+        self.with_timeout(async {
+            match &self.connection {
+                Connection::Websocket(client) => Ok(client.disconnect_stream(id).await?),
+                Connection::Http(client) => Ok(client.disconnect_stream(id).await?),
+            }
+        })
+        .await
+    }
+
+    async fn close(&self) {
+        match &self.connection {
+            Connection::Websocket(client) => client.close().await,
+            Connection::Http(client) => client.close().await,
+        }
+    }
+}
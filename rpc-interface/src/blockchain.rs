@@ -62,6 +62,13 @@ pub trait BlockchainInterface {
         hash: Blake2bHash,
     ) -> RPCResult<ExecutedTransaction, (), Self::Error>;
 
+    /// Tries to fetch a transaction (including reward transactions) given its hash. Unlike
+    /// `get_transaction_by_hash`, returns `None` instead of an error if the transaction is not found.
+    async fn find_transaction_by_hash(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> RPCResult<Option<ExecutedTransaction>, (), Self::Error>;
+
     /// Returns all the transactions (including reward transactions) for the given block number. Note
     /// that this only considers blocks in the main chain.
     async fn get_transactions_by_block_number(
@@ -116,6 +123,15 @@ pub trait BlockchainInterface {
         address: Address,
     ) -> RPCResult<Account, BlockchainState, Self::Error>;
 
+    /// Tries to fetch the account at the given address as it was right after the given block was
+    /// applied. Only blocks since the latest macro block are supported, since older account states
+    /// aren't retained.
+    async fn get_account_by_address_at(
+        &mut self,
+        address: Address,
+        block_number: u32,
+    ) -> RPCResult<Account, BlockchainState, Self::Error>;
+
     /// Fetches all accounts in the accounts tree.
     /// IMPORTANT: This operation iterates over all accounts in the accounts tree
     /// and thus is extremely computationally expensive.
@@ -144,6 +160,13 @@ pub trait BlockchainInterface {
         address: Address,
     ) -> RPCResult<Validator, BlockchainState, Self::Error>;
 
+    /// Tries to fetch a validator information given its address. Unlike `get_validator_by_address`,
+    /// returns `None` instead of an error if the validator is not found.
+    async fn find_validator_by_address(
+        &mut self,
+        address: Address,
+    ) -> RPCResult<Option<Validator>, BlockchainState, Self::Error>;
+
     /// Fetches all validators in the staking contract.
     /// IMPORTANT: This operation iterates over all validators in the staking contract
     /// and thus is extremely computationally expensive.
@@ -192,4 +215,12 @@ pub trait BlockchainInterface {
         addresses: Vec<Address>,
         log_types: Vec<LogType>,
     ) -> Result<BoxStream<'static, RPCData<BlockLog, BlockchainState>>, Self::Error>;
+
+    /// Subscribes to the logs of the transaction with the given hash. An event is emitted whenever
+    /// a block containing the transaction is applied or reverted, e.g. during a rebranch.
+    #[stream]
+    async fn subscribe_for_logs_by_transaction(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<BoxStream<'static, RPCData<BlockLog, BlockchainState>>, Self::Error>;
 }
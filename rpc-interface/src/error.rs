@@ -1,5 +1,9 @@
 use thiserror::Error;
 
+/// JSON-RPC error code with which the node responds if its mempool rejects a transaction. It lies
+/// in the range reserved for implementation-defined server errors.
+pub const MEMPOOL_REJECTED_ERROR_CODE: i64 = -32001;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Invalid combination of transaction parameters")]
@@ -1,8 +1,9 @@
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use nimiq_hash::Blake2bHash;
 use nimiq_transaction::Transaction;
 
-use crate::types::{HashOrTx, MempoolInfo, RPCResult};
+use crate::types::{HashOrTx, MempoolInfo, RPCData, RPCResult};
 
 #[nimiq_jsonrpc_derive::proxy(name = "MempoolProxy", rename_all = "camelCase")]
 #[async_trait]
@@ -36,4 +37,19 @@ pub trait MempoolInterface {
         &mut self,
         hash: Blake2bHash,
     ) -> RPCResult<Transaction, (), Self::Error>;
+
+    /// Tries to obtain the given transaction (using its hash) from the mempool. Unlike
+    /// `get_transaction_from_mempool`, returns `None` instead of an error if the transaction is not
+    /// in the mempool.
+    async fn find_transaction_in_mempool(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> RPCResult<Option<Transaction>, (), Self::Error>;
+
+    /// Subscribes to transactions added to the mempool (retrieves either the hashes or the full transactions).
+    #[stream]
+    async fn subscribe_for_mempool_transactions(
+        &mut self,
+        include_transactions: bool,
+    ) -> Result<BoxStream<'static, RPCData<HashOrTx, ()>>, Self::Error>;
 }
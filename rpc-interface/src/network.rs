@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::types::RPCResult;
+use crate::types::{PeerContact, RPCResult};
 
 #[nimiq_jsonrpc_derive::proxy(name = "NetworkProxy", rename_all = "camelCase")]
 #[async_trait]
@@ -15,4 +15,20 @@ pub trait NetworkInterface {
 
     /// Returns a list with the IDs of all our peers.
     async fn get_peer_list(&mut self) -> RPCResult<Vec<String>, (), Self::Error>;
+
+    /// Returns the addresses our local peer is currently listening on.
+    async fn get_listen_addresses(&mut self) -> RPCResult<Vec<String>, (), Self::Error>;
+
+    /// Returns a list with the IDs of all peers we know a contact for but are not connected to.
+    async fn get_unconnected_peer_list(&mut self) -> RPCResult<Vec<String>, (), Self::Error>;
+
+    /// Returns the median offset in milliseconds of our peers' clocks to the local clock of the
+    /// node. A positive offset means that the peers' clocks are ahead.
+    async fn get_clock_offset(&mut self) -> RPCResult<i64, (), Self::Error>;
+
+    /// Returns the contacts of all known peers that provide the given services, e.g. `HISTORY`.
+    async fn get_peer_contacts(
+        &mut self,
+        required_services: Vec<String>,
+    ) -> RPCResult<Vec<PeerContact>, (), Self::Error>;
 }
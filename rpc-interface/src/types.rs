@@ -554,6 +554,17 @@ impl ExecutedTransaction {
             execution_result: true,
         }
     }
+
+    /// The transaction that was executed.
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Whether the transaction was executed successfully.
+    pub fn succeeded(&self) -> bool {
+        self.execution_result
+    }
+
     pub fn try_from_historic_transaction(
         tx: HistoricTransaction,
         cur_block_height: Option<u32>,
@@ -1356,3 +1367,16 @@ impl MempoolInfo {
         info
     }
 }
+
+/// A contact of a peer known to the node.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerContact {
+    pub peer_id: String,
+    /// The addresses the peer advertises.
+    pub addresses: Vec<String>,
+    /// The names of the services the peer provides, e.g. `HISTORY`.
+    pub services: Vec<String>,
+    /// When the contact was created in seconds since the unix epoch. `None` for seeds.
+    pub timestamp: Option<u64>,
+}
@@ -15,6 +15,22 @@ pub trait WalletInterface {
         passphrase: Option<String>,
     ) -> RPCResult<Address, (), Self::Error>;
 
+    /// Exports the private key of an account in hexadecimal format, as accepted by `import_raw_key`. The account
+    /// must either be unlocked or the passphrase must be given.
+    async fn export_raw_key(
+        &mut self,
+        address: Address,
+        passphrase: Option<String>,
+    ) -> RPCResult<String, (), Self::Error>;
+
+    /// Exports the private key of an account encrypted with the passphrase, in hexadecimal format. The account must
+    /// be unlocked.
+    async fn export_encrypted_key(
+        &mut self,
+        address: Address,
+        passphrase: Option<String>,
+    ) -> RPCResult<String, (), Self::Error>;
+
     /// Returns if an account has been imported.
     // `nimiq_jsonrpc_derive::proxy` requires the receiver type to be a mutable reference.
     #[allow(clippy::wrong_self_convention)]
@@ -32,7 +48,8 @@ pub trait WalletInterface {
         passphrase: Option<String>,
     ) -> RPCResult<ReturnAccount, (), Self::Error>;
 
-    /// Unlocks the account.
+    /// Unlocks the account. If a non-zero `duration` in seconds is given, the account is locked again once it has
+    /// passed, otherwise it stays unlocked until it is locked explicitly.
     async fn unlock_account(
         &mut self,
         address: Address,
@@ -53,6 +70,10 @@ pub trait WalletInterface {
         is_hex: bool,
     ) -> RPCResult<ReturnSignature, (), Self::Error>;
 
+    /// Signs the hex encoded transaction with the unlocked account of its sender and returns the signed transaction in
+    /// hexadecimal format. Any recipient proof contained in the transaction data is left untouched.
+    async fn sign_transaction(&mut self, raw_tx: String) -> RPCResult<String, (), Self::Error>;
+
     /// Verifies the signature based on the provided public key and message.
     async fn verify_signature(
         &mut self,
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use futures::{future, stream::BoxStream, StreamExt};
 use nimiq_account::{BlockLog as BBlockLog, TransactionLog};
 use nimiq_blockchain::interface::{HistoryIndexInterface, HistoryInterface};
-use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainEvent};
+use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError, BlockchainEvent};
 use nimiq_blockchain_proxy::{BlockchainProxy, BlockchainReadProxy};
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
@@ -155,14 +155,28 @@ impl BlockchainInterface for BlockchainDispatcher {
         &mut self,
         hash: Blake2bHash,
     ) -> RPCResult<ExecutedTransaction, (), Self::Error> {
+        Ok(self
+            .find_transaction_by_hash(hash.clone())
+            .await?
+            .data
+            .ok_or(Error::TransactionNotFound(hash))?
+            .into())
+    }
+
+    async fn find_transaction_by_hash(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> RPCResult<Option<ExecutedTransaction>, (), Self::Error> {
         if let BlockchainReadProxy::Full(blockchain) = self.blockchain.read() {
             // Get all the historic transactions that correspond to this hash.
-            let hist_tx = blockchain
+            let Some(hist_tx) = blockchain
                 .history_store
                 .history_index()
                 .ok_or(Error::RequiresHistoryIndex)?
                 .get_hist_tx_by_hash(&hash, None)
-                .ok_or_else(|| Error::TransactionNotFound(hash.clone()))?;
+            else {
+                return Ok(None.into());
+            };
 
             // Convert the historic transaction into a regular transaction. This will also convert
             // reward inherents.
@@ -170,7 +184,6 @@ impl BlockchainInterface for BlockchainDispatcher {
                 hist_tx,
                 Some(blockchain.block_number()),
             )
-            .ok_or_else(|| Error::TransactionNotFound(hash.clone()))?
             .into())
         } else {
             Err(Error::NotSupportedForLightBlockchain)
@@ -381,6 +394,37 @@ impl BlockchainInterface for BlockchainDispatcher {
         }
     }
 
+    async fn get_account_by_address_at(
+        &mut self,
+        address: Address,
+        block_number: u32,
+    ) -> RPCResult<Account, BlockchainState, Self::Error> {
+        let blockchain_proxy = self.blockchain.read();
+        if let BlockchainReadProxy::Full(ref blockchain) = blockchain_proxy {
+            let account = match blockchain.get_account_at(&address, block_number) {
+                Ok(account) => account.ok_or(Error::NoConsensus)?,
+                Err(BlockchainError::AccountsStateNotRetained(_)) => {
+                    return Err(Error::AccountStateNotRetained(block_number))
+                }
+                Err(BlockchainError::BlockNotFound) => {
+                    return Err(Error::BlockNotFound(block_number))
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let block_hash = blockchain
+                .get_block_at(block_number, false, None)
+                .map_err(|_| Error::BlockNotFound(block_number))?
+                .hash();
+            Ok(Account::from_account_with_state(
+                address,
+                account,
+                BlockchainState::new(block_number, block_hash),
+            ))
+        } else {
+            Err(Error::NotSupportedForLightBlockchain)
+        }
+    }
+
     async fn get_accounts(&mut self) -> RPCResult<Vec<Account>, BlockchainState, Self::Error> {
         let blockchain_proxy = self.blockchain.read();
         if let BlockchainReadProxy::Full(ref blockchain) = blockchain_proxy {
@@ -481,6 +525,21 @@ impl BlockchainInterface for BlockchainDispatcher {
         get_validator_by_address(&self.blockchain.read(), &address)
     }
 
+    async fn find_validator_by_address(
+        &mut self,
+        address: Address,
+    ) -> RPCResult<Option<Validator>, BlockchainState, Self::Error> {
+        let blockchain_proxy = self.blockchain.read();
+
+        match get_validator_by_address(&blockchain_proxy, &address) {
+            Ok(validator) => Ok(RPCData::new(Some(validator.data), validator.metadata)),
+            Err(Error::ValidatorNotFound(_)) => {
+                Ok(RPCData::with_blockchain(None, &blockchain_proxy))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     async fn get_validators(&mut self) -> RPCResult<Vec<Validator>, BlockchainState, Self::Error> {
         let blockchain_proxy = self.blockchain.read();
 
@@ -741,4 +800,73 @@ impl BlockchainInterface for BlockchainDispatcher {
             Err(Error::NotSupportedForLightBlockchain)
         }
     }
+
+    #[stream]
+    async fn subscribe_for_logs_by_transaction(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<BoxStream<'static, RPCData<BlockLog, BlockchainState>>, Self::Error> {
+        if let BlockchainReadProxy::Full(blockchain) = self.blockchain.read() {
+            let stream = BroadcastStream::new(blockchain.log_notifier.subscribe());
+
+            Ok(stream
+                .filter_map(move |event| {
+                    let result = match event {
+                        Ok(BBlockLog::AppliedBlock {
+                            block_hash,
+                            block_number,
+                            timestamp,
+                            tx_logs,
+                            ..
+                        }) => {
+                            let tx_logs: Vec<TransactionLog> = tx_logs
+                                .into_iter()
+                                .filter(|tx_log| tx_log.tx_hash == hash)
+                                .collect();
+                            (!tx_logs.is_empty()).then(|| {
+                                RPCData::new(
+                                    BlockLog::AppliedBlock {
+                                        inherent_logs: vec![],
+                                        timestamp,
+                                        tx_logs,
+                                    },
+                                    BlockchainState {
+                                        block_number,
+                                        block_hash,
+                                    },
+                                )
+                            })
+                        }
+                        Ok(BBlockLog::RevertedBlock {
+                            block_hash,
+                            block_number,
+                            tx_logs,
+                            ..
+                        }) => {
+                            let tx_logs: Vec<TransactionLog> = tx_logs
+                                .into_iter()
+                                .filter(|tx_log| tx_log.tx_hash == hash)
+                                .collect();
+                            (!tx_logs.is_empty()).then(|| {
+                                RPCData::new(
+                                    BlockLog::RevertedBlock {
+                                        inherent_logs: vec![],
+                                        tx_logs,
+                                    },
+                                    BlockchainState {
+                                        block_number,
+                                        block_hash,
+                                    },
+                                )
+                            })
+                        }
+                        Err(_) => None,
+                    };
+                    future::ready(result)
+                })
+                .boxed())
+        } else {
+            Err(Error::NotSupportedForLightBlockchain)
+        }
+    }
 }
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt};
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_mempool::{mempool::Mempool, mempool_transactions::TxPriority};
 use nimiq_rpc_interface::{
     mempool::MempoolInterface,
-    types::{HashOrTx, MempoolInfo, RPCResult},
+    types::{HashOrTx, MempoolInfo, RPCData, RPCResult},
 };
 use nimiq_serde::Deserialize;
 use nimiq_transaction::Transaction;
@@ -98,4 +99,29 @@ impl MempoolInterface for MempoolDispatcher {
             return Err(Error::TransactionNotFound(hash));
         }
     }
+
+    async fn find_transaction_in_mempool(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> RPCResult<Option<Transaction>, (), Self::Error> {
+        Ok(self.mempool.get_transaction_by_hash(&hash).into())
+    }
+
+    #[stream]
+    async fn subscribe_for_mempool_transactions(
+        &mut self,
+        include_transactions: bool,
+    ) -> Result<BoxStream<'static, RPCData<HashOrTx, ()>>, Self::Error> {
+        Ok(self
+            .mempool
+            .subscribe_transactions()
+            .map(move |tx| {
+                if include_transactions {
+                    HashOrTx::from(tx).into()
+                } else {
+                    HashOrTx::from(tx.hash::<Blake2bHash>()).into()
+                }
+            })
+            .boxed())
+    }
 }
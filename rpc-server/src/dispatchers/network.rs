@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use nimiq_network_interface::network::Network as InterfaceNetwork;
+use nimiq_network_interface::{network::Network as InterfaceNetwork, peer_info::Services};
 use nimiq_network_libp2p::Network;
-use nimiq_rpc_interface::{network::NetworkInterface, types::RPCResult};
+use nimiq_rpc_interface::{
+    network::NetworkInterface,
+    types::{PeerContact, RPCResult},
+};
 
 use crate::error::Error;
 
@@ -39,4 +42,62 @@ impl NetworkInterface for NetworkDispatcher {
             .collect::<Vec<_>>()
             .into())
     }
+
+    async fn get_listen_addresses(&mut self) -> RPCResult<Vec<String>, (), Self::Error> {
+        Ok(self
+            .network
+            .listen_addresses()
+            .await?
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>()
+            .into())
+    }
+
+    async fn get_unconnected_peer_list(&mut self) -> RPCResult<Vec<String>, (), Self::Error> {
+        Ok(self
+            .network
+            .unconnected_contacts()
+            .await?
+            .into_iter()
+            .map(|peer_id| peer_id.to_string())
+            .collect::<Vec<_>>()
+            .into())
+    }
+
+    async fn get_clock_offset(&mut self) -> RPCResult<i64, (), Self::Error> {
+        Ok(self.network.clock_offset().await?.into())
+    }
+
+    async fn get_peer_contacts(
+        &mut self,
+        required_services: Vec<String>,
+    ) -> RPCResult<Vec<PeerContact>, (), Self::Error> {
+        let mut services = Services::empty();
+        for name in required_services {
+            services |= Services::from_name(&name.to_uppercase().replace('-', "_"))
+                .ok_or_else(|| Error::InvalidArgument(format!("Unknown service: {name}")))?;
+        }
+
+        Ok(self
+            .network
+            .peer_contacts(services)
+            .into_iter()
+            .map(|contact| PeerContact {
+                peer_id: contact.peer_id().to_string(),
+                addresses: contact
+                    .addresses
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect(),
+                services: contact
+                    .services
+                    .iter_names()
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+                timestamp: contact.timestamp,
+            })
+            .collect::<Vec<_>>()
+            .into())
+    }
 }
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use nimiq_database::traits::WriteTransaction;
@@ -7,7 +7,8 @@ use nimiq_rpc_interface::{
     types::{RPCResult, ReturnAccount, ReturnSignature},
     wallet::WalletInterface,
 };
-use nimiq_serde::Deserialize;
+use nimiq_serde::{Deserialize, Serialize};
+use nimiq_transaction::Transaction;
 use nimiq_utils::otp::Locked;
 use nimiq_wallet::{WalletAccount, WalletStore};
 use parking_lot::RwLock;
@@ -63,6 +64,44 @@ impl WalletInterface for WalletDispatcher {
         Ok(address.into())
     }
 
+    async fn export_raw_key(
+        &mut self,
+        address: Address,
+        passphrase: Option<String>,
+    ) -> RPCResult<String, (), Self::Error> {
+        if let Some(wallet) = self.unlocked_wallets.read().get(&address) {
+            return Ok(hex::encode(wallet.key_pair.private.serialize_to_vec()).into());
+        }
+
+        let passphrase = passphrase.unwrap_or_default();
+        let account = self
+            .wallet_store
+            .get(&address, None)
+            .ok_or(Error::AccountNotFound(address))?
+            .unlock(passphrase.as_bytes())
+            .map_err(|_locked| Error::WrongPassphrase)?;
+
+        Ok(hex::encode(account.key_pair.private.serialize_to_vec()).into())
+    }
+
+    async fn export_encrypted_key(
+        &mut self,
+        address: Address,
+        passphrase: Option<String>,
+    ) -> RPCResult<String, (), Self::Error> {
+        let account = self
+            .unlocked_wallets
+            .read()
+            .get(&address)
+            .cloned()
+            .ok_or(Error::UnlockedWalletNotFound(address))?;
+
+        let passphrase = passphrase.unwrap_or_default();
+        let locked_account = Locked::with_defaults(account, passphrase.as_bytes())?;
+
+        Ok(hex::encode(locked_account.serialize_to_vec()).into())
+    }
+
     async fn is_account_imported(&mut self, address: Address) -> RPCResult<bool, (), Self::Error> {
         let is_imported = self.wallet_store.get(&address, None).is_some();
 
@@ -99,12 +138,11 @@ impl WalletInterface for WalletDispatcher {
         .into())
     }
 
-    // # TODO The duration parameter is ignored.
     async fn unlock_account(
         &mut self,
         address: Address,
         passphrase: Option<String>,
-        _duration: Option<u64>,
+        duration: Option<u64>,
     ) -> RPCResult<bool, (), Self::Error> {
         let passphrase = passphrase.unwrap_or_default();
         let account = self
@@ -116,7 +154,12 @@ impl WalletInterface for WalletDispatcher {
             .unlock(passphrase.as_bytes())
             .map_err(|_locked| Error::WrongPassphrase)?;
 
-        self.unlocked_wallets.write().insert(unlocked_account);
+        self.unlocked_wallets.write().insert(
+            unlocked_account,
+            duration
+                .filter(|&duration| duration > 0)
+                .map(Duration::from_secs),
+        );
 
         Ok(true.into())
     }
@@ -166,6 +209,18 @@ impl WalletInterface for WalletDispatcher {
         .into())
     }
 
+    async fn sign_transaction(&mut self, raw_tx: String) -> RPCResult<String, (), Self::Error> {
+        let mut tx = Transaction::deserialize_from_vec(&hex::decode(&raw_tx)?)?;
+
+        let unlocked_wallets = self.unlocked_wallets.read();
+        let wallet = unlocked_wallets
+            .get(&tx.sender)
+            .ok_or_else(|| Error::UnlockedWalletNotFound(tx.sender.clone()))?;
+        wallet.sign_transaction(&mut tx);
+
+        Ok(hex::encode(tx.serialize_to_vec()).into())
+    }
+
     async fn verify_signature(
         &mut self,
         message: String,
@@ -2,6 +2,7 @@ use nimiq_hash::Blake2bHash;
 use nimiq_jsonrpc_core::RpcError;
 use nimiq_keys::Address;
 use nimiq_mempool::verify::VerifyErr;
+use nimiq_rpc_interface::error::MEMPOOL_REJECTED_ERROR_CODE;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -87,12 +88,26 @@ pub enum Error {
     #[error("No consensus")]
     NoConsensus,
 
+    #[error("Account state at block {0} is not retained")]
+    AccountStateNotRetained(u32),
+
+    #[error("{0}")]
+    Blockchain(#[from] nimiq_blockchain_interface::BlockchainError),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
 impl From<Error> for RpcError {
     fn from(e: Error) -> Self {
-        RpcError::internal_error(Some(serde_json::value::Value::String(e.to_string())))
+        match e {
+            // Clients tell mempool rejections apart from other errors by their error code.
+            Error::MempoolError(_) => RpcError {
+                code: MEMPOOL_REJECTED_ERROR_CODE,
+                message: Some("Mempool rejected transaction".to_owned()),
+                data: Some(serde_json::value::Value::String(e.to_string())),
+            },
+            _ => RpcError::internal_error(Some(serde_json::value::Value::String(e.to_string()))),
+        }
     }
 }
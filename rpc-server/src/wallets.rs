@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use nimiq_keys::Address;
 use nimiq_utils::otp::Unlocked;
@@ -7,22 +10,58 @@ use nimiq_wallet::WalletAccount;
 #[derive(Default)]
 pub struct UnlockedWallets {
     pub unlocked_wallets: HashMap<Address, Unlocked<WalletAccount>>,
+    /// Points in time after which the respective wallets are locked again.
+    expiries: HashMap<Address, Instant>,
 }
 
 impl UnlockedWallets {
-    pub fn insert(&mut self, wallet: Unlocked<WalletAccount>) {
+    /// Adds an unlocked wallet. If a `duration` is given, the wallet is locked again once it has
+    /// passed, otherwise it stays unlocked until it is removed.
+    pub fn insert(&mut self, wallet: Unlocked<WalletAccount>, duration: Option<Duration>) {
         log::info!("Unlocking {:?}", &wallet.address);
+        self.remove_expired();
+        match duration.and_then(|duration| Instant::now().checked_add(duration)) {
+            Some(expiry) => self.expiries.insert(wallet.address.clone(), expiry),
+            None => self.expiries.remove(&wallet.address),
+        };
         self.unlocked_wallets.insert(wallet.address.clone(), wallet);
     }
 
     pub fn get(&self, address: &Address) -> Option<&WalletAccount> {
         log::info!("Accessing {:?}", address);
+        if self.is_expired(address) {
+            return None;
+        }
         self.unlocked_wallets
             .get(address)
             .map(Unlocked::unlocked_data)
     }
 
     pub fn remove(&mut self, address: &Address) -> Option<Unlocked<WalletAccount>> {
+        self.remove_expired();
+        self.expiries.remove(address);
         self.unlocked_wallets.remove(address)
     }
+
+    fn is_expired(&self, address: &Address) -> bool {
+        self.expiries
+            .get(address)
+            .is_some_and(|expiry| *expiry <= Instant::now())
+    }
+
+    /// Drops the unlocked wallets whose unlock duration has passed.
+    fn remove_expired(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<Address> = self
+            .expiries
+            .iter()
+            .filter(|(_, expiry)| **expiry <= now)
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in expired {
+            log::info!("Locking {:?} after its unlock duration passed", &address);
+            self.expiries.remove(&address);
+            self.unlocked_wallets.remove(&address);
+        }
+    }
 }
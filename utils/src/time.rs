@@ -20,6 +20,10 @@ impl OffsetTime {
         }
     }
 
+    pub fn offset(&self) -> i64 {
+        self.offset.load(Ordering::Relaxed)
+    }
+
     pub fn set_offset(&self, new_offset: i64) {
         self.offset.store(new_offset, Ordering::Relaxed);
     }